@@ -0,0 +1,37 @@
+#!/usr/bin/env bash
+# Run the listing against the recorded outputs in tests/fixtures and compare it with
+# each fixture's expected.txt, so every backend is covered on any OS without root.
+#
+# A fixture directory holds `backend` (lsof, sockstat, fstat or proc), the recorded
+# `<backend>.txt`, `ps.txt` and `ip.txt`, and optionally a fake `proc/` tree (the
+# only input of the proc backend).
+#
+# Usage: scripts/fixture-tests.sh [--bless]
+set -euo pipefail
+
+bless=false
+[ "${1:-}" = "--bless" ] && bless=true
+
+cargo build --quiet
+bin=$PWD/target/debug/lsof-work-ports
+
+root=$(mktemp -d)
+trap 'rm -rf "$root"' EXIT
+
+failed=0
+for dir in tests/fixtures/*/; do
+  name=$(basename "$dir")
+  actual=$(LWP_ROOT=$root NO_COLOR=1 \
+    LWP_FIXTURES=$dir LWP_PROC_ROOT=${dir}proc LWP_BACKEND=$(cat "${dir}backend") \
+    "$bin" --all --output plain)
+  if $bless; then
+    printf '%s\n' "$actual" >"${dir}expected.txt"
+    echo "blessed $name"
+  elif diff -u "${dir}expected.txt" <(printf '%s\n' "$actual"); then
+    echo "ok $name"
+  else
+    echo "FAILED $name" >&2
+    failed=1
+  fi
+done
+exit $failed
@@ -0,0 +1,28 @@
+#!/usr/bin/env bash
+# Fail when the median wall time of a plain listing exceeds the startup budget,
+# so shell prompts and status bars can keep calling the tool on every refresh.
+#
+# Usage: scripts/startup-budget.sh [budget_ms] [runs]
+set -euo pipefail
+
+budget_ms=${1:-100}
+runs=${2:-20}
+
+cargo build --release --quiet
+bin=target/release/lsof-work-ports
+
+now_ms() { perl -MTime::HiRes=time -e 'printf "%d\n", time * 1000'; }
+
+times=()
+for _ in $(seq "$runs"); do
+  start=$(now_ms)
+  "$bin" --all --output plain >/dev/null || true
+  times+=($(( $(now_ms) - start )))
+done
+
+median=$(printf '%s\n' "${times[@]}" | sort -n | sed -n "$(( (runs + 1) / 2 ))p")
+echo "median ${median}ms over ${runs} runs (budget ${budget_ms}ms)"
+if [ "$median" -gt "$budget_ms" ]; then
+  echo "startup budget exceeded" >&2
+  exit 1
+fi
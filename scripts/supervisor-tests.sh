@@ -0,0 +1,46 @@
+#!/usr/bin/env bash
+# Start a held command under --root and check that the detached supervisor picks up
+# the entry from that root (it runs the command and records its PID), then check that
+# purge stops it.
+#
+# Usage: scripts/supervisor-tests.sh
+set -euo pipefail
+
+cargo build --quiet
+bin=$PWD/target/debug/lsof-work-ports
+
+root=$(mktemp -d)
+# A HOME without state, so a supervisor reading the default dirs finds nothing
+home=$(mktemp -d)
+cleanup() {
+  HOME=$home "$bin" --root "$root" hold --stop lwp-test >/dev/null 2>&1 || true
+  rm -rf "$root" "$home"
+}
+trap cleanup EXIT
+
+HOME=$home NO_COLOR=1 "$bin" --root "$root" hold --name lwp-test -- sleep 30 >/dev/null
+
+registry=$root/state/supervised.json
+started=
+for _ in $(seq 50); do
+  if grep -q '"child_pid": [0-9]' "$registry" 2>/dev/null; then
+    started=1
+    break
+  fi
+  sleep 0.1
+done
+if [ -z "$started" ]; then
+  echo "FAILED hold under --root: the supervisor never started the command" >&2
+  cat "$registry" >&2 || true
+  exit 1
+fi
+echo "ok hold under --root"
+
+# purge stops the supervisor before deleting the state it writes to
+supervisor=$(grep -o '"pid": [0-9]*' "$registry" | grep -o '[0-9]*$')
+HOME=$home NO_COLOR=1 "$bin" --root "$root" purge --yes >/dev/null
+if kill -0 "$supervisor" 2>/dev/null || [ -e "$root/state" ]; then
+  echo "FAILED purge: supervisor $supervisor still running or state left behind" >&2
+  exit 1
+fi
+echo "ok purge stops held commands"
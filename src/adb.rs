@@ -0,0 +1,117 @@
+//! `--adb <serial>`: ports bound inside an Android device or emulator, plus the
+//! `adb forward`/`adb reverse` mappings between it and this machine
+
+use crate::theme::Themed;
+use anyhow::{Context, Result};
+use colored::Colorize;
+use std::process::Command;
+
+fn adb(serial: &str, args: &[&str]) -> Result<String> {
+    let output = Command::new("adb")
+        .args(["-s", serial])
+        .args(args)
+        .output()
+        .context("Failed to run adb (is the Android SDK platform-tools on PATH?)")?;
+    anyhow::ensure!(
+        output.status.success(),
+        "adb {} failed: {}",
+        args.join(" "),
+        String::from_utf8_lossy(&output.stderr).trim()
+    );
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Listening TCP ports on the device, from `ss -tln` or (older images) `netstat -tln`
+fn device_ports(serial: &str) -> Result<Vec<(String, u16)>> {
+    let output = match adb(serial, &["shell", "ss", "-tln"]) {
+        Ok(output) if output.lines().any(|l| l.starts_with("LISTEN")) => output,
+        _ => adb(serial, &["shell", "netstat", "-tln"])?,
+    };
+    // Both put the local address in the fourth column
+    let mut ports: Vec<(String, u16)> = output
+        .lines()
+        .filter(|line| line.contains("LISTEN"))
+        .filter_map(|line| line.split_whitespace().nth(3))
+        .filter_map(crate::collector::split_address)
+        .collect();
+    ports.sort_by_key(|(_, port)| *port);
+    ports.dedup();
+    Ok(ports)
+}
+
+/// `<serial> tcp:<from> tcp:<to>` lines of `adb forward --list`/`adb reverse --list`
+fn parse_mappings(output: &str) -> Vec<(String, String, String)> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let parts: Vec<_> = line.split_whitespace().collect();
+            Some((
+                parts.first()?.to_string(),
+                parts.get(1)?.to_string(),
+                parts.get(2)?.to_string(),
+            ))
+        })
+        .collect()
+}
+
+/// Print the device's listeners and the port mappings in the listing
+pub fn print_section(serial: &str, port_infos: &[crate::PortInfo]) -> Result<()> {
+    let arrow = crate::output::glyphs().arrow;
+    let local_process = |spec: &str| {
+        let port: u16 = spec.strip_prefix("tcp:")?.parse().ok()?;
+        port_infos
+            .iter()
+            .find(|i| i.port == port)
+            .map(|i| format!("{} {}", i.process, crate::format_pid(&i.pid)))
+    };
+
+    println!();
+    println!("{}", format!("android {}", serial).heading().bold());
+    let ports = device_ports(serial)?;
+    if ports.is_empty() {
+        println!("  {}", "No listening ports on the device".muted());
+    }
+    for (address, port) in &ports {
+        println!(
+            "  {} {}",
+            format!(":{:<5}", port).accent().bold(),
+            address.muted()
+        );
+    }
+
+    // Forwards are listed for every device; keep this one's
+    let forwards: Vec<_> = parse_mappings(&adb(serial, &["forward", "--list"])?)
+        .into_iter()
+        .filter(|(s, _, _)| s == serial)
+        .collect();
+    let reverses = parse_mappings(&adb(serial, &["reverse", "--list"])?);
+    if forwards.is_empty() && reverses.is_empty() {
+        return Ok(());
+    }
+    println!();
+    println!("{}", "adb forwards".heading().bold());
+    for (_, local, device) in &forwards {
+        let holder = local_process(local).unwrap_or_default();
+        println!(
+            "  {} {} device {}  {}",
+            local.accent().bold(),
+            arrow,
+            device,
+            format!("forward {}", holder).trim_end().muted()
+        );
+    }
+    for (_, device, local) in &reverses {
+        let holder = local_process(local)
+            .map(|p| p.success().to_string())
+            .unwrap_or_else(|| "nothing listening locally".warning().to_string());
+        println!(
+            "  device {} {} {}  {} {}",
+            device,
+            arrow,
+            local.accent().bold(),
+            "reverse".muted(),
+            holder
+        );
+    }
+    Ok(())
+}
@@ -0,0 +1,88 @@
+//! Advertising a port over mDNS/Bonjour so LAN devices can discover it
+//!
+//! Uses the platform's registration tool (`dns-sd` on macOS, `avahi-publish-service`
+//! on Linux) and keeps the registration alive while the command runs.
+
+use crate::theme::Themed;
+use anyhow::{Context, Result};
+use std::process::Command;
+
+/// Register `name` for `port` until interrupted
+pub fn run(port: u16, name: &str, service_type: &str) -> Result<()> {
+    let listeners = crate::tcp_listeners(port)?;
+    if listeners.is_empty() {
+        println!(
+            "{}",
+            format!(
+                "{} Nothing is listening on port {} yet",
+                crate::output::glyphs().warn,
+                port
+            )
+            .warning()
+        );
+    } else if !listeners
+        .iter()
+        .any(|i| i.exposure == crate::interfaces::Exposure::Lan)
+    {
+        println!(
+            "{}",
+            format!(
+                "{} Port {} isn't reachable from the LAN; devices won't be able to connect",
+                crate::output::glyphs().warn,
+                port
+            )
+            .warning()
+        );
+    }
+
+    let host = format!("{}.local", name);
+    let ip = crate::interfaces::list()
+        .into_iter()
+        .map(|i| i.addr)
+        .find(|ip| ip.is_ipv4() && !ip.is_loopback())
+        .context("No LAN IPv4 address to advertise")?
+        .to_string();
+    let port_str = port.to_string();
+
+    // Proxy registrations publish both the service and a host record for <name>.local
+    let mut address_record = None;
+    let mut command = if cfg!(target_os = "macos") {
+        let mut c = Command::new("dns-sd");
+        c.args(["-P", name, service_type, "local", &port_str, &host, &ip]);
+        c
+    } else {
+        address_record = Some(
+            Command::new("avahi-publish-address")
+                .args(["-R", &host, &ip])
+                .spawn()
+                .context("Failed to run avahi-publish-address (is avahi installed?)")?,
+        );
+        let mut c = Command::new("avahi-publish-service");
+        c.args(["-H", &host, name, service_type, &port_str]);
+        c
+    };
+
+    println!(
+        "Advertising {} ({}) as http://{}:{} {} press Ctrl-C to stop",
+        name.success(),
+        service_type,
+        host,
+        port,
+        crate::output::glyphs().dash
+    );
+
+    // The registration lives as long as the child process
+    let status = command
+        .status()
+        .context("Failed to run dns-sd/avahi-publish-service (is mDNS available?)");
+    if let Some(mut child) = address_record {
+        let _ = child.kill();
+    }
+    let status = status?;
+    anyhow::ensure!(
+        status.success() || status.code().is_none(),
+        "mDNS registration exited with {}",
+        status
+    );
+    Ok(())
+}
@@ -0,0 +1,209 @@
+//! `[alerts]` rules evaluated by the daemon on every snapshot
+//!
+//! ```toml
+//! [[alerts.rules]]
+//! port = 5432
+//! down_for = 30          # seconds
+//!
+//! [[alerts.rules]]
+//! process = "node"
+//! max_processes = 10
+//!
+//! [[alerts.notifiers]]
+//! type = "desktop"
+//! ```
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::process::Command;
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AlertsConfig {
+    #[serde(default)]
+    pub rules: Vec<Rule>,
+    /// Where alerts are sent (default: stdout)
+    #[serde(default)]
+    pub notifiers: Vec<Notifier>,
+}
+
+impl AlertsConfig {
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty() && self.notifiers.is_empty()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Rule {
+    /// Label used in notifications (defaults to a description of the rule)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// Alert when nothing listens on this port...
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub port: Option<u16>,
+    /// ...for longer than this many seconds
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub down_for: Option<u64>,
+    /// Alert when more than `max_processes` processes with this name hold ports
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub process: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_processes: Option<usize>,
+}
+
+impl Rule {
+    fn label(&self) -> String {
+        if let Some(name) = &self.name {
+            return name.clone();
+        }
+        match (self.port, &self.process) {
+            (Some(port), _) => format!("port {} down", port),
+            (None, Some(process)) => format!("too many {} processes", process),
+            _ => "alert".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum Notifier {
+    /// Print to standard output
+    Stdout,
+    /// Desktop notification (osascript on macOS, notify-send elsewhere)
+    Desktop,
+    /// Run a shell command with the message in `$LWP_ALERT`
+    Command { command: String },
+}
+
+impl Notifier {
+    fn send(&self, message: &str) -> Result<()> {
+        match self {
+            Notifier::Stdout => println!("{}", message),
+            Notifier::Desktop => {
+                let status = if cfg!(target_os = "macos") {
+                    let script = format!(
+                        "display notification {:?} with title \"lsof-work-ports\"",
+                        message
+                    );
+                    Command::new("osascript").args(["-e", &script]).status()
+                } else {
+                    Command::new("notify-send")
+                        .args(["lsof-work-ports", message])
+                        .status()
+                };
+                status.context("Failed to send desktop notification")?;
+            }
+            Notifier::Command { command } => {
+                Command::new("sh")
+                    .args(["-c", command])
+                    .env("LWP_ALERT", message)
+                    .status()
+                    .with_context(|| format!("Failed to run alert command: {}", command))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Check that every rule is one of the supported shapes
+pub fn validate(config: &AlertsConfig) -> Result<()> {
+    for (i, rule) in config.rules.iter().enumerate() {
+        let port_rule = rule.port.is_some();
+        let process_rule = rule.process.is_some() && rule.max_processes.is_some();
+        anyhow::ensure!(
+            port_rule != process_rule,
+            "alerts.rules[{}]: set either `port` (with optional `down_for`) or `process` with `max_processes`",
+            i
+        );
+    }
+    Ok(())
+}
+
+/// Rule state across snapshots: when each condition started and whether it was reported
+#[derive(Default)]
+pub struct Evaluator {
+    since: HashMap<usize, Instant>,
+    firing: HashMap<usize, bool>,
+}
+
+impl Evaluator {
+    /// Evaluate every rule against a snapshot, returning messages for firing/resolved transitions
+    pub fn evaluate(
+        &mut self,
+        config: &AlertsConfig,
+        port_infos: &[crate::PortInfo],
+    ) -> Vec<String> {
+        let mut messages = Vec::new();
+        for (i, rule) in config.rules.iter().enumerate() {
+            let (violated, detail) = check(rule, port_infos);
+            let grace = Duration::from_secs(rule.down_for.unwrap_or(0));
+
+            let message = if violated {
+                let since = *self.since.entry(i).or_insert_with(Instant::now);
+                let firing = self.firing.entry(i).or_default();
+                if *firing || since.elapsed() < grace {
+                    continue;
+                }
+                *firing = true;
+                format!(
+                    "{} {}: {}",
+                    crate::output::glyphs().warn,
+                    rule.label(),
+                    detail
+                )
+            } else {
+                self.since.remove(&i);
+                if self.firing.remove(&i) != Some(true) {
+                    continue;
+                }
+                format!(
+                    "{} resolved: {}",
+                    crate::output::glyphs().check,
+                    rule.label()
+                )
+            };
+            messages.push(message);
+        }
+        messages
+    }
+}
+
+/// Whether a rule is currently violated, with a short explanation
+fn check(rule: &Rule, port_infos: &[crate::PortInfo]) -> (bool, String) {
+    if let Some(port) = rule.port {
+        let down = !port_infos.iter().any(|i| i.port == port);
+        return (down, format!("nothing is listening on port {}", port));
+    }
+
+    let process = rule.process.as_deref().unwrap_or_default();
+    let max = rule.max_processes.unwrap_or(usize::MAX);
+    let mut pids: Vec<&str> = port_infos
+        .iter()
+        .filter(|i| i.process.eq_ignore_ascii_case(process))
+        .map(|i| &*i.pid)
+        .collect();
+    pids.sort_unstable();
+    pids.dedup();
+    (
+        pids.len() > max,
+        format!(
+            "{} {} processes hold ports (max {})",
+            pids.len(),
+            process,
+            max
+        ),
+    )
+}
+
+/// Send a message to every configured notifier (stdout when there are none)
+pub fn notify(config: &AlertsConfig, message: &str) {
+    if config.notifiers.is_empty() {
+        println!("{}", message);
+    }
+    for notifier in &config.notifiers {
+        if let Err(e) = notifier.send(message) {
+            eprintln!("{:#}", e);
+        }
+    }
+}
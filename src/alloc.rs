@@ -0,0 +1,132 @@
+//! `alloc`: hand out several free ports at once, e.g. one per test shard
+//!
+//! A port is free when it can be bound on both the loopback and the wildcard address.
+//! The search starts at a per-call offset in the range, so harnesses that allocate
+//! concurrently rarely pick the same ports. `--reserve` leaves each port with a
+//! connection in TIME_WAIT, which keeps the kernel from handing it out as an
+//! ephemeral port for about a minute while servers that set SO_REUSEADDR (nearly all
+//! do) can still bind it. Handed-out ports are leased (see `leases`), so concurrent
+//! calls from other shells skip them until the lease runs out or its owner exits.
+
+use anyhow::Result;
+use clap::ValueEnum;
+use std::net::{Ipv4Addr, TcpListener, TcpStream};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Away from the usual dev server ports and below the Linux ephemeral range (32768+)
+pub const DEFAULT_RANGE: &str = "10000-19999";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum AllocFormat {
+    /// One port per line
+    Plain,
+    /// `PORT_0=10042` lines, for `eval` or an env file
+    Env,
+    /// A JSON array of ports
+    Json,
+}
+
+fn is_free(port: u16) -> bool {
+    TcpListener::bind((Ipv4Addr::LOCALHOST, port)).is_ok()
+        && TcpListener::bind((Ipv4Addr::UNSPECIFIED, port)).is_ok()
+}
+
+/// Connect to the port once and close, leaving the connection in TIME_WAIT
+fn reserve(port: u16) -> std::io::Result<()> {
+    let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, port))?;
+    let client = TcpStream::connect(listener.local_addr()?)?;
+    let (server, _) = listener.accept()?;
+    // The side that closes first keeps the TIME_WAIT entry
+    drop(client);
+    drop(server);
+    Ok(())
+}
+
+/// The shell or harness that ran us; it uses the ports after we exit
+pub fn caller_pid() -> u32 {
+    #[cfg(unix)]
+    return std::os::unix::process::parent_id();
+    #[cfg(not(unix))]
+    return std::process::id();
+}
+
+/// How long handed-out ports are leased unless `--lease` says otherwise
+pub const DEFAULT_LEASE_SECS: u64 = 300;
+
+pub struct Options {
+    pub count: usize,
+    pub format: AllocFormat,
+    pub reserve: bool,
+    pub lease_secs: u64,
+    /// Process holding the leases; defaults to the caller of `alloc`
+    pub owner: Option<u32>,
+}
+
+pub fn run(range: &str, options: &Options) -> Result<()> {
+    let count = options.count;
+    anyhow::ensure!(count > 0, "--count must be at least 1");
+    let mut candidates = crate::expand_ports(range)?;
+    candidates.sort_unstable();
+    candidates.dedup();
+    anyhow::ensure!(
+        !candidates.is_empty(),
+        "--range {} contains no ports",
+        range
+    );
+
+    let offset = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| {
+        d.subsec_nanos() as usize ^ std::process::id() as usize
+    }) % candidates.len();
+    candidates.rotate_left(offset);
+
+    let owner = options.owner.unwrap_or_else(caller_pid);
+    let expires = crate::history::now() + options.lease_secs;
+    let ports = crate::leases::update(|leases| {
+        let mut ports = Vec::with_capacity(count);
+        for port in candidates {
+            if ports.len() == count {
+                break;
+            }
+            if crate::well_known::service(port).is_some()
+                || leases.iter().any(|l| l.port == port)
+                || !is_free(port)
+            {
+                continue;
+            }
+            if options.reserve && reserve(port).is_err() {
+                continue;
+            }
+            ports.push(port);
+        }
+        anyhow::ensure!(
+            ports.len() == count,
+            "Only {} free port(s) in {}, {} requested",
+            ports.len(),
+            range,
+            count
+        );
+        if options.lease_secs > 0 {
+            leases.extend(ports.iter().map(|&port| crate::leases::Lease {
+                port,
+                owner_pid: owner,
+                expires,
+            }));
+        }
+        Ok(ports)
+    })?;
+
+    match options.format {
+        AllocFormat::Plain => {
+            for port in &ports {
+                println!("{}", port);
+            }
+        }
+        AllocFormat::Env => {
+            for (i, port) in ports.iter().enumerate() {
+                println!("PORT_{}={}", i, port);
+            }
+        }
+        AllocFormat::Json => println!("{}", serde_json::to_string(&ports)?),
+    }
+    Ok(())
+}
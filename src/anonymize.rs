@@ -0,0 +1,130 @@
+//! `--anonymize`: mask usernames, home directories and the hostname in listings
+//!
+//! Names are replaced by a short stable hash (`user-3f9a`, `host-81c2`), so the same
+//! user maps to the same mask everywhere and different users stay distinguishable,
+//! while ports, process names and the layout are untouched.
+
+use std::sync::OnceLock;
+
+/// Directories whose next path component is a user name
+const HOME_ROOTS: &[&str] = &["/home/", "/Users/", "C:\\Users\\", "/var/home/"];
+
+struct Masks {
+    users: Vec<String>,
+    hosts: Vec<String>,
+}
+
+static MASKS: OnceLock<Option<Masks>> = OnceLock::new();
+
+/// FNV-1a, the same across runs and versions
+pub fn stable_hash(text: &str) -> u64 {
+    text.bytes().fold(0xcbf29ce484222325u64, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x100000001b3)
+    })
+}
+
+fn short_hash(text: &str) -> String {
+    format!("{:04x}", stable_hash(text) & 0xffff)
+}
+
+fn user_mask(name: &str) -> String {
+    format!("user-{}", short_hash(name))
+}
+
+fn host_mask(name: &str) -> String {
+    format!("host-{}", short_hash(name))
+}
+
+fn hostname() -> Option<String> {
+    let output = std::process::Command::new("hostname").output().ok()?;
+    let name = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    (!name.is_empty()).then_some(name)
+}
+
+pub fn init(enabled: bool) {
+    let masks = enabled.then(|| {
+        let users = ["USER", "USERNAME", "LOGNAME"]
+            .iter()
+            .filter_map(|var| std::env::var(var).ok())
+            .filter(|name| !name.is_empty() && name != "root")
+            .collect();
+        // Both the FQDN and its first label show up in command lines
+        let mut hosts: Vec<String> = hostname().into_iter().collect();
+        if let Some(short) = hosts.first().and_then(|h| h.split_once('.')).map(|s| s.0) {
+            hosts.push(short.to_string());
+        }
+        Masks { users, hosts }
+    });
+    let _ = MASKS.set(masks);
+}
+
+/// Replace whole-word occurrences of `word` (not inside a longer name)
+fn replace_word(text: &str, word: &str, mask: &str) -> String {
+    let is_name_char = |c: char| c.is_alphanumeric() || c == '_' || c == '-';
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(i) = rest.find(word) {
+        let before = rest[..i].chars().next_back();
+        let after = rest[i + word.len()..].chars().next();
+        out.push_str(&rest[..i]);
+        if before.is_some_and(is_name_char) || after.is_some_and(is_name_char) {
+            out.push_str(word);
+        } else {
+            out.push_str(mask);
+        }
+        rest = &rest[i + word.len()..];
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Mask the user component of home directory paths, for any user
+fn mask_home_paths(text: &str) -> String {
+    let mut text = text.to_string();
+    for root in HOME_ROOTS {
+        let mut out = String::with_capacity(text.len());
+        let mut rest = text.as_str();
+        while let Some(i) = rest.find(root) {
+            let start = i + root.len();
+            out.push_str(&rest[..start]);
+            let len = rest[start..]
+                .find(|c: char| c == '/' || c == '\\' || c.is_whitespace() || c == ':')
+                .unwrap_or(rest.len() - start);
+            let name = &rest[start..start + len];
+            if name.is_empty() || name.starts_with("user-") {
+                out.push_str(name);
+            } else {
+                out.push_str(&user_mask(name));
+            }
+            rest = &rest[start + len..];
+        }
+        out.push_str(rest);
+        text = out;
+    }
+    text
+}
+
+/// `text` with personal names masked; unchanged unless `--anonymize` was given
+pub fn text(text: &str) -> String {
+    let Some(Some(masks)) = MASKS.get() else {
+        return text.to_string();
+    };
+    let mut text = mask_home_paths(text);
+    for host in &masks.hosts {
+        text = replace_word(&text, host, &host_mask(host));
+    }
+    for user in &masks.users {
+        text = replace_word(&text, user, &user_mask(user));
+    }
+    text
+}
+
+/// A user name as its mask; `root`, `nobody` and macOS `_` accounts are kept
+pub fn user(name: &str) -> String {
+    match MASKS.get() {
+        Some(Some(_)) if !matches!(name, "root" | "nobody") && !name.starts_with('_') => {
+            user_mask(name)
+        }
+        _ => name.to_string(),
+    }
+}
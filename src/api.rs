@@ -0,0 +1,589 @@
+//! `daemon --api`: a local HTTP API on a Unix socket, and the typed client behind `api`
+//!
+//! The socket is `daemon.sock` in the state directory, readable by the user only.
+//! Requests are plain HTTP/1.1, so `curl --unix-socket` works as well:
+//!
+//! - `GET /ports`: the listeners of the latest snapshot
+//! - `GET /events`: `listen`/`close`/`alert` events as JSON lines, as they happen
+//! - `GET /workspace?path=/abs/project`: the listeners of one project, for editor
+//!   extensions showing a ports panel: processes started from inside the directory or
+//!   running a file in it
+//! - `POST /kill?port=3000`: the processes that would be killed, with a `confirm`
+//!   token; `POST /kill?port=3000&confirm=<token>` kills them if they still hold the
+//!   port (add `force=1` for SIGKILL)
+//!
+//! Query values are percent-decoded. Errors are answered with a 4xx/5xx status and
+//! `{"error": "..."}`.
+
+use crate::events::Event;
+use crate::theme::Themed;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Write};
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// How long a kill confirmation token stays valid
+const CONFIRM_TTL: Duration = Duration::from_secs(60);
+
+/// How long the daemon waits for killed processes to release their port
+const KILL_TIMEOUT: Duration = Duration::from_secs(5);
+
+pub fn socket_path() -> Result<PathBuf> {
+    Ok(crate::paths::state_dir()?.join("daemon.sock"))
+}
+
+/// A listener as returned by `GET /ports`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Port {
+    pub port: u16,
+    pub process: String,
+    pub pid: String,
+    pub user: String,
+    pub command: String,
+    pub address: String,
+    pub family: String,
+    /// "TCP" or "UDP"
+    #[serde(default)]
+    pub protocol: String,
+    pub interface: String,
+    /// Name of the `[[ports]]` range the port is in
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub port_name: Option<String>,
+}
+
+impl From<&crate::PortInfo> for Port {
+    fn from(info: &crate::PortInfo) -> Self {
+        Self {
+            port: info.port,
+            process: info.process.to_string(),
+            pid: info.pid.to_string(),
+            user: info.user.clone(),
+            command: info.command.clone(),
+            address: info.address.clone(),
+            family: info.family.clone(),
+            protocol: info.protocol.name().to_string(),
+            interface: info.interface.clone(),
+            port_name: None,
+        }
+    }
+}
+
+/// A listener of a workspace, with why it belongs there
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkspacePort {
+    #[serde(flatten)]
+    pub port: Port,
+    /// Working directory of the process
+    pub cwd: Option<String>,
+    /// `cwd` when started from inside the workspace, `command` when running a file in it
+    pub reason: String,
+}
+
+/// What `GET /workspace` returns
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Workspace {
+    /// The workspace directory, with symlinks resolved
+    pub path: String,
+    pub ports: Vec<WorkspacePort>,
+}
+
+/// What `POST /kill?port=N` would kill, confirmed by sending `confirm` back
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KillPlan {
+    pub port: u16,
+    pub processes: Vec<Port>,
+    pub confirm: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct ErrorBody {
+    error: String,
+}
+
+struct PendingKill {
+    port: u16,
+    pids: Vec<Arc<str>>,
+    expires: Instant,
+}
+
+/// What the daemon loop shares with the connections
+#[derive(Default)]
+pub struct State {
+    ports: Mutex<Vec<Port>>,
+    subscribers: Mutex<Vec<mpsc::Sender<String>>>,
+    pending: Mutex<HashMap<String, PendingKill>>,
+}
+
+impl State {
+    /// Publish a new snapshot and the events it produced
+    pub fn update(&self, config: &crate::Config, port_infos: &[crate::PortInfo], events: &[Event]) {
+        *self.ports.lock().unwrap() = port_infos
+            .iter()
+            .map(|info| Port {
+                port_name: config.port_name(info.port).map(str::to_string),
+                ..Port::from(info)
+            })
+            .collect();
+        let lines: Vec<String> = events
+            .iter()
+            .filter_map(|e| serde_json::to_string(e).ok())
+            .collect();
+        // Subscribers that disconnected are dropped on the first failed send
+        self.subscribers.lock().unwrap().retain(|subscriber| {
+            lines
+                .iter()
+                .all(|line| subscriber.send(line.clone()).is_ok())
+        });
+    }
+}
+
+/// Bind the socket and answer requests on background threads
+pub fn serve(state: Arc<State>) -> Result<PathBuf> {
+    let path = socket_path()?;
+    std::fs::create_dir_all(path.parent().context("Invalid state dir")?)?;
+    if path.exists() {
+        anyhow::ensure!(
+            UnixStream::connect(&path).is_err(),
+            "Another daemon is serving {}",
+            path.display()
+        );
+        // Left behind by a daemon that didn't exit cleanly
+        std::fs::remove_file(&path)?;
+    }
+    let listener =
+        UnixListener::bind(&path).with_context(|| format!("Failed to bind {}", path.display()))?;
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o600))?;
+    }
+
+    std::thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            let state = state.clone();
+            std::thread::spawn(move || {
+                if let Err(e) = handle(stream, &state) {
+                    eprintln!("api: {:#}", e);
+                }
+            });
+        }
+    });
+    Ok(path)
+}
+
+struct Request {
+    method: String,
+    path: String,
+    query: HashMap<String, String>,
+}
+
+fn read_request(reader: &mut impl BufRead) -> Result<Request> {
+    let mut line = String::new();
+    reader.read_line(&mut line)?;
+    let mut parts = line.split_whitespace();
+    let (Some(method), Some(target)) = (parts.next(), parts.next()) else {
+        anyhow::bail!("Malformed request line");
+    };
+    // Headers are not used; requests carry everything in the query
+    loop {
+        let mut header = String::new();
+        if reader.read_line(&mut header)? == 0 || header.trim().is_empty() {
+            break;
+        }
+    }
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+    let query = query
+        .split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .map(|(key, value)| (percent_decode(key), percent_decode(value)))
+        .collect();
+    Ok(Request {
+        method: method.to_string(),
+        path: path.to_string(),
+        query,
+    })
+}
+
+/// `%2F` → `/`; anything that isn't a valid escape is kept as it is
+fn percent_decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = text
+            .get(i + 1..i + 3)
+            .filter(|hex| hex.bytes().all(|b| b.is_ascii_hexdigit()))
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match (bytes[i], escaped) {
+            (b'%', Some(byte)) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            (byte, _) => {
+                decoded.push(byte);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+fn percent_encode(text: &str) -> String {
+    text.bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' | b'/' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+fn status_text(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        400 => "Bad Request",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        409 => "Conflict",
+        _ => "Internal Server Error",
+    }
+}
+
+fn respond(stream: &mut UnixStream, status: u16, body: &impl Serialize) -> Result<()> {
+    let body = serde_json::to_string(body)?;
+    write!(
+        stream,
+        "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\n\
+         Content-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        status_text(status),
+        body.len(),
+        body
+    )?;
+    Ok(())
+}
+
+fn error(stream: &mut UnixStream, status: u16, message: impl Into<String>) -> Result<()> {
+    respond(
+        stream,
+        status,
+        &ErrorBody {
+            error: message.into(),
+        },
+    )
+}
+
+fn handle(mut stream: UnixStream, state: &State) -> Result<()> {
+    let request = read_request(&mut BufReader::new(stream.try_clone()?))?;
+    match (request.method.as_str(), request.path.as_str()) {
+        ("GET", "/ports") => {
+            let ports = state.ports.lock().unwrap().clone();
+            respond(&mut stream, 200, &ports)
+        }
+        ("GET", "/events") => stream_events(stream, state),
+        ("GET", "/workspace") => workspace(&mut stream, state, &request.query),
+        ("POST", "/kill") => kill(&mut stream, state, &request.query),
+        (_, "/ports" | "/events" | "/workspace" | "/kill") => {
+            error(&mut stream, 405, "Method not allowed")
+        }
+        _ => error(&mut stream, 404, format!("No endpoint {}", request.path)),
+    }
+}
+
+fn stream_events(mut stream: UnixStream, state: &State) -> Result<()> {
+    let (sender, receiver) = mpsc::channel();
+    state.subscribers.lock().unwrap().push(sender);
+    write!(
+        stream,
+        "HTTP/1.1 200 OK\r\nContent-Type: application/x-ndjson\r\nConnection: close\r\n\r\n"
+    )?;
+    stream.flush()?;
+    for line in receiver {
+        // The client hung up; the sender is dropped with the next event
+        if writeln!(stream, "{}", line).is_err() {
+            break;
+        }
+    }
+    Ok(())
+}
+
+/// `node /project/server.js`, `--config=/project/vite.config.ts`; the path may contain
+/// spaces, so it is looked for as text
+fn runs_file_in(command: &str, root: &Path) -> bool {
+    let prefix = format!("{}/", root.display().to_string().trim_end_matches('/'));
+    command.match_indices(&prefix).any(|(i, _)| {
+        command[..i]
+            .chars()
+            .next_back()
+            .is_none_or(|c| c.is_whitespace() || c == '=' || c == '"' || c == '\'')
+    })
+}
+
+/// Listeners started from inside `root`, or running a file inside it
+fn workspace_ports(ports: Vec<Port>, root: &Path) -> Vec<WorkspacePort> {
+    let mut pids: Vec<String> = ports.iter().map(|p| p.pid.clone()).collect();
+    pids.sort_unstable();
+    pids.dedup();
+    let cwds = crate::pool::map(&pids, |pid| crate::get_process_cwd(pid));
+    ports
+        .into_iter()
+        .filter_map(|port| {
+            let cwd = pids
+                .binary_search(&port.pid)
+                .ok()
+                .and_then(|i| cwds[i].clone());
+            let reason = if cwd
+                .as_deref()
+                .is_some_and(|cwd| Path::new(cwd).starts_with(root))
+            {
+                "cwd"
+            } else if runs_file_in(&port.command, root) {
+                "command"
+            } else {
+                return None;
+            };
+            Some(WorkspacePort {
+                port,
+                cwd,
+                reason: reason.to_string(),
+            })
+        })
+        .collect()
+}
+
+fn workspace(
+    stream: &mut UnixStream,
+    state: &State,
+    query: &HashMap<String, String>,
+) -> Result<()> {
+    let Some(path) = query.get("path").map(Path::new) else {
+        return error(stream, 400, "Missing path");
+    };
+    if !path.is_absolute() {
+        return error(stream, 400, "The path must be absolute");
+    }
+    // Processes report resolved directories (/private/tmp rather than /tmp on macOS)
+    let Ok(root) = path.canonicalize() else {
+        return error(
+            stream,
+            404,
+            format!("No such directory: {}", path.display()),
+        );
+    };
+    let ports = state.ports.lock().unwrap().clone();
+    respond(
+        stream,
+        200,
+        &Workspace {
+            path: root.display().to_string(),
+            ports: workspace_ports(ports, &root),
+        },
+    )
+}
+
+/// Unguessable enough for a socket only the user can open
+fn new_token() -> String {
+    use std::hash::{BuildHasher, Hasher};
+    let mut hasher = std::collections::hash_map::RandomState::new().build_hasher();
+    hasher.write_u128(
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos(),
+    );
+    format!("{:016x}", hasher.finish())
+}
+
+fn kill(stream: &mut UnixStream, state: &State, query: &HashMap<String, String>) -> Result<()> {
+    let Some(port) = query.get("port").and_then(|p| p.parse::<u16>().ok()) else {
+        return error(stream, 400, "Missing or invalid port");
+    };
+    let holders = crate::filter_port_infos(crate::get_port_info()?, Some(port), None, None);
+    if holders.is_empty() {
+        return error(
+            stream,
+            409,
+            format!("Nothing is listening on port {}", port),
+        );
+    }
+    let pids = crate::deduplicate_pids(&holders);
+
+    let mut pending = state.pending.lock().unwrap();
+    pending.retain(|_, p| p.expires > Instant::now());
+    let Some(token) = query.get("confirm") else {
+        let token = new_token();
+        pending.insert(
+            token.clone(),
+            PendingKill {
+                port,
+                pids,
+                expires: Instant::now() + CONFIRM_TTL,
+            },
+        );
+        let plan = KillPlan {
+            port,
+            processes: holders.iter().map(Port::from).collect(),
+            confirm: token,
+        };
+        return respond(stream, 200, &plan);
+    };
+
+    match pending.remove(token) {
+        Some(plan) if plan.port == port && plan.pids == pids => {}
+        Some(_) => {
+            return error(
+                stream,
+                409,
+                format!("The processes on port {} changed; ask again", port),
+            )
+        }
+        None => return error(stream, 409, "Unknown or expired confirmation; ask again"),
+    }
+    drop(pending);
+    let options = crate::kill::Options {
+        mode: crate::kill::KillMode::Pid,
+        force: query.get("force").is_some_and(|f| f == "1" || f == "true"),
+        all: false,
+        timeout: KILL_TIMEOUT,
+    };
+    match crate::kill::run(&crate::kill::Target::Port(port), &options) {
+        Ok(()) => respond(stream, 200, &serde_json::json!({ "released": port })),
+        Err(e) => error(stream, 409, format!("{:#}", e)),
+    }
+}
+
+/// Typed client for the daemon API
+pub struct Client {
+    path: PathBuf,
+}
+
+impl Client {
+    /// The API of the daemon started with `--api`
+    pub fn connect() -> Result<Self> {
+        let path = socket_path()?;
+        anyhow::ensure!(
+            path.exists(),
+            "No daemon API at {}; start one with `lsof-work-ports daemon --api`",
+            path.display()
+        );
+        Ok(Self { path })
+    }
+
+    /// Send a request and return the body reader once the status is a success
+    fn request(&self, method: &str, target: &str) -> Result<BufReader<UnixStream>> {
+        let mut stream = UnixStream::connect(&self.path)
+            .with_context(|| format!("Failed to connect to {}", self.path.display()))?;
+        write!(
+            stream,
+            "{} {} HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n",
+            method, target
+        )?;
+        let mut reader = BufReader::new(stream);
+        let mut status_line = String::new();
+        reader.read_line(&mut status_line)?;
+        let status: u16 = status_line
+            .split_whitespace()
+            .nth(1)
+            .and_then(|s| s.parse().ok())
+            .context("Malformed response from the daemon")?;
+        loop {
+            let mut header = String::new();
+            if reader.read_line(&mut header)? == 0 || header.trim().is_empty() {
+                break;
+            }
+        }
+        if status != 200 {
+            let body: ErrorBody = serde_json::from_reader(reader)
+                .with_context(|| format!("Daemon answered {}", status))?;
+            anyhow::bail!(body.error);
+        }
+        Ok(reader)
+    }
+
+    pub fn ports(&self) -> Result<Vec<Port>> {
+        Ok(serde_json::from_reader(self.request("GET", "/ports")?)?)
+    }
+
+    /// Events as they happen, until the daemon stops
+    pub fn events(&self) -> Result<impl Iterator<Item = Result<Event>>> {
+        let reader = self.request("GET", "/events")?;
+        Ok(reader.lines().map(|line| Ok(serde_json::from_str(&line?)?)))
+    }
+
+    /// Listeners belonging to the project at `path`
+    pub fn workspace(&self, path: &Path) -> Result<Workspace> {
+        let path = path.to_str().context("The workspace path is not UTF-8")?;
+        Ok(serde_json::from_reader(self.request(
+            "GET",
+            &format!("/workspace?path={}", percent_encode(path)),
+        )?)?)
+    }
+
+    /// What killing the holders of `port` would affect, to be confirmed with `kill`
+    pub fn kill_plan(&self, port: u16) -> Result<KillPlan> {
+        Ok(serde_json::from_reader(
+            self.request("POST", &format!("/kill?port={}", port))?,
+        )?)
+    }
+
+    /// Kill the processes of a plan; fails when they changed in the meantime
+    pub fn kill(&self, plan: &KillPlan, force: bool) -> Result<()> {
+        let force = if force { "&force=1" } else { "" };
+        self.request(
+            "POST",
+            &format!("/kill?port={}&confirm={}{}", plan.port, plan.confirm, force),
+        )?;
+        Ok(())
+    }
+}
+
+/// `api ports`
+pub fn print_ports() -> Result<()> {
+    let ports = Client::connect()?.ports()?;
+    println!("{}", serde_json::to_string_pretty(&ports)?);
+    Ok(())
+}
+
+/// `api workspace`: the listeners of a project directory (default: the current one)
+pub fn print_workspace(path: Option<&Path>) -> Result<()> {
+    let dir = std::env::current_dir()?;
+    let path = path.map_or_else(|| dir.clone(), |path| dir.join(path));
+    let workspace = Client::connect()?.workspace(&path)?;
+    println!("{}", serde_json::to_string_pretty(&workspace)?);
+    Ok(())
+}
+
+/// `api events`: one JSON line per event until interrupted
+pub fn print_events() -> Result<()> {
+    for event in Client::connect()?.events()? {
+        println!("{}", serde_json::to_string(&event?)?);
+    }
+    Ok(())
+}
+
+/// `api kill`: show what the daemon would kill and confirm before it does
+pub fn kill_port(port: u16, yes: bool, force: bool) -> Result<()> {
+    let client = Client::connect()?;
+    let plan = client.kill_plan(port)?;
+    println!("The daemon will signal:");
+    for process in &plan.processes {
+        println!(
+            "  {} {} {}",
+            process.process.success(),
+            crate::format_pid(&process.pid),
+            process.command.muted()
+        );
+    }
+    if !yes && !crate::confirm("Proceed?")? {
+        println!("Aborted");
+        return Ok(());
+    }
+    client.kill(&plan, force)?;
+    println!("{} Released port {}", crate::output::glyphs().check, port);
+    Ok(())
+}
@@ -0,0 +1,126 @@
+//! Crash reports and on-demand bug report bundles
+//!
+//! Reports are plain text files in the temp directory. Home directory paths and
+//! the user name are redacted so reports can be attached to public issues.
+
+use anyhow::{Context, Result};
+use std::path::PathBuf;
+use std::process::Command;
+
+const ISSUES_URL: &str = "https://github.com/elzup/lsof-work-ports/issues";
+
+/// Number of raw collector lines included in a report
+const COLLECTOR_SAMPLE_LINES: usize = 30;
+
+/// Install a panic hook that writes a crash report instead of a bare backtrace
+pub fn install_panic_hook() {
+    std::panic::set_hook(Box::new(|info| {
+        let backtrace = std::backtrace::Backtrace::force_capture();
+        let crash = format!("{}\n\nBacktrace:\n{}", info, backtrace);
+
+        eprintln!("lsof-work-ports crashed: {}", info);
+        // Detected when it happens, once --backend and --root are applied
+        let backend = crate::collector::Backend::detect().name();
+        match write_report(backend, Some(&crash)) {
+            Ok(path) => {
+                eprintln!("A crash report was written to {}", path.display());
+                eprintln!("Please attach it when filing an issue: {}", ISSUES_URL);
+            }
+            Err(e) => eprintln!("Failed to write crash report: {:#}", e),
+        }
+    }));
+}
+
+/// Generate a bug report on demand and print where it was written
+pub fn generate(backend: &str) -> Result<()> {
+    let path = write_report(backend, None)?;
+    println!(
+        "{} Bug report written to {}",
+        crate::output::glyphs().check,
+        path.display()
+    );
+    println!("Review it, then attach it to an issue: {}", ISSUES_URL);
+    Ok(())
+}
+
+fn write_report(backend: &str, crash: Option<&str>) -> Result<PathBuf> {
+    let timestamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default();
+    let path = std::env::temp_dir().join(format!(
+        "lsof-work-ports-report-{}-{}.txt",
+        timestamp,
+        std::process::id()
+    ));
+
+    let content = redact(&build_report(backend, crash));
+    std::fs::write(&path, content)
+        .with_context(|| format!("Failed to write {}", path.display()))?;
+    Ok(path)
+}
+
+fn build_report(backend: &str, crash: Option<&str>) -> String {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let mut report = String::new();
+
+    report.push_str("# lsof-work-ports bug report\n\n");
+    report.push_str(&format!("version: {}\n", env!("CARGO_PKG_VERSION")));
+    report.push_str(&format!(
+        "os: {} {} ({})\n",
+        std::env::consts::OS,
+        os_version(),
+        std::env::consts::ARCH
+    ));
+    report.push_str(&format!("backend: {}\n", backend));
+    report.push_str(&format!("args: {:?}\n", args));
+
+    if let Some(crash) = crash {
+        report.push_str("\n## Panic\n\n");
+        report.push_str(crash);
+        report.push('\n');
+    }
+
+    report.push_str(&format!(
+        "\n## Collector sample (first {} lines)\n\n",
+        COLLECTOR_SAMPLE_LINES
+    ));
+    report.push_str(&collector_sample());
+    report
+}
+
+fn os_version() -> String {
+    Command::new("uname")
+        .arg("-r")
+        .output()
+        .map(|o| String::from_utf8_lossy(&o.stdout).trim().to_string())
+        .unwrap_or_default()
+}
+
+fn collector_sample() -> String {
+    match crate::collector::Backend::detect().raw_output() {
+        Ok(output) => output
+            .lines()
+            .take(COLLECTOR_SAMPLE_LINES)
+            .map(|line| format!("{}\n", line))
+            .collect(),
+        Err(e) => format!("({:#})\n", e),
+    }
+}
+
+/// Replace the home directory and user name with placeholders
+fn redact(text: &str) -> String {
+    let mut text = text.to_string();
+    if let Ok(home) = std::env::var("HOME") {
+        if !home.is_empty() && home != "/" {
+            text = text.replace(&home, "~");
+        }
+    }
+    if let Ok(user) = std::env::var("USER") {
+        // Very short names would mangle unrelated words
+        if user.len() >= 3 {
+            text = text.replace(&user, "<user>");
+        }
+    }
+    text
+}
@@ -0,0 +1,117 @@
+//! Framework port fallback chains: 3000 is taken, so the next dev server starts on
+//! 3001, the next on 3002...
+//!
+//! A chain is a framework's preferred port followed by consecutive ports held by
+//! instances of the same process. Whatever holds the preferred port is the squatter
+//! to kill to get back to one instance on the expected port.
+
+use crate::theme::Themed;
+use colored::Colorize;
+
+/// Default ports of popular dev servers
+const PREFERRED_PORTS: &[u16] = &[
+    3000, // Next.js, Create React App, Remix, Rails
+    3333, // AdonisJS
+    4000, // Phoenix, Gatsby (serve)
+    4200, // Angular
+    4321, // Astro
+    5000, // Flask
+    5173, // Vite
+    6006, // Storybook
+    8000, // Django
+    8080, // webpack-dev-server, Vue CLI
+    8888, // Jupyter
+];
+
+pub struct Chain {
+    /// The preferred port and its holder
+    pub preferred: u16,
+    pub squatter: String,
+    pub squatter_pid: String,
+    /// Ports the later instances fell back to, with their pids
+    pub fallbacks: Vec<(u16, String)>,
+    pub process: String,
+}
+
+fn holder(infos: &[crate::GroupedPortInfo], port: u16) -> Option<&crate::GroupedPortInfo> {
+    infos.iter().find(|i| i.port == port)
+}
+
+/// Chains among the listed ports
+pub fn detect(infos: &[crate::GroupedPortInfo]) -> Vec<Chain> {
+    let first_process =
+        |info: &crate::GroupedPortInfo| info.processes.first().map(|p| p.to_string());
+    let first_pid = |info: &crate::GroupedPortInfo| {
+        info.pids.first().map(|p| p.to_string()).unwrap_or_default()
+    };
+
+    PREFERRED_PORTS
+        .iter()
+        .filter_map(|&preferred| {
+            let squatter = holder(infos, preferred)?;
+            let next = holder(infos, preferred.checked_add(1)?)?;
+            let process = first_process(next)?;
+
+            let mut fallbacks = Vec::new();
+            let mut port = preferred + 1;
+            while let Some(info) = holder(infos, port) {
+                if first_process(info).as_deref() != Some(process.as_str()) {
+                    break;
+                }
+                fallbacks.push((port, first_pid(info)));
+                port = port.checked_add(1)?;
+            }
+            Some(Chain {
+                preferred,
+                squatter: first_process(squatter).unwrap_or_default(),
+                squatter_pid: first_pid(squatter),
+                fallbacks,
+                process,
+            })
+        })
+        .collect()
+}
+
+pub fn print_section(chains: &[Chain], header: bool) {
+    if chains.is_empty() {
+        return;
+    }
+    if header {
+        println!("{}", "chains".heading().bold());
+    }
+    let glyphs = crate::output::glyphs();
+    for chain in chains {
+        let fallbacks: Vec<String> = chain
+            .fallbacks
+            .iter()
+            .map(|(port, pid)| {
+                format!(
+                    "{} {}",
+                    format!(":{}", port).accent().bold(),
+                    crate::format_pid(pid).muted()
+                )
+            })
+            .collect();
+        println!(
+            "  {} {} {} holds the preferred port {} {} {} fell back to {}",
+            format!(":{}", chain.preferred).accent().bold(),
+            chain.squatter.success(),
+            crate::format_pid(&chain.squatter_pid).muted(),
+            glyphs.sep,
+            chain.fallbacks.len(),
+            chain.process.success(),
+            fallbacks.join(", ")
+        );
+        println!(
+            "    {}",
+            format!(
+                "reclaim it: lsof-work-ports kill {} (or k in --interactive)",
+                chain.preferred
+            )
+            .muted()
+        );
+    }
+    if header {
+        println!();
+    }
+}
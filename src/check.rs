@@ -0,0 +1,85 @@
+//! `check`: exit 0 when a port is free and 1 when it's taken, for CI and Makefiles
+
+use crate::theme::Themed;
+use anyhow::Result;
+use std::collections::BTreeSet;
+use std::io::ErrorKind;
+use std::net::{Ipv4Addr, TcpListener};
+
+/// Whether binding fails because the port is taken. This catches listeners the
+/// collector can't see (other users' processes without root); other bind errors, like
+/// EACCES on privileged ports, don't say the port is in use.
+pub fn bind_in_use(port: u16) -> bool {
+    [Ipv4Addr::LOCALHOST, Ipv4Addr::UNSPECIFIED]
+        .into_iter()
+        .any(|ip| TcpListener::bind((ip, port)).is_err_and(|e| e.kind() == ErrorKind::AddrInUse))
+}
+
+pub fn run(port: u16) -> Result<()> {
+    let listeners = crate::tcp_listeners(port)?;
+    if listeners.is_empty() && !bind_in_use(port) {
+        // The test bind uses SO_REUSEADDR, which TIME_WAIT and FIN_WAIT don't block
+        let closing = crate::explain::closing_sockets(port).unwrap_or_default();
+        if closing.is_empty() {
+            println!(
+                "{} Port {} is free",
+                crate::output::glyphs().check.success(),
+                port.to_string().accent()
+            );
+            return Ok(());
+        }
+        let states: BTreeSet<&str> = closing.iter().map(|s| s.state.as_str()).collect();
+        let clears = closing
+            .iter()
+            .filter_map(|s| s.clears_in)
+            .max()
+            .map(|secs| format!(", clear in ~{}s", secs))
+            .unwrap_or_default();
+        println!(
+            "{} Port {} has no listener, but {} closing connection(s) ({}{}) hold it",
+            crate::output::glyphs().warn.warning(),
+            port.to_string().accent(),
+            closing.len(),
+            states.into_iter().collect::<Vec<_>>().join(", "),
+            clears
+        );
+        println!(
+            "{}",
+            "  Servers that bind with SO_REUSEADDR (most do) can start; others fail until then"
+                .muted()
+        );
+        return Ok(());
+    }
+
+    let glyph = crate::output::glyphs().warn.warning();
+    if listeners.is_empty() {
+        println!(
+            "{} Port {} is in use by a process not visible to this user (try sudo)",
+            glyph,
+            port.to_string().accent()
+        );
+    }
+    // One line per process, not per IPv4/IPv6 socket
+    let mut shown = Vec::new();
+    for info in &listeners {
+        if shown.contains(&info.pid) {
+            continue;
+        }
+        shown.push(info.pid.clone());
+        println!(
+            "{} Port {} is in use by {} (pid {}) on {}",
+            glyph,
+            port.to_string().accent(),
+            info.process.label(),
+            info.pid,
+            info.address
+        );
+        if !info.command.is_empty() {
+            println!("{}", format!("  {}", info.command).muted());
+        }
+        if let Some(warning) = crate::well_known::conflict_warning(port, &info.process) {
+            println!("{}", format!("  {}", warning).warning());
+        }
+    }
+    std::process::exit(1)
+}
@@ -0,0 +1,651 @@
+//! Socket collectors: `lsof` (Linux/macOS), `sockstat` (FreeBSD), `fstat` (OpenBSD),
+//! and `/proc` on Linux systems without lsof
+//!
+//! Each backend parses its tool's output into [`Listener`]s, TCP and UDP sockets in
+//! any state; the listing keeps the listening ones unless `--state` asks for others.
+//! Enrichment with the command line, start time and interface happens in `enrich`.
+
+use crate::theme::Themed;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::process::Command;
+use std::sync::{Arc, OnceLock};
+
+/// Transport protocol of a socket
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Protocol {
+    Tcp,
+    Udp,
+}
+
+impl Protocol {
+    /// "TCP" or "UDP", as lsof prints it
+    pub fn name(self) -> &'static str {
+        match self {
+            Protocol::Tcp => "TCP",
+            Protocol::Udp => "UDP",
+        }
+    }
+}
+
+/// State of a TCP listener
+pub const LISTEN: &str = "LISTEN";
+
+/// State of a bound UDP socket without a peer, the UDP counterpart of a listener
+pub const UNCONN: &str = "UNCONN";
+
+/// A socket as reported by a backend: a listener, a bound UDP socket or a connection
+#[derive(Debug, Clone)]
+pub struct Listener {
+    pub process: Arc<str>,
+    pub pid: Arc<str>,
+    /// Owner of the process
+    pub user: String,
+    /// "IPv4" or "IPv6"
+    pub family: String,
+    /// Local address without the port ("*", "127.0.0.1", "[::1]")
+    pub address: String,
+    pub port: u16,
+    pub protocol: Protocol,
+    /// TCP state as lsof prints it (`LISTEN`, `ESTABLISHED`, `TIME_WAIT`, ...);
+    /// [`UNCONN`] or `ESTABLISHED` for UDP
+    pub state: String,
+    /// Remote `address:port` of a connection
+    pub peer: Option<String>,
+    /// Identity of the socket (inode or kernel address), empty when unknown
+    pub socket: String,
+}
+
+impl Listener {
+    /// A TCP listener or an unconnected UDP socket
+    pub fn is_listening(&self) -> bool {
+        self.state == LISTEN || self.state == UNCONN
+    }
+}
+
+/// The state of a socket whose tool prints none (UDP, sockstat, fstat): connected
+/// sockets count as established
+fn connection_state(protocol: Protocol, connected: bool) -> String {
+    match (protocol, connected) {
+        (_, true) => "ESTABLISHED",
+        (Protocol::Tcp, false) => LISTEN,
+        (Protocol::Udp, false) => UNCONN,
+    }
+    .to_string()
+}
+
+/// Split lsof's `local->peer` name
+fn split_peer(name: &str) -> (&str, Option<&str>) {
+    match name.split_once("->") {
+        Some((local, peer)) => (local, Some(peer)),
+        None => (name, None),
+    }
+}
+
+/// One shared allocation per distinct string: the pid and process name repeat for
+/// every socket of a process, and are cloned again when the rows are grouped
+#[derive(Default)]
+pub struct Interner(HashSet<Arc<str>>);
+
+impl Interner {
+    pub fn intern(&mut self, text: &str) -> Arc<str> {
+        if let Some(shared) = self.0.get(text) {
+            return shared.clone();
+        }
+        let shared: Arc<str> = Arc::from(text);
+        self.0.insert(shared.clone());
+        shared
+    }
+}
+
+/// `[collector]` table of the config
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct CollectorConfig {
+    #[serde(default, skip_serializing_if = "LsofConfig::is_empty")]
+    pub lsof: LsofConfig,
+}
+
+impl CollectorConfig {
+    pub fn is_empty(&self) -> bool {
+        self.lsof.is_empty()
+    }
+}
+
+/// `[collector.lsof]`: how lsof is run, e.g. `+c 0`, a full path or a wrapper
+/// (`binary = "toolbox"`, `extra_args = ["run", "lsof"]`)
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct LsofConfig {
+    /// Program run instead of `lsof`; `LWP_LSOF_BINARY` overrides it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub binary: Option<String>,
+    /// Arguments placed before ours; `LWP_LSOF_ARGS` (split on whitespace) overrides them
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub extra_args: Vec<String>,
+}
+
+impl LsofConfig {
+    pub fn is_empty(&self) -> bool {
+        self.binary.is_none() && self.extra_args.is_empty()
+    }
+
+    /// The config with the environment overrides applied
+    pub fn resolve(&self) -> Self {
+        let binary = std::env::var("LWP_LSOF_BINARY")
+            .ok()
+            .filter(|b| !b.is_empty())
+            .or_else(|| self.binary.clone());
+        let extra_args = match std::env::var("LWP_LSOF_ARGS") {
+            Ok(args) => args.split_whitespace().map(String::from).collect(),
+            Err(_) => self.extra_args.clone(),
+        };
+        LsofConfig { binary, extra_args }
+    }
+
+    pub fn binary(&self) -> &str {
+        self.binary.as_deref().unwrap_or("lsof")
+    }
+}
+
+/// The resolved `[collector.lsof]`, read on the first lsof run
+static LSOF: OnceLock<LsofConfig> = OnceLock::new();
+
+/// Take `[collector]` from an already loaded config instead of reading it again
+pub fn use_config(config: &CollectorConfig) {
+    let _ = LSOF.set(config.lsof.resolve());
+}
+
+/// `lsof` with the configured binary and extra arguments; every lsof call goes here
+pub fn lsof() -> Command {
+    let settings = LSOF.get_or_init(|| {
+        // A broken config is reported by the commands that need it
+        crate::Config::load()
+            .map(|c| c.collector.lsof)
+            .unwrap_or_default()
+            .resolve()
+    });
+    let mut command = Command::new(settings.binary());
+    command.args(&settings.extra_args);
+    command
+}
+
+/// Whether the configured lsof binary can be run, by path or from `PATH`
+pub fn lsof_available() -> bool {
+    static AVAILABLE: OnceLock<bool> = OnceLock::new();
+    *AVAILABLE.get_or_init(|| {
+        let binary = lsof().get_program().to_owned();
+        let binary = std::path::Path::new(&binary);
+        if binary.components().count() > 1 {
+            return binary.is_file();
+        }
+        std::env::var_os("PATH")
+            .is_some_and(|path| std::env::split_paths(&path).any(|dir| dir.join(binary).is_file()))
+    })
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum Backend {
+    Lsof,
+    Sockstat,
+    Fstat,
+    /// Read /proc/net/{tcp,udp}{,6} and /proc/*/fd directly (Linux)
+    Proc,
+}
+
+/// `--backend`
+static CHOSEN: OnceLock<Backend> = OnceLock::new();
+
+pub fn init_backend(backend: Option<Backend>) {
+    if let Some(backend) = backend {
+        let _ = CHOSEN.set(backend);
+    }
+}
+
+impl Backend {
+    /// The one given with `--backend` or named by `LWP_BACKEND`, else the native tool
+    /// of the platform (lsof is not in the BSD base systems, nor in minimal containers)
+    pub fn detect() -> Self {
+        if let Some(backend) = CHOSEN.get() {
+            *backend
+        } else if let Some(backend) = std::env::var("LWP_BACKEND")
+            .ok()
+            .and_then(|name| Self::from_name(&name))
+        {
+            backend
+        } else if cfg!(target_os = "freebsd") {
+            Backend::Sockstat
+        } else if cfg!(target_os = "openbsd") {
+            Backend::Fstat
+        } else if cfg!(target_os = "linux") && !crate::fixtures::active() && !lsof_available() {
+            Backend::Proc
+        } else {
+            Backend::Lsof
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Backend::Lsof => "lsof",
+            Backend::Sockstat => "sockstat",
+            Backend::Fstat => "fstat",
+            Backend::Proc => "proc",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        [
+            Backend::Lsof,
+            Backend::Sockstat,
+            Backend::Fstat,
+            Backend::Proc,
+        ]
+        .into_iter()
+        .find(|backend| backend.name() == name)
+    }
+
+    /// The tool with the arguments for its column output
+    fn command(self) -> Command {
+        let mut command = match self {
+            Backend::Lsof => lsof(),
+            _ => Command::new(self.name()),
+        };
+        match self {
+            Backend::Lsof => command.args(["-i", "-P", "-n"]),
+            Backend::Sockstat => command.args(["-4", "-6", "-c", "-l", "-P", "tcp,udp"]),
+            Backend::Fstat | Backend::Proc => &mut command,
+        };
+        command
+    }
+
+    pub fn run(self, mut command: Command) -> Result<String> {
+        let output = command
+            .output()
+            .with_context(|| format!("Failed to execute {} command", self.name()))?;
+        anyhow::ensure!(
+            output.status.success(),
+            "{} command returned an error",
+            self.name()
+        );
+        Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+    }
+
+    /// Raw output of the backend's tool (the socket tables for `proc`)
+    pub fn raw_output(self) -> Result<String> {
+        if self == Backend::Proc {
+            // Fixtures provide a fake tree through LWP_PROC_ROOT instead
+            return crate::procfs::tables();
+        }
+        if let Some(recorded) = crate::fixtures::output(self.name()) {
+            return Ok(recorded);
+        }
+        if self == Backend::Lsof {
+            let mut fields = lsof();
+            // Field output has no columns to shift, so names may be untruncated
+            fields.args(["+c", "0", "-i", "-P", "-n", LSOF_FIELDS]);
+            match self.run(fields) {
+                Ok(stdout) if is_lsof_fields(&stdout) => return Ok(stdout),
+                // A build without -F: the column output is parsed instead
+                _ => {}
+            }
+        }
+        self.run(self.command())
+    }
+
+    /// Sockets in every state, connections included
+    pub fn sockets(self) -> Result<Vec<Listener>> {
+        let stdout = self.raw_output()?;
+        let parsed = self.parse(&stdout);
+        if let Some(Some(verbose)) = STRICT.get() {
+            report_skipped(self, &parsed.skipped, *verbose);
+        }
+        Ok(parsed.sockets)
+    }
+
+    /// TCP listeners and unconnected UDP sockets
+    pub fn listeners(self) -> Result<Vec<Listener>> {
+        let mut sockets = self.sockets()?;
+        sockets.retain(Listener::is_listening);
+        Ok(sockets)
+    }
+
+    pub fn parse(self, stdout: &str) -> Parsed {
+        match self {
+            Backend::Lsof if is_lsof_fields(stdout) => parse_lsof_fields(stdout),
+            Backend::Lsof => parse_lsof(stdout),
+            Backend::Sockstat => parse_sockstat(stdout),
+            Backend::Fstat => parse_fstat(stdout),
+            Backend::Proc => crate::procfs::parse(stdout),
+        }
+    }
+}
+
+/// Sockets of one backend run, and the socket lines that did not parse
+#[derive(Default)]
+pub struct Parsed {
+    pub sockets: Vec<Listener>,
+    pub skipped: Vec<String>,
+}
+
+impl Parsed {
+    /// Parse each `relevant` line with `parse`, keeping the lines it rejects
+    fn lines(
+        stdout: &str,
+        relevant: impl Fn(&str) -> bool,
+        mut parse: impl FnMut(&str) -> Option<Listener>,
+    ) -> Self {
+        let mut parsed = Parsed::default();
+        for line in stdout.lines().skip(1).filter(|line| relevant(line)) {
+            match parse(line) {
+                Some(socket) => parsed.sockets.push(socket),
+                None => parsed.skipped.push(line.to_string()),
+            }
+        }
+        parsed
+    }
+}
+
+/// `--strict-parse`: `Some(verbose)` when skipped lines are reported
+static STRICT: OnceLock<Option<bool>> = OnceLock::new();
+
+pub fn init_strict(strict: bool, verbose: bool) {
+    let _ = STRICT.set(strict.then_some(verbose));
+}
+
+/// Lines shown with `--strict-parse -v`
+const SKIPPED_SAMPLES: usize = 5;
+
+fn report_skipped(backend: Backend, skipped: &[String], verbose: bool) {
+    if skipped.is_empty() {
+        return;
+    }
+    eprintln!(
+        "{} {} {} line(s) could not be parsed; their ports are missing from the output",
+        "warning:".warning(),
+        skipped.len(),
+        backend.name()
+    );
+    if !verbose {
+        eprintln!("{}", "  (-v shows them)".muted());
+        return;
+    }
+    for line in skipped.iter().take(SKIPPED_SAMPLES) {
+        eprintln!("  {}", line.muted());
+    }
+    if skipped.len() > SKIPPED_SAMPLES {
+        eprintln!(
+            "{}",
+            format!("  ... and {} more", skipped.len() - SKIPPED_SAMPLES).muted()
+        );
+    }
+}
+
+fn is_pid(field: &str) -> bool {
+    !field.is_empty() && field.bytes().all(|b| b.is_ascii_digit())
+}
+
+/// Width lsof cuts process names to without `+c`
+const LSOF_NAME_WIDTH: usize = 9;
+
+/// The untruncated name of `process`, from the program path at the start of its
+/// command line: lsof cuts names to 9 characters (`com.docke` for
+/// `.../MacOS/com.docker.backend`) when `+c 0` is not available, and the kernel keeps
+/// 15 (Linux) or 16 (macOS) characters even with it
+pub fn full_name(process: &str, command: &str) -> Option<String> {
+    // Shorter names are complete, e.g. `nginx` of `nginx: worker process`
+    if process.chars().count() < LSOF_NAME_WIDTH {
+        return None;
+    }
+    // The name starts at the beginning or after a `/`, and a directory of the
+    // same name (`Google Chrome.app/`) is not the program itself
+    command
+        .match_indices(process)
+        .filter(|(start, _)| *start == 0 || command[..*start].ends_with('/'))
+        .find_map(|(start, _)| {
+            let rest = &command[start + process.len()..];
+            let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+            let name = &command[start..start + process.len() + end];
+            (!name.contains('/') && name.len() > process.len()).then(|| name.to_string())
+        })
+}
+
+/// Split `addr:port` at the last colon ("*:8080", "127.0.0.1:3000", "[::1]:5000")
+pub fn split_address(name_field: &str) -> Option<(String, u16)> {
+    let (address, port) = name_field.rsplit_once(':')?;
+    Some((address.to_string(), port.parse().ok()?))
+}
+
+/// pid, command, login, fd, type, device, protocol, name and TCP state, one per line
+const LSOF_FIELDS: &str = "-FpcLftdPnT";
+
+/// Field output starts with the `p<pid>` line of the first process (or is empty)
+fn is_lsof_fields(stdout: &str) -> bool {
+    stdout
+        .lines()
+        .next()
+        .is_none_or(|line| line.strip_prefix('p').is_some_and(is_pid))
+}
+
+/// A socket in `lsof -F` output, filled in field by field
+#[derive(Default)]
+struct FieldFile<'a> {
+    fd: &'a str,
+    family: &'a str,
+    device: &'a str,
+    protocol: &'a str,
+    name: &'a str,
+    state: &'a str,
+}
+
+/// `lsof -F pcLftdPnT`: a `p`/`c`/`L` block per process, then `f`/`t`/`d`/`P`/`n`/`T`
+/// lines per file. Unlike the columns this survives spaces in process names.
+fn parse_lsof_fields(stdout: &str) -> Parsed {
+    let mut strings = Interner::default();
+    let mut parsed = Parsed::default();
+    let (mut pid, mut process, mut user) = ("", "", "");
+    let mut file: Option<FieldFile> = None;
+
+    let mut finish = |file: Option<FieldFile>, pid: &str, process: &str, user: &str| {
+        let Some(file) = file else { return };
+        let (local, peer) = split_peer(file.name);
+        let (protocol, state) = match file.protocol {
+            "TCP" if !file.state.is_empty() => (Protocol::Tcp, file.state.to_string()),
+            // Unbound UDP sockets have no port
+            "UDP" if !local.ends_with(":*") => (
+                Protocol::Udp,
+                connection_state(Protocol::Udp, peer.is_some()),
+            ),
+            _ => return,
+        };
+        match split_address(local) {
+            Some((address, port)) => parsed.sockets.push(Listener {
+                process: strings.intern(process),
+                pid: strings.intern(pid),
+                user: user.to_string(),
+                family: file.family.to_string(),
+                address,
+                port,
+                protocol,
+                state,
+                peer: peer.map(str::to_string),
+                socket: file.device.to_string(),
+            }),
+            None => parsed.skipped.push(format!(
+                "p{} c{} f{} P{} n{}",
+                pid, process, file.fd, file.protocol, file.name
+            )),
+        }
+    };
+
+    for line in stdout.lines() {
+        let Some(tag) = line.chars().next() else {
+            continue;
+        };
+        let value = &line[tag.len_utf8()..];
+        match tag {
+            'p' => {
+                finish(file.take(), pid, process, user);
+                (pid, process, user) = (value, "", "");
+            }
+            'c' => process = value,
+            'L' => user = value,
+            'f' => {
+                finish(file.take(), pid, process, user);
+                file = Some(FieldFile {
+                    fd: value,
+                    ..FieldFile::default()
+                });
+            }
+            _ => {
+                let Some(file) = file.as_mut() else { continue };
+                match tag {
+                    't' => file.family = value,
+                    'd' => file.device = value,
+                    'P' => file.protocol = value,
+                    'n' => file.name = value,
+                    'T' => {
+                        if let Some(state) = value.strip_prefix("ST=") {
+                            file.state = state;
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }
+    finish(file, pid, process, user);
+    parsed
+}
+
+/// Column output of lsof builds without `-F`:
+/// `COMMAND PID USER FD TYPE DEVICE SIZE/OFF NODE NAME`, e.g.
+/// `node 1234 me 23u IPv4 0x... 0t0 TCP 127.0.0.1:3000 (LISTEN)`, connections with
+/// `local->peer (ESTABLISHED)`; UDP sockets have no state
+/// (`chrome 2210 me 88u IPv4 500100 0t0 UDP *:5353`)
+fn parse_lsof(stdout: &str) -> Parsed {
+    let mut strings = Interner::default();
+    Parsed::lines(
+        stdout,
+        |line| line.contains(" TCP ") || (line.contains(" UDP ") && !line.ends_with(":*")),
+        |line| {
+            // A name with spaces shifts the columns, e.g. `Google Chrome` without +c
+            let parts: Vec<_> = line.split_whitespace().collect();
+            if parts.len() < 9 || !is_pid(parts[1]) {
+                return None;
+            }
+            let (local, peer) = split_peer(parts[8]);
+            let (protocol, state) = match (parts.len(), parts[7]) {
+                (10, "TCP") => {
+                    let state = parts[9].strip_prefix('(')?.strip_suffix(')')?;
+                    (Protocol::Tcp, state.to_string())
+                }
+                (9, "UDP") => (
+                    Protocol::Udp,
+                    connection_state(Protocol::Udp, peer.is_some()),
+                ),
+                _ => return None,
+            };
+            let (address, port) = split_address(local)?;
+            Some(Listener {
+                process: strings.intern(parts[0]),
+                pid: strings.intern(parts[1]),
+                user: parts[2].to_string(),
+                family: parts[4].to_string(),
+                address,
+                port,
+                protocol,
+                state,
+                peer: peer.map(str::to_string),
+                socket: parts[5].to_string(),
+            })
+        },
+    )
+}
+
+/// `USER COMMAND PID FD PROTO LOCAL-ADDRESS FOREIGN-ADDRESS`, e.g.
+/// `www nginx 900 6 tcp4 *:80 *:*` or `root syslogd 610 6 udp4 *:514 *:*`; sockstat
+/// prints no state, so connected sockets (with a foreign address) count as established
+fn parse_sockstat(stdout: &str) -> Parsed {
+    let mut strings = Interner::default();
+    // `? ? ? ?` rows are sockets without a process (kernel, NFS)
+    Parsed::lines(
+        stdout,
+        |line| line.split_whitespace().nth(2).is_some_and(|pid| pid != "?"),
+        |line| {
+            let parts: Vec<_> = line.split_whitespace().collect();
+            if parts.len() < 6 || !is_pid(parts[2]) {
+                return None;
+            }
+            let (protocol, family) = parts[4].split_at(3);
+            let protocol = match protocol {
+                "tcp" => Protocol::Tcp,
+                "udp" => Protocol::Udp,
+                _ => return None,
+            };
+            let family = match family {
+                "4" => "IPv4",
+                "6" | "46" => "IPv6",
+                _ => return None,
+            };
+            let (address, port) = split_address(parts[5])?;
+            let peer = parts.get(6).filter(|peer| **peer != "*:*");
+            Some(Listener {
+                process: strings.intern(parts[1]),
+                pid: strings.intern(parts[2]),
+                user: parts[0].to_string(),
+                family: family.to_string(),
+                address,
+                port,
+                protocol,
+                state: connection_state(protocol, peer.is_some()),
+                peer: peer.map(|peer| peer.to_string()),
+                socket: format!("fd{}", parts[3]),
+            })
+        },
+    )
+}
+
+/// `USER CMD PID FD internet[6] stream tcp 0x... ADDRESS`, e.g.
+/// `root sshd 6570 3* internet stream tcp 0x... *:22`, and `dgram udp` for UDP;
+/// connected sockets continue with `<--`/`-->` and the peer address. fstat prints no
+/// state, so those count as established.
+fn parse_fstat(stdout: &str) -> Parsed {
+    let mut strings = Interner::default();
+    Parsed::lines(
+        stdout,
+        |line| line.contains(" stream tcp ") || line.contains(" dgram udp "),
+        |line| {
+            let parts: Vec<_> = line.split_whitespace().collect();
+            let family = match parts.get(4) {
+                Some(&"internet") => "IPv4",
+                Some(&"internet6") => "IPv6",
+                _ => return None,
+            };
+            let protocol = match (parts.get(5), parts.get(6)) {
+                (Some(&"stream"), Some(&"tcp")) => Protocol::Tcp,
+                (Some(&"dgram"), Some(&"udp")) => Protocol::Udp,
+                _ => return None,
+            };
+            let peer = match parts.len() {
+                9 => None,
+                11 if matches!(parts[9], "<--" | "-->") => Some(parts[10]),
+                _ => return None,
+            };
+            if !is_pid(parts[2]) {
+                return None;
+            }
+            let (address, port) = split_address(parts[8])?;
+            Some(Listener {
+                process: strings.intern(parts[1]),
+                pid: strings.intern(parts[2]),
+                user: parts[0].to_string(),
+                family: family.to_string(),
+                address,
+                port,
+                protocol,
+                state: connection_state(protocol, peer.is_some()),
+                peer: peer.map(str::to_string),
+                socket: parts[7].to_string(),
+            })
+        },
+    )
+}
@@ -0,0 +1,284 @@
+//! `config` subcommands: `get` / `set` read and change single values by dotted path,
+//! `add` / `remove` / `list` manage the `[[ports]]` ranges
+//!
+//! Paths follow the TOML structure, with indexes for arrays: `score_threshold`,
+//! `display.pinned`, `ports.0.name`, `collector.lsof.binary`. Values are checked against
+//! the config types before anything is written.
+//!
+//! Saving edits the file's TOML rather than writing the typed config out: keys the types
+//! don't read (e.g. `hook` or `[alerts]` in a build without that feature) are kept.
+
+use crate::theme::Themed;
+use crate::{Config, PortEntry};
+use anyhow::{Context, Result};
+use toml::Value;
+
+/// The config as TOML, including the sections left out of the file while empty
+fn full_value(config: &Config) -> Result<Value> {
+    let mut table = toml::Table::try_from(config).context("Failed to serialize config")?;
+    let sections = [
+        ("ports", Value::try_from(&config.ports)?),
+        ("display", Value::try_from(&config.display)?),
+        #[cfg(feature = "daemon")]
+        ("alerts", Value::try_from(&config.alerts)?),
+        ("services", Value::try_from(&config.services)?),
+        ("queries", Value::try_from(&config.queries)?),
+        ("secret_keys", Value::try_from(&config.secret_keys)?),
+        ("insights", Value::Boolean(config.insights)),
+        ("collector", Value::try_from(&config.collector)?),
+    ];
+    for (key, value) in sections {
+        table.entry(key).or_insert(value);
+    }
+    Ok(Value::Table(table))
+}
+
+/// `file` updated with `config`: keys the types read from it are replaced, or removed
+/// when `config` leaves them out; keys they don't know are kept as they are
+pub fn merge(file: toml::Table, config: &Config) -> Result<toml::Table> {
+    // What the types make of the file, to tell their keys from unknown ones
+    let read: Config = Value::Table(file.clone())
+        .try_into()
+        .context("Failed to parse config file")?;
+    let known = toml::Table::try_from(&read).context("Failed to serialize config")?;
+    let updated = toml::Table::try_from(config).context("Failed to serialize config")?;
+    Ok(merge_tables(file, &known, updated))
+}
+
+fn merge_tables(file: toml::Table, known: &toml::Table, mut updated: toml::Table) -> toml::Table {
+    let mut merged = toml::Table::new();
+    for (key, value) in file {
+        let value = match (updated.remove(&key), value, known.get(&key)) {
+            // Not read by the types
+            (None, value, None) => value,
+            // A section left out now, which may still hold unknown keys
+            (None, Value::Table(old), Some(Value::Table(known))) => {
+                let rest = merge_tables(old, known, toml::Table::new());
+                if rest.is_empty() {
+                    continue;
+                }
+                Value::Table(rest)
+            }
+            // Read, and left out now (an emptied list, an unset option)
+            (None, _, Some(_)) => continue,
+            (Some(Value::Table(new)), Value::Table(old), Some(Value::Table(known))) => {
+                Value::Table(merge_tables(old, known, new))
+            }
+            (Some(new), _, _) => new,
+        };
+        merged.insert(key, value);
+    }
+    merged.extend(updated);
+    merged
+}
+
+fn lookup<'a>(value: &'a Value, key: &str) -> Option<&'a Value> {
+    key.split('.')
+        .try_fold(value, |value, segment| match value {
+            Value::Table(table) => table.get(segment),
+            Value::Array(array) => segment.parse::<usize>().ok().and_then(|i| array.get(i)),
+            _ => None,
+        })
+}
+
+/// Put `new` at `key`, creating missing tables on the way
+fn insert(root: &mut Value, key: &str, new: Value) -> Result<()> {
+    let segments: Vec<&str> = key.split('.').collect();
+    anyhow::ensure!(
+        segments.iter().all(|s| !s.is_empty()),
+        "Invalid config key `{}`",
+        key
+    );
+    let (last, parents) = segments.split_last().context("Empty config key")?;
+    let mut value = root;
+    for (depth, segment) in parents.iter().enumerate() {
+        let prefix = segments[..=depth].join(".");
+        value = match value {
+            Value::Table(table) => table
+                .entry(*segment)
+                .or_insert_with(|| Value::Table(toml::Table::new())),
+            Value::Array(array) => segment
+                .parse::<usize>()
+                .ok()
+                .and_then(|i| array.get_mut(i))
+                .with_context(|| format!("No entry `{}`", prefix))?,
+            _ => anyhow::bail!("`{}` is not a table", segments[..depth].join(".")),
+        };
+    }
+    match value {
+        Value::Table(table) => {
+            table.insert(last.to_string(), new);
+        }
+        Value::Array(array) => {
+            let slot = last
+                .parse::<usize>()
+                .ok()
+                .and_then(|i| array.get_mut(i))
+                .with_context(|| format!("No entry `{}`", key))?;
+            *slot = new;
+        }
+        _ => anyhow::bail!("`{}` is not a table", parents.join(".")),
+    }
+    Ok(())
+}
+
+/// A TOML value (`3000`, `true`, `[3000, 5432]`, `"text"`), else the text as a string
+fn parse_value(raw: &str) -> Value {
+    toml::from_str::<toml::Table>(&format!("value = {}", raw))
+        .ok()
+        .and_then(|mut table| table.remove("value"))
+        .unwrap_or_else(|| Value::String(raw.to_string()))
+}
+
+/// The config with `new` at `key`, if that makes a valid config
+fn with_value(config: &Config, key: &str, new: Value) -> Result<Config> {
+    let mut value = full_value(config)?;
+    insert(&mut value, key, new.clone())?;
+    let updated: Config = value
+        .try_into()
+        .with_context(|| format!("Invalid value for `{}`", key))?;
+    // Unknown keys are dropped by deserializing, so they don't come back
+    anyhow::ensure!(
+        lookup(&full_value(&updated)?, key) == Some(&new),
+        "Unknown config key `{}`",
+        key
+    );
+    Ok(updated)
+}
+
+pub fn get(key: &str, json: bool) -> Result<()> {
+    let config = Config::load()?;
+    let value = full_value(&config)?;
+    let value = lookup(&value, key).with_context(|| format!("`{}` is not set", key))?;
+    if json {
+        println!("{}", serde_json::to_string(value)?);
+        return Ok(());
+    }
+    match value {
+        Value::String(text) => println!("{}", text),
+        Value::Table(table) => print!("{}", toml::to_string(table)?),
+        other => println!("{}", other),
+    }
+    Ok(())
+}
+
+pub fn set(key: &str, raw: &str) -> Result<()> {
+    let _lock = Config::lock()?;
+    let config = Config::load()?;
+    let parsed = parse_value(raw);
+    let updated = match with_value(&config, key, parsed.clone()) {
+        Ok(updated) => updated,
+        // `name = 8080` means the text "8080" when the field is a string
+        Err(e) if !parsed.is_str() => {
+            with_value(&config, key, Value::String(raw.to_string())).map_err(|_| e)?
+        }
+        Err(e) => return Err(e),
+    };
+    updated.save()?;
+    println!("{} {} = {}", crate::output::glyphs().check, key, raw);
+    Ok(())
+}
+
+/// Add a `[[ports]]` range, e.g. `3000-3100` named `frontend`
+pub fn add(spec: &str, name: Option<&str>) -> Result<()> {
+    let spec = spec.trim();
+    anyhow::ensure!(
+        !crate::expand_ports(spec)?.is_empty(),
+        "No ports in \"{}\"",
+        spec
+    );
+    let _lock = Config::lock()?;
+    let mut config = Config::load()?;
+    anyhow::ensure!(
+        !config.ports.iter().any(|e| e.ports == spec),
+        "{} is already monitored",
+        spec
+    );
+    config.ports.push(PortEntry {
+        ports: spec.to_string(),
+        name: name.map(str::to_string),
+        http: None,
+        status: None,
+    });
+    config.save()?;
+    println!(
+        "{} Monitoring {}",
+        crate::output::glyphs().check,
+        describe(config.ports.last().unwrap())
+    );
+    Ok(())
+}
+
+fn describe(entry: &PortEntry) -> String {
+    match &entry.name {
+        Some(name) => format!("{} ({})", entry.ports, name),
+        None => entry.ports.clone(),
+    }
+}
+
+/// Remove the range given as its spec or name, or the only range with this port
+pub fn remove(target: &str) -> Result<()> {
+    let _lock = Config::lock()?;
+    let mut config = Config::load()?;
+    let exact: Vec<usize> = (0..config.ports.len())
+        .filter(|&i| {
+            let entry = &config.ports[i];
+            entry.ports == target || entry.name.as_deref() == Some(target)
+        })
+        .collect();
+    let matches = if exact.is_empty() {
+        let port: u16 = target
+            .parse()
+            .with_context(|| format!("No [[ports]] range is \"{}\"", target))?;
+        (0..config.ports.len())
+            .filter(|&i| config.ports[i].expand().is_ok_and(|p| p.contains(&port)))
+            .collect()
+    } else {
+        exact
+    };
+    match matches.as_slice() {
+        [] => anyhow::bail!("No [[ports]] range contains {}", target),
+        [index] => {
+            let entry = config.ports.remove(*index);
+            config.save()?;
+            println!(
+                "{} Removed {}",
+                crate::output::glyphs().check,
+                describe(&entry)
+            );
+            Ok(())
+        }
+        several => anyhow::bail!(
+            "{} is in several ranges ({}); give the range or its name",
+            target,
+            several
+                .iter()
+                .map(|&i| describe(&config.ports[i]))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+    }
+}
+
+/// The `[[ports]]` ranges, one per line
+pub fn list() -> Result<()> {
+    let config = Config::load()?;
+    if config.ports.is_empty() {
+        println!("No port ranges; add one with `lsof-work-ports config add 3000-3100 --name web`");
+        return Ok(());
+    }
+    for entry in &config.ports {
+        let health = entry
+            .http
+            .as_deref()
+            .map(|path| format!("  health: {}", path))
+            .unwrap_or_default();
+        println!(
+            "{:<20} {}{}",
+            entry.ports.accent(),
+            entry.name.as_deref().unwrap_or("").success(),
+            health.muted()
+        );
+    }
+    Ok(())
+}
@@ -0,0 +1,135 @@
+//! `daemon`: poll the listening ports periodically, evaluate `[alerts]` rules and
+//! record listening sessions in the history
+//!
+//! On Linux it also reports listeners whose accept queue overflows, and with
+//! `--detect-hangs` monitored listeners that stop accepting or responding. With
+//! `--events`, port changes and alerts are also appended to `events.jsonl`, and with
+//! `--output-file` to a file of your choice. With `--api` the snapshots and events are
+//! served on a Unix socket (see `api`).
+//!
+//! Runs in the foreground; use `hold -- lsof-work-ports daemon` to keep it in the background.
+
+use crate::theme::Themed;
+use anyhow::{Context, Result};
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// `daemon.pid` in the state directory, for `purge` to stop a running daemon
+fn pid_path() -> Result<PathBuf> {
+    Ok(crate::paths::state_dir()?.join("daemon.pid"))
+}
+
+/// PID of the running daemon, if any
+pub fn running() -> Option<u32> {
+    let pid = std::fs::read_to_string(pid_path().ok()?).ok()?;
+    let pid = pid.trim();
+    // The file outlives a killed daemon, and its pid may have been reused since
+    let details = crate::get_process_details(&[pid]);
+    let (_, command) = details.get(pid)?;
+    command.contains("daemon").then(|| pid.parse().ok())?
+}
+
+/// State shared with the API's socket threads
+#[cfg(unix)]
+type Api = std::sync::Arc<crate::api::State>;
+/// Without Unix sockets `--api` fails at startup
+#[cfg(not(unix))]
+type Api = std::convert::Infallible;
+
+#[cfg(unix)]
+fn start_api() -> Result<Api> {
+    let state = Api::default();
+    let path = crate::api::serve(state.clone())?;
+    println!("{}", format!("API on {}", path.display()).muted());
+    Ok(state)
+}
+
+#[cfg(not(unix))]
+fn start_api() -> Result<Api> {
+    anyhow::bail!("--api needs Unix sockets")
+}
+
+#[cfg(unix)]
+fn publish(
+    api: &Api,
+    config: &crate::Config,
+    port_infos: &[crate::PortInfo],
+    events: &[crate::events::Event],
+) {
+    api.update(config, port_infos, events);
+}
+
+#[cfg(not(unix))]
+fn publish(api: &Api, _: &crate::Config, _: &[crate::PortInfo], _: &[crate::events::Event]) {
+    match *api {}
+}
+
+pub fn run(
+    interval: Duration,
+    detect_hangs: bool,
+    events: bool,
+    output_file: Option<crate::events::Sink>,
+    api: bool,
+) -> Result<()> {
+    let config = crate::Config::load()?;
+    crate::alerts::validate(&config.alerts)?;
+    let api = api.then(start_api).transpose()?;
+    let pid_path = pid_path()?;
+    std::fs::create_dir_all(pid_path.parent().context("Invalid state dir")?)?;
+    std::fs::write(&pid_path, std::process::id().to_string())
+        .with_context(|| format!("Failed to write {}", pid_path.display()))?;
+    let sinks: Vec<crate::events::Sink> = events
+        .then(crate::events::Sink::state)
+        .transpose()?
+        .into_iter()
+        .chain(output_file)
+        .collect();
+    println!(
+        "{}",
+        format!(
+            "Watching ports every {}s with {} alert rule(s) {} press Ctrl-C to stop",
+            interval.as_secs_f32(),
+            config.alerts.rules.len(),
+            crate::output::glyphs().dash
+        )
+        .muted()
+    );
+
+    let mut evaluator = crate::alerts::Evaluator::default();
+    let mut overflow = crate::overflow::Monitor::default();
+    let mut hangs = crate::probe::HangMonitor::default();
+    let mut tracker = crate::events::Tracker::default();
+    let mut sessions = crate::history::Sessions::default();
+    let mut snapshots = crate::snapshot::Snapshots::default();
+    loop {
+        match snapshots.next() {
+            Ok(port_infos) => {
+                if let Err(e) = sessions.update(&port_infos) {
+                    eprintln!("{:#}", e);
+                }
+                let mut messages = evaluator.evaluate(&config.alerts, &port_infos);
+                messages.extend(overflow.check(&port_infos));
+                if detect_hangs {
+                    messages.extend(hangs.check(&config, &port_infos));
+                }
+                for message in &messages {
+                    crate::alerts::notify(&config.alerts, message);
+                }
+                if !sinks.is_empty() || api.is_some() {
+                    let mut log = tracker.diff(&port_infos);
+                    log.extend(messages.iter().map(|m| crate::events::Event::alert(m)));
+                    for sink in &sinks {
+                        if let Err(e) = sink.append(&log) {
+                            eprintln!("{:#}", e);
+                        }
+                    }
+                    if let Some(api) = &api {
+                        publish(api, &config, &port_infos, &log);
+                    }
+                }
+            }
+            Err(e) => eprintln!("{:#}", e),
+        }
+        std::thread::sleep(interval);
+    }
+}
@@ -0,0 +1,71 @@
+//! Local processes connected to a database port, shown by `explain`
+//!
+//! "Who is still connected to my dev DB" usually blocks a `DROP DATABASE` or a
+//! restore. Only processes on this machine are visible; connections from containers
+//! or other hosts show up as the database's side of the socket only.
+
+/// Default ports of the databases whose clients are summarized
+const DATABASE_PORTS: &[u16] = &[3306, 5432, 6379, 27017];
+
+/// Server processes, for databases moved to another port
+const DATABASE_PROCESSES: &[&str] = &["postgres", "mysqld", "mariadbd", "redis-server", "mongod"];
+
+/// Whether `port`, served by `processes`, is a database whose clients are worth listing
+pub fn is_database(port: u16, processes: &[&str]) -> bool {
+    DATABASE_PORTS.contains(&port)
+        || processes
+            .iter()
+            .any(|process| DATABASE_PROCESSES.contains(process))
+}
+
+/// A client process and its number of established connections
+pub struct Client {
+    pub process: String,
+    pub pid: String,
+    pub connections: usize,
+}
+
+/// Established connections to `port` by local processes, most connections first
+pub fn collect(port: u16) -> Vec<Client> {
+    if crate::fixtures::active() {
+        return Vec::new();
+    }
+    let Ok(output) = crate::collector::lsof()
+        .args(["-P", "-n", "-sTCP:ESTABLISHED", "-Fpcn"])
+        .arg(format!("-iTCP:{}", port))
+        .output()
+    else {
+        return Vec::new();
+    };
+
+    // `p<pid>`, `c<command>`, then `n<local>-><remote>` per connection; the client end
+    // is the one whose remote side is the database port
+    let suffix = format!(":{}", port);
+    let mut clients: Vec<Client> = Vec::new();
+    let (mut pid, mut process) = ("", "");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    for line in stdout.lines() {
+        if let Some(value) = line.strip_prefix('p') {
+            pid = value;
+        } else if let Some(value) = line.strip_prefix('c') {
+            process = value;
+        } else if let Some(name) = line.strip_prefix('n') {
+            let Some((_, remote)) = name.split_once("->") else {
+                continue;
+            };
+            if !remote.ends_with(&suffix) {
+                continue;
+            }
+            match clients.iter_mut().find(|c| c.pid == pid) {
+                Some(client) => client.connections += 1,
+                None => clients.push(Client {
+                    process: process.to_string(),
+                    pid: pid.to_string(),
+                    connections: 1,
+                }),
+            }
+        }
+    }
+    clients.sort_by_key(|c| std::cmp::Reverse(c.connections));
+    clients
+}
@@ -0,0 +1,63 @@
+//! Listeners whose owner `ps` can't describe: zombie processes and sockets that
+//! outlived their process (passed to a child that exited, or held by the kernel)
+
+use std::process::Command;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Owner {
+    /// Exited but not reaped by its parent; threads can keep sockets open meanwhile
+    Defunct { ppid: Option<String> },
+    /// `ps` no longer knows the pid
+    Exited,
+}
+
+fn ps_field(pid: &str, field: &str) -> Option<String> {
+    let output = Command::new("ps")
+        .args(["-p", pid, "-o", field])
+        .output()
+        .ok()?;
+    let value = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    (!value.is_empty()).then_some(value)
+}
+
+/// Classify the owner of a listener when its command is missing or marked `<defunct>`
+pub fn detect(pid: &str, command: &str) -> Option<Owner> {
+    if !command.is_empty() && !command.contains("<defunct>") {
+        return None;
+    }
+    match ps_field(pid, "stat=") {
+        Some(stat) if stat.starts_with('Z') => Some(Owner::Defunct {
+            ppid: ps_field(pid, "ppid="),
+        }),
+        Some(_) => None,
+        None => Some(Owner::Exited),
+    }
+}
+
+impl Owner {
+    /// Shown instead of the command in the listing
+    pub fn label(&self, pid: &str) -> String {
+        match self {
+            Owner::Defunct { .. } => format!("defunct pid {} (zombie)", pid),
+            Owner::Exited => format!("held by kernel / exited pid {}", pid),
+        }
+    }
+
+    /// How to get the port back, for `explain`
+    pub fn remedy(&self) -> String {
+        match self {
+            Owner::Defunct { ppid: Some(ppid) } => format!(
+                "The process exited but its parent [{}] hasn't reaped it. Restarting or killing the parent (`kill {}`) releases the port.",
+                ppid, ppid
+            ),
+            Owner::Defunct { ppid: None } => {
+                "The process exited but its parent hasn't reaped it. Restarting the parent releases the port."
+                    .to_string()
+            }
+            Owner::Exited => {
+                "The socket outlived its process. Wait for lingering connections (TIME_WAIT) to clear; if it persists, only a reboot frees it."
+                    .to_string()
+            }
+        }
+    }
+}
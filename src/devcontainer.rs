@@ -0,0 +1,292 @@
+//! `devcontainer`: compare the forwards of devcontainer.json with the listening ports
+//!
+//! Inside the container, every entry of `forwardPorts` should have a server behind it,
+//! and a dev server missing from `forwardPorts` is only reachable through auto-forwarding
+//! (unless `portsAttributes` says to ignore it). On the host, a forward is a listener
+//! of the editor or of docker; a forwarded port held by anything else means the editor
+//! moved the forward to another port.
+
+use crate::theme::Themed;
+use anyhow::{Context, Result};
+use clap::ValueEnum;
+use serde_json::Value;
+use std::path::Path;
+
+/// Which side of the forwards this runs on
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Side {
+    Container,
+    Host,
+}
+
+impl Side {
+    fn detect() -> Side {
+        let in_container = Path::new("/.dockerenv").exists()
+            || Path::new("/run/.containerenv").exists()
+            || ["REMOTE_CONTAINERS", "CODESPACES", "DEVCONTAINER"]
+                .iter()
+                .any(|var| std::env::var_os(var).is_some());
+        if in_container {
+            Side::Container
+        } else {
+            Side::Host
+        }
+    }
+}
+
+/// An entry of `forwardPorts`: `3000`, or `"db:5432"` for another service's port
+struct Forward {
+    service: Option<String>,
+    port: u16,
+}
+
+/// Settings of a `portsAttributes` key (`"3000"`, `"3000-3010"`)
+struct Attributes {
+    ports: Vec<u16>,
+    label: Option<String>,
+    on_auto_forward: Option<String>,
+}
+
+struct DevContainer {
+    forwards: Vec<Forward>,
+    attributes: Vec<Attributes>,
+    /// `onAutoForward` of `otherPortsAttributes`
+    other_on_auto_forward: Option<String>,
+}
+
+impl DevContainer {
+    fn parse(text: &str) -> Result<DevContainer> {
+        let json: Value = serde_json::from_str(&crate::verify::strip_jsonc(text))
+            .context("Invalid devcontainer.json")?;
+        let forwards = match json.get("forwardPorts") {
+            Some(Value::Array(items)) => items.iter().filter_map(forward).collect(),
+            _ => Vec::new(),
+        };
+        // Keys may also be regular expressions; those are left out
+        let attributes = match json.get("portsAttributes") {
+            Some(Value::Object(map)) => map
+                .iter()
+                .filter_map(|(key, value)| {
+                    Some(Attributes {
+                        ports: crate::expand_ports(key).ok()?,
+                        label: string(value, "label"),
+                        on_auto_forward: string(value, "onAutoForward"),
+                    })
+                })
+                .collect(),
+            _ => Vec::new(),
+        };
+        let other_on_auto_forward = json
+            .get("otherPortsAttributes")
+            .and_then(|value| string(value, "onAutoForward"));
+        Ok(DevContainer {
+            forwards,
+            attributes,
+            other_on_auto_forward,
+        })
+    }
+
+    fn attributes(&self, port: u16) -> Option<&Attributes> {
+        self.attributes.iter().find(|a| a.ports.contains(&port))
+    }
+
+    fn label(&self, port: u16) -> Option<&str> {
+        self.attributes(port).and_then(|a| a.label.as_deref())
+    }
+
+    /// Whether the editor leaves the port alone when it isn't in `forwardPorts`
+    fn ignores(&self, port: u16) -> bool {
+        let on_auto_forward = match self.attributes(port) {
+            Some(attributes) => attributes.on_auto_forward.as_deref(),
+            None => self.other_on_auto_forward.as_deref(),
+        };
+        on_auto_forward == Some("ignore")
+    }
+
+    fn forwards(&self, port: u16) -> bool {
+        self.forwards
+            .iter()
+            .any(|f| f.service.is_none() && f.port == port)
+    }
+}
+
+fn forward(item: &Value) -> Option<Forward> {
+    match item {
+        Value::Number(n) => Some(Forward {
+            service: None,
+            port: u16::try_from(n.as_u64()?).ok()?,
+        }),
+        Value::String(s) => match s.rsplit_once(':') {
+            Some((service, port)) => Some(Forward {
+                service: Some(service.to_string()),
+                port: port.parse().ok()?,
+            }),
+            None => Some(Forward {
+                service: None,
+                port: s.parse().ok()?,
+            }),
+        },
+        _ => None,
+    }
+}
+
+fn string(value: &Value, key: &str) -> Option<String> {
+    value.get(key).and_then(Value::as_str).map(str::to_string)
+}
+
+/// Processes that hold the host end of a forward: the editor, docker, Codespaces
+fn is_forwarder(info: &crate::PortInfo) -> bool {
+    let process = info.process.to_lowercase();
+    let command = info.command.to_lowercase();
+    process == "code"
+        || process.starts_with("code ")
+        || [
+            "vscode",
+            "visual studio code",
+            "cursor",
+            "codespace",
+            "devcontainer",
+        ]
+        .iter()
+        .any(|name| command.contains(name))
+        || [
+            "docker", "vpnkit", "gvproxy", "rancher", "podman", "orbstack",
+        ]
+        .iter()
+        .any(|name| process.contains(name) || command.contains(name))
+}
+
+fn describe(info: &crate::PortInfo) -> String {
+    format!("{} (pid {})", info.process, info.pid)
+}
+
+pub fn run(side: Option<Side>) -> Result<()> {
+    let root = crate::verify::project_root()?;
+    let files = crate::verify::project_files(&root, crate::verify::is_devcontainer);
+    anyhow::ensure!(
+        !files.is_empty(),
+        "No devcontainer.json in {}",
+        root.display()
+    );
+    let side = side.unwrap_or_else(Side::detect);
+    let config = crate::Config::load()?;
+    let listeners = crate::get_port_info()?;
+    let tcp = |port: u16| {
+        listeners
+            .iter()
+            .filter(move |i| i.port == port && i.protocol == crate::collector::Protocol::Tcp)
+    };
+    let glyphs = crate::output::glyphs();
+
+    for file in &files {
+        let text = std::fs::read_to_string(root.join(file))
+            .with_context(|| format!("Failed to read {}", file))?;
+        let devcontainer = DevContainer::parse(&text).with_context(|| format!("In {}", file))?;
+        println!(
+            "{} {}",
+            file.heading(),
+            format!("(seen from the {})", side_name(side)).muted()
+        );
+        let mut issues = 0;
+
+        for forward in &devcontainer.forwards {
+            let port = forward.port.to_string();
+            let label = devcontainer
+                .label(forward.port)
+                .map(|label| format!(" [{}]", label))
+                .unwrap_or_default();
+            if let (Some(service), Side::Container) = (&forward.service, side) {
+                println!(
+                    "  {} {}:{}{} {}",
+                    glyphs.arrow.muted(),
+                    service,
+                    port.accent(),
+                    label,
+                    "is forwarded from another service, not checked".muted()
+                );
+                continue;
+            }
+            let holders: Vec<&crate::PortInfo> = tcp(forward.port).collect();
+            // On the host, a listener other than the editor or docker took the port first
+            let foreign = match side {
+                Side::Host => holders.iter().find(|i| !is_forwarder(i)),
+                Side::Container => None,
+            };
+            match (holders.first(), foreign) {
+                (None, _) => {
+                    issues += 1;
+                    let hint = match side {
+                        Side::Host => " here (container stopped or editor not attached?)",
+                        Side::Container => "",
+                    };
+                    println!(
+                        "  {} {}{} is forwarded, but nothing listens on it{}",
+                        glyphs.warn.warning(),
+                        port.accent(),
+                        label,
+                        hint
+                    );
+                }
+                (_, Some(info)) => {
+                    issues += 1;
+                    println!(
+                        "  {} {}{} is held by {} on the host, so the forward lands on another port",
+                        glyphs.warn.warning(),
+                        port.accent(),
+                        label,
+                        describe(info).label()
+                    );
+                }
+                (Some(info), None) => println!(
+                    "  {} {}{} {} {}",
+                    glyphs.check.success(),
+                    port.accent(),
+                    label,
+                    glyphs.arrow.muted(),
+                    describe(info)
+                ),
+            }
+        }
+
+        // Dev servers inside the container that nothing forwards on purpose
+        if side == Side::Container {
+            let mut unforwarded: Vec<&crate::PortInfo> = listeners
+                .iter()
+                .filter(|i| i.protocol == crate::collector::Protocol::Tcp)
+                .filter(|i| !devcontainer.forwards(i.port) && !devcontainer.ignores(i.port))
+                .filter(|i| crate::dev_score(i, &config) >= config.score_threshold)
+                .collect();
+            unforwarded.sort_by_key(|i| i.port);
+            unforwarded.dedup_by_key(|i| i.port);
+            for info in unforwarded {
+                issues += 1;
+                let label = devcontainer
+                    .label(info.port)
+                    .map(|label| format!(" [{}]", label))
+                    .unwrap_or_default();
+                println!(
+                    "  {} {}{} {} listens, but isn't in forwardPorts",
+                    glyphs.warn.warning(),
+                    info.port.to_string().accent(),
+                    label,
+                    describe(info)
+                );
+            }
+        }
+
+        if issues == 0 {
+            println!(
+                "  {} forwards match the listening ports",
+                glyphs.check.success()
+            );
+        }
+    }
+    Ok(())
+}
+
+fn side_name(side: Side) -> &'static str {
+    match side {
+        Side::Container => "container",
+        Side::Host => "host",
+    }
+}
@@ -0,0 +1,75 @@
+//! Framework-specific endpoints of recognized dev servers, shown by `explain`
+
+use crate::PortInfo;
+
+/// Log lines announcing the Dart VM service (the URI carries an auth token)
+const DART_VM_MARKERS: &[&str] = &[
+    "Dart VM service is listening on ",
+    "Observatory listening on ",
+    "debugger and profiler on ",
+    "is available at: ",
+];
+
+/// (label, URL) pairs for the dev server on `info.port`
+pub fn links(info: &PortInfo) -> Vec<(&'static str, String)> {
+    let command = info.command.to_lowercase();
+    let base = format!("http://localhost:{}", info.port);
+    let ws = format!("ws://localhost:{}", info.port);
+
+    if command.contains("vite") {
+        return vec![
+            ("App", format!("{}/", base)),
+            ("HMR websocket", format!("{}/", ws)),
+        ];
+    }
+    if command.contains("metro")
+        || command.contains("react-native start")
+        || command.contains("expo start")
+    {
+        return vec![
+            ("Bundler status", format!("{}/status", base)),
+            ("Debugger targets", format!("{}/json/list", base)),
+        ];
+    }
+    if command.contains("dart") || command.contains("flutter") {
+        if let Some(uri) = dart_vm_service_uri(info) {
+            return vec![("Dart VM service", uri)];
+        }
+    }
+    if command.contains("webpack") {
+        return vec![
+            ("App", format!("{}/", base)),
+            ("Dev server websocket", format!("{}/ws", ws)),
+        ];
+    }
+    if crate::inspector::detect(info.port, &info.command).is_some() {
+        return vec![("Debugger targets", format!("{}/json/list", base))];
+    }
+    Vec::new()
+}
+
+/// The VM service URI from the command line or the log of a `hold`/`up` supervisor
+fn dart_vm_service_uri(info: &PortInfo) -> Option<String> {
+    if let Some(uri) = info
+        .command
+        .split_whitespace()
+        .find_map(|arg| arg.strip_prefix("--vm-service-uri="))
+    {
+        return Some(uri.to_string());
+    }
+
+    let entries = crate::supervisor::load().ok()?;
+    let entry = entries.iter().find(|e| {
+        crate::supervisor::group_pids(e)
+            .iter()
+            .any(|p| *p == *info.pid)
+    })?;
+    let log = std::fs::read_to_string(crate::supervisor::log_path(&entry.name).ok()?).ok()?;
+    log.lines().rev().find_map(|line| {
+        DART_VM_MARKERS.iter().find_map(|marker| {
+            let (_, rest) = line.split_once(marker)?;
+            let uri = rest.split_whitespace().next()?;
+            uri.starts_with("http").then(|| uri.to_string())
+        })
+    })
+}
@@ -0,0 +1,101 @@
+//! `doctor`: check the config and that the collector can run and be parsed
+//!
+//! Meant for environments where the defaults don't work (a wrapper around lsof in a
+//! toolbox container, a non-standard path), so a misconfigured `[collector.lsof]`
+//! shows up as a failed check instead of an empty listing.
+
+use crate::collector::Backend;
+use crate::theme::Themed;
+use anyhow::Result;
+
+struct Report {
+    failures: usize,
+}
+
+impl Report {
+    fn ok(&self, what: &str, detail: &str) {
+        let check = crate::output::glyphs().check.success();
+        if detail.is_empty() {
+            println!("{} {}", check, what);
+        } else {
+            println!("{} {} {}", check, what, detail.muted());
+        }
+    }
+
+    fn fail(&mut self, what: &str, detail: &str) {
+        self.failures += 1;
+        println!(
+            "{} {} {}",
+            crate::output::glyphs().warn.warning(),
+            what,
+            detail
+        );
+    }
+}
+
+pub fn run() -> Result<()> {
+    let mut report = Report { failures: 0 };
+
+    let config = match crate::Config::load() {
+        Ok(config) => {
+            let path = crate::paths::config_file()?;
+            report.ok("config", &path.display().to_string());
+            config
+        }
+        Err(e) => {
+            report.fail("config", &format!("{:#}", e));
+            crate::Config::default()
+        }
+    };
+
+    if crate::fixtures::active() {
+        report.ok("fixtures", "LWP_FIXTURES is set, commands are not run");
+    }
+
+    let backend = Backend::detect();
+    if backend == Backend::Lsof {
+        let lsof = config.collector.lsof.resolve();
+        let command = std::iter::once(lsof.binary())
+            .chain(lsof.extra_args.iter().map(String::as_str))
+            .collect::<Vec<_>>()
+            .join(" ");
+        report.ok("lsof command", &command);
+    } else if backend == Backend::Proc && !crate::collector::lsof_available() {
+        report.ok("lsof command", "not installed, reading /proc instead");
+    }
+
+    match backend.raw_output() {
+        Ok(stdout) => {
+            let parsed = backend.parse(&stdout);
+            let listening = parsed.sockets.iter().filter(|s| s.is_listening()).count();
+            let detail = format!("{} listening socket(s)", listening);
+            if parsed.skipped.is_empty() {
+                report.ok(backend.name(), &detail);
+            } else {
+                report.fail(
+                    backend.name(),
+                    &format!(
+                        "{}, {} line(s) could not be parsed (see --strict-parse -v)",
+                        detail,
+                        parsed.skipped.len()
+                    ),
+                );
+            }
+        }
+        Err(e) => report.fail(backend.name(), &format!("{:#}", e)),
+    }
+
+    if !crate::fixtures::active() {
+        match std::process::Command::new("ps")
+            .args(["-p", "1", "-o", "pid="])
+            .output()
+        {
+            Ok(output) if output.status.success() => report.ok("ps", ""),
+            Ok(_) => report.fail("ps", "ps -p 1 returned an error"),
+            Err(e) => report.fail("ps", &e.to_string()),
+        }
+    }
+
+    anyhow::ensure!(report.failures == 0, "{} check(s) failed", report.failures);
+    Ok(())
+}
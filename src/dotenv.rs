@@ -0,0 +1,114 @@
+//! Compare `PORT`-like variables of the project's `.env` files with what its
+//! processes actually listen on
+//!
+//! Frameworks silently move to the next free port when the configured one is taken,
+//! leaving the browser pointed at an old instance.
+
+use crate::PortInfo;
+use std::path::Path;
+
+/// Files read from the current directory, in dotenv precedence order
+const FILES: &[&str] = &[
+    ".env",
+    ".env.local",
+    ".env.development",
+    ".env.development.local",
+];
+
+/// A `PORT`/`*_PORT` variable found in a dotenv file
+pub struct EnvPort {
+    pub file: &'static str,
+    pub name: String,
+    pub port: u16,
+}
+
+/// `PORT`-like variables of the dotenv files in `dir`
+pub fn env_ports(dir: &Path) -> Vec<EnvPort> {
+    let mut ports = Vec::new();
+    for file in FILES {
+        let Ok(content) = std::fs::read_to_string(dir.join(file)) else {
+            continue;
+        };
+        for line in content.lines() {
+            let line = line.trim();
+            let line = line.strip_prefix("export ").unwrap_or(line);
+            let Some((name, value)) = line.split_once('=') else {
+                continue;
+            };
+            let name = name.trim();
+            if name != "PORT" && !name.ends_with("_PORT") {
+                continue;
+            }
+            let value = value.trim().trim_matches(|c| c == '"' || c == '\'');
+            if let Ok(port) = value.parse() {
+                // Later files override earlier ones
+                ports.retain(|p: &EnvPort| p.name != name);
+                ports.push(EnvPort {
+                    file,
+                    name: name.to_string(),
+                    port,
+                });
+            }
+        }
+    }
+    ports
+}
+
+/// Warnings for env ports that none of the project's processes listen on
+pub fn mismatch_warnings(port_infos: &[PortInfo]) -> Vec<String> {
+    let Ok(dir) = std::env::current_dir() else {
+        return Vec::new();
+    };
+    let env_ports = env_ports(&dir);
+    if env_ports.is_empty() {
+        return Vec::new();
+    }
+
+    // Listeners started from this project directory
+    let mut pids: Vec<&str> = port_infos.iter().map(|i| &*i.pid).collect();
+    pids.sort_unstable();
+    pids.dedup();
+    let inside = crate::pool::map(&pids, |pid| {
+        crate::get_process_cwd(pid).is_some_and(|cwd| Path::new(&cwd).starts_with(&dir))
+    });
+    let project: Vec<&PortInfo> = port_infos
+        .iter()
+        .filter(|info| pids.binary_search(&&*info.pid).is_ok_and(|i| inside[i]))
+        .collect();
+    if project.is_empty() {
+        return Vec::new();
+    }
+
+    let glyphs = crate::output::glyphs();
+    env_ports
+        .iter()
+        .filter(|env| !project.iter().any(|i| i.port == env.port))
+        .map(|env| {
+            let actual: Vec<String> = project
+                .iter()
+                .map(|i| format!("{} {} on :{}", i.process, crate::format_pid(&i.pid), i.port))
+                .collect();
+            let squatter = port_infos
+                .iter()
+                .find(|i| i.port == env.port)
+                .map(|i| {
+                    format!(
+                        " (:{} is held by {} {})",
+                        env.port,
+                        i.process,
+                        crate::format_pid(&i.pid)
+                    )
+                })
+                .unwrap_or_default();
+            format!(
+                "{} {} sets {}={}, but this project listens elsewhere: {}{}",
+                glyphs.warn,
+                env.file,
+                env.name,
+                env.port,
+                actual.join(", "),
+                squatter
+            )
+        })
+        .collect()
+}
@@ -0,0 +1,179 @@
+//! `daemon --events`: append port events to `$XDG_STATE_HOME/lsof-work-ports/events.jsonl`
+//!
+//! One JSON object per line, for other local tools to tail:
+//! `{"time":1700000000,"event":"listen","port":3000,"process":"node","pid":"123"}`.
+//! Events are `listen`, `close` and `alert` (with `message`). The file is rotated to
+//! `events.jsonl.1` .. `.3` when it grows past 5 MiB.
+//!
+//! `daemon --output-file` and `--watch --output-file` write the same records to a
+//! file of the user's choice, with its own rotation settings.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+#[cfg(feature = "daemon")]
+const FILE: &str = "events.jsonl";
+
+/// Size at which the file is rotated
+#[cfg(feature = "daemon")]
+const MAX_BYTES: u64 = 5 * 1024 * 1024;
+
+/// Rotated files kept besides the current one
+#[cfg(feature = "daemon")]
+const KEEP: usize = 3;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Event {
+    /// Unix timestamp
+    pub time: u64,
+    pub event: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub port: Option<u16>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub process: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pid: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+#[cfg(feature = "daemon")]
+impl Event {
+    pub fn alert(message: &str) -> Self {
+        Self {
+            time: crate::history::now(),
+            event: "alert".to_string(),
+            port: None,
+            process: None,
+            pid: None,
+            message: Some(message.to_string()),
+        }
+    }
+}
+
+#[cfg(feature = "daemon")]
+pub fn path() -> Result<PathBuf> {
+    Ok(crate::paths::state_dir()?.join(FILE))
+}
+
+/// A JSON-lines file events are appended to, rotated by size
+pub struct Sink {
+    path: PathBuf,
+    /// Rotate before appending once the file reaches this size
+    max_bytes: Option<u64>,
+    /// Rotated files kept besides the current one
+    keep: usize,
+}
+
+/// (port, process, pid)
+type Listening = (u16, Arc<str>, Arc<str>);
+
+/// Listeners of the previous snapshot, to turn snapshots into listen/close events
+#[derive(Default)]
+pub struct Tracker {
+    previous: Option<BTreeSet<Listening>>,
+}
+
+impl Tracker {
+    /// Events since the last snapshot (none for the first one, which is the baseline)
+    pub fn diff(&mut self, port_infos: &[crate::PortInfo]) -> Vec<Event> {
+        let current: BTreeSet<Listening> = port_infos
+            .iter()
+            .map(|i| (i.port, i.process.clone(), i.pid.clone()))
+            .collect();
+        let Some(previous) = self.previous.replace(current.clone()) else {
+            return Vec::new();
+        };
+
+        let time = crate::history::now();
+        let event = |name: &'static str, (port, process, pid): &Listening| Event {
+            time,
+            event: name.to_string(),
+            port: Some(*port),
+            process: Some(process.to_string()),
+            pid: Some(pid.to_string()),
+            message: None,
+        };
+        let mut events: Vec<Event> = previous
+            .difference(&current)
+            .map(|l| event("close", l))
+            .collect();
+        events.extend(current.difference(&previous).map(|l| event("listen", l)));
+        events
+    }
+}
+
+/// Shift `path` to `.1`, `.1` to `.2`, ..., dropping the oldest
+fn rotate(path: &Path, keep: usize) -> Result<()> {
+    let numbered = |n: usize| PathBuf::from(format!("{}.{}", path.display(), n));
+    if keep == 0 {
+        return std::fs::remove_file(path)
+            .with_context(|| format!("Failed to rotate {}", path.display()));
+    }
+    for n in (1..keep).rev() {
+        if numbered(n).exists() {
+            std::fs::rename(numbered(n), numbered(n + 1))?;
+        }
+    }
+    std::fs::rename(path, numbered(1))
+        .with_context(|| format!("Failed to rotate {}", path.display()))
+}
+
+impl Sink {
+    /// `events.jsonl` in the state directory
+    #[cfg(feature = "daemon")]
+    pub fn state() -> Result<Self> {
+        Ok(Self {
+            path: path()?,
+            max_bytes: Some(MAX_BYTES),
+            keep: KEEP,
+        })
+    }
+
+    /// A file given with `--output-file`; `rotate_mb` 0 never rotates
+    pub fn file(path: PathBuf, truncate: bool, rotate_mb: u64, keep: usize) -> Result<Self> {
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent)?;
+        }
+        if truncate {
+            std::fs::write(&path, "")
+                .with_context(|| format!("Failed to truncate {}", path.display()))?;
+        }
+        Ok(Self {
+            path,
+            max_bytes: (rotate_mb > 0).then(|| rotate_mb * 1024 * 1024),
+            keep,
+        })
+    }
+
+    /// Append events, rotating the file first when it is too large
+    pub fn append(&self, events: &[Event]) -> Result<()> {
+        if events.is_empty() {
+            return Ok(());
+        }
+        let path = &self.path;
+        std::fs::create_dir_all(path.parent().context("Invalid events path")?)?;
+        if self
+            .max_bytes
+            .is_some_and(|max| std::fs::metadata(path).is_ok_and(|m| m.len() >= max))
+        {
+            rotate(path, self.keep)?;
+        }
+
+        let mut lines = String::new();
+        for event in events {
+            lines.push_str(&serde_json::to_string(event)?);
+            lines.push('\n');
+        }
+        std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .and_then(|mut f| f.write_all(lines.as_bytes()))
+            .with_context(|| format!("Failed to write {}", path.display()))
+    }
+}
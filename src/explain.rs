@@ -0,0 +1,400 @@
+//! Detailed explanation of what is (or was recently) using a port
+
+use crate::theme::Themed;
+use anyhow::{Context, Result};
+use colored::Colorize;
+use std::process::Command;
+
+/// A TCP socket in a closing state (TIME_WAIT, FIN_WAIT_1/2) on the queried port
+#[derive(Debug, Clone)]
+pub struct ClosingSocket {
+    pub state: String,
+    pub peer: String,
+    /// Seconds until the kernel releases the socket, when known
+    pub clears_in: Option<u64>,
+}
+
+/// Accept queue of a listening TCP socket
+#[derive(Debug, Clone)]
+pub struct ListenQueue {
+    pub local: String,
+    pub port: u16,
+    /// Connections waiting for accept()
+    pub queued: u32,
+    /// Listen backlog (maximum queue length)
+    pub backlog: u32,
+}
+
+impl ListenQueue {
+    pub fn is_full(&self) -> bool {
+        self.queued > 0 && self.queued >= self.backlog
+    }
+}
+
+/// `explain --pid`: explain each port held by the processes
+pub fn run_pids(pids: &[u32]) -> Result<()> {
+    let mut ports: Vec<u16> = crate::get_port_info()?
+        .iter()
+        .filter(|info| crate::holds_pid(info, pids))
+        .map(|info| info.port)
+        .collect();
+    ports.sort_unstable();
+    ports.dedup();
+    let pids: Vec<String> = pids.iter().map(u32::to_string).collect();
+    anyhow::ensure!(
+        !ports.is_empty(),
+        "PID {} holds no listening port",
+        pids.join(", ")
+    );
+    for (i, port) in ports.into_iter().enumerate() {
+        if i > 0 {
+            println!();
+        }
+        run(port)?;
+    }
+    Ok(())
+}
+
+/// Local processes with established connections to the database on `port`
+fn print_db_clients(port: u16) {
+    let clients = crate::db_clients::collect(port);
+    if clients.is_empty() {
+        println!("  No local client is connected");
+        return;
+    }
+    let total: usize = clients.iter().map(|c| c.connections).sum();
+    println!(
+        "  Connected clients ({} connection(s) from {} process(es)):",
+        total,
+        clients.len()
+    );
+    for client in &clients {
+        println!(
+            "    {} {}  {}",
+            client.process.success(),
+            crate::format_pid(&client.pid).muted(),
+            format!("x{}", client.connections).bold()
+        );
+    }
+}
+
+/// What a debugger port is, and the `ws://` URLs to attach to
+fn print_debugger(debugger: crate::inspector::Debugger, port: u16) {
+    println!("      {}", debugger.label().label());
+    for target in crate::inspector::targets(port) {
+        println!(
+            "      {} {}  {}",
+            "DevTools:".muted(),
+            target.websocket_url.underline(),
+            target.title.muted()
+        );
+    }
+    if debugger == crate::inspector::Debugger::Node && port == crate::inspector::NODE_DEFAULT_PORT {
+        println!(
+            "      {} Another `node --inspect` fails to bind {}; give it `--inspect=0` (any free port) or `--inspect=<port>`.",
+            "Note:".bold(),
+            port
+        );
+    }
+}
+
+/// Print listeners and lingering closing sockets for `port`
+pub fn run(port: u16) -> Result<()> {
+    let listeners = crate::filter_port_infos(crate::get_port_info()?, Some(port), None, None);
+
+    println!("{}", format!("Port {}", port).accent().bold());
+
+    if listeners.is_empty() {
+        println!("  Nothing is listening");
+    } else {
+        println!("  Listening:");
+        for info in &listeners {
+            println!(
+                "    {} {}  {}  started {}",
+                info.process.success(),
+                crate::format_pid(&info.pid).muted(),
+                info.address,
+                info.start_time
+            );
+            let owner = crate::defunct::detect(&info.pid, &info.command);
+            match &owner {
+                Some(owner) => println!("      {}", owner.label(&info.pid).label()),
+                None => println!("      {}", info.command.muted()),
+            }
+            if let Some(owner) = owner {
+                println!("      {} {}", "Note:".bold(), owner.remedy());
+            }
+            for (label, url) in crate::devlinks::links(info) {
+                println!(
+                    "      {} {}",
+                    format!("{}:", label).muted(),
+                    url.underline()
+                );
+            }
+            if let Some(debugger) = crate::inspector::detect(port, &info.command) {
+                print_debugger(debugger, port);
+            }
+        }
+        if listeners
+            .iter()
+            .any(|i| crate::well_known::is_airplay_receiver(port, &i.process))
+        {
+            println!();
+            println!(
+                "  {} This is the macOS AirPlay Receiver (ControlCenter), not your app.",
+                "Note:".bold()
+            );
+            println!(
+                "  To free port {}: {}",
+                port,
+                crate::well_known::AIRPLAY_FIX
+            );
+        }
+    }
+
+    let processes: Vec<&str> = listeners.iter().map(|i| &*i.process).collect();
+    if crate::db_clients::is_database(port, &processes) {
+        print_db_clients(port);
+    }
+
+    let queues: Vec<ListenQueue> = listen_queues()
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|q| q.port == port)
+        .collect();
+    if !queues.is_empty() {
+        println!("  Listen sockets:");
+        for queue in &queues {
+            // Several sockets bound to the same address can only come from SO_REUSEPORT
+            let group = queues.iter().filter(|q| q.local == queue.local).count();
+            let mut notes = Vec::new();
+            if group > 1 {
+                notes.push(format!("SO_REUSEPORT group of {}", group));
+            }
+            if queue.is_full() {
+                notes.push("accept queue full, new connections are dropped".to_string());
+            }
+            println!(
+                "    {}  queue {}/{}  {}",
+                queue.local,
+                queue.queued,
+                queue.backlog,
+                notes.join(", ").warning()
+            );
+        }
+        if listeners.is_empty() {
+            println!(
+                "  {} No visible process owns these sockets (a defunct process's thread, the kernel, or another user's process). {}",
+                "Note:".bold(),
+                crate::defunct::Owner::Exited.remedy()
+            );
+        }
+    }
+
+    let closing = closing_sockets(port).unwrap_or_default();
+    if !closing.is_empty() {
+        println!("  Closing connections ({}):", closing.len());
+        for socket in &closing {
+            let clears = match socket.clears_in {
+                Some(secs) => format!("clears in ~{}s", secs),
+                None => "clear time unknown".to_string(),
+            };
+            println!(
+                "    {:<11} {}  {}",
+                socket.state.warning(),
+                socket.peer,
+                clears.muted()
+            );
+        }
+
+        if listeners.is_empty() {
+            let max_wait = closing.iter().filter_map(|s| s.clears_in).max();
+            println!();
+            println!(
+                "  {} A bind() without SO_REUSEADDR can fail with EADDRINUSE until these clear{}.",
+                "Hint:".bold(),
+                max_wait.map(|s| format!(" (~{}s)", s)).unwrap_or_default()
+            );
+        }
+    }
+
+    Ok(())
+}
+
+/// Accept queues of all listening TCP sockets
+pub fn listen_queues() -> Result<Vec<ListenQueue>> {
+    if cfg!(target_os = "linux") {
+        listen_queues_ss()
+    } else {
+        listen_queues_netstat()
+    }
+}
+
+/// Linux: for listening sockets `ss` reports the accept queue as Recv-Q and the backlog as Send-Q
+fn listen_queues_ss() -> Result<Vec<ListenQueue>> {
+    let output = Command::new("ss")
+        .arg("-tln")
+        .output()
+        .context("Failed to execute ss command")?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    Ok(stdout
+        .lines()
+        .skip(1)
+        .filter_map(|line| {
+            let parts: Vec<_> = line.split_whitespace().collect();
+            if parts.len() < 4 {
+                return None;
+            }
+            let (_, port) = parts[3].rsplit_once(':')?;
+            Some(ListenQueue {
+                local: parts[3].to_string(),
+                port: port.parse().ok()?,
+                queued: parts[1].parse().ok()?,
+                backlog: parts[2].parse().ok()?,
+            })
+        })
+        .collect())
+}
+
+/// macOS/BSD: `netstat -Lan` prints `qlen/incqlen/maxqlen  address.port`
+fn listen_queues_netstat() -> Result<Vec<ListenQueue>> {
+    let output = Command::new("netstat")
+        .args(["-Lan", "-p", "tcp"])
+        .output()
+        .context("Failed to execute netstat command")?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    Ok(stdout
+        .lines()
+        .filter_map(|line| {
+            let parts: Vec<_> = line.split_whitespace().collect();
+            // FreeBSD prefixes the protocol (`tcp4 0/0/128 *.3000`)
+            let (queue, local) = match parts.as_slice() {
+                [queue, local] => (*queue, *local),
+                [_, queue, local] => (*queue, *local),
+                _ => return None,
+            };
+            let lengths: Vec<u32> = queue.split('/').filter_map(|n| n.parse().ok()).collect();
+            let [queued, .., backlog] = lengths.as_slice() else {
+                return None;
+            };
+            let (_, port) = local.rsplit_once('.')?;
+            Some(ListenQueue {
+                local: local.to_string(),
+                port: port.parse().ok()?,
+                queued: *queued,
+                backlog: *backlog,
+            })
+        })
+        .collect())
+}
+
+pub fn closing_sockets(port: u16) -> Result<Vec<ClosingSocket>> {
+    if cfg!(target_os = "linux") {
+        closing_sockets_ss(port)
+    } else {
+        closing_sockets_netstat(port)
+    }
+}
+
+/// Linux: `ss -tan -o` reports per-socket timers, e.g. `timer:(timewait,52sec,0)`
+fn closing_sockets_ss(port: u16) -> Result<Vec<ClosingSocket>> {
+    let output = Command::new("ss")
+        .args(["-tan", "-o"])
+        .output()
+        .context("Failed to execute ss command")?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    Ok(stdout
+        .lines()
+        .skip(1)
+        .filter_map(|line| {
+            let parts: Vec<_> = line.split_whitespace().collect();
+            if parts.len() < 5 || !is_closing_state(parts[0]) {
+                return None;
+            }
+            let (_, local_port) = parts[3].rsplit_once(':')?;
+            if local_port.parse::<u16>().ok()? != port {
+                return None;
+            }
+            Some(ClosingSocket {
+                state: parts[0].replace('-', "_"),
+                peer: parts[4].to_string(),
+                clears_in: parts.get(5).and_then(|timer| parse_ss_timer(timer)),
+            })
+        })
+        .collect())
+}
+
+/// Parse the remaining seconds from `timer:(timewait,52sec,0)` or `timer:(timewait,1min2sec,0)`
+fn parse_ss_timer(timer: &str) -> Option<u64> {
+    let mut rest = timer.strip_prefix("timer:(")?.split(',').nth(1)?;
+
+    let mut secs = 0.0;
+    while !rest.is_empty() {
+        let unit_start = rest.find(|c: char| !c.is_ascii_digit() && c != '.')?;
+        let unit_end = rest[unit_start..]
+            .find(|c: char| c.is_ascii_digit())
+            .map_or(rest.len(), |i| unit_start + i);
+        let value: f64 = rest[..unit_start].parse().ok()?;
+        secs += match &rest[unit_start..unit_end] {
+            "min" => value * 60.0,
+            "sec" => value,
+            "ms" => value / 1000.0,
+            _ => return None,
+        };
+        rest = &rest[unit_end..];
+    }
+    Some(secs.ceil() as u64)
+}
+
+/// macOS/BSD: `netstat -an -p tcp` has no timers, so estimate 2*MSL
+fn closing_sockets_netstat(port: u16) -> Result<Vec<ClosingSocket>> {
+    let output = Command::new("netstat")
+        .args(["-an", "-p", "tcp"])
+        .output()
+        .context("Failed to execute netstat command")?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let estimate = time_wait_duration();
+
+    Ok(stdout
+        .lines()
+        .filter_map(|line| {
+            let parts: Vec<_> = line.split_whitespace().collect();
+            if parts.len() < 6 || !is_closing_state(parts[5]) {
+                return None;
+            }
+            // Addresses look like `127.0.0.1.3000` on macOS
+            let (_, local_port) = parts[3].rsplit_once('.')?;
+            if local_port.parse::<u16>().ok()? != port {
+                return None;
+            }
+            Some(ClosingSocket {
+                state: parts[5].to_string(),
+                peer: parts[4].to_string(),
+                clears_in: estimate,
+            })
+        })
+        .collect())
+}
+
+fn is_closing_state(state: &str) -> bool {
+    matches!(
+        state.replace('-', "_").as_str(),
+        "TIME_WAIT" | "FIN_WAIT_1" | "FIN_WAIT_2" | "FIN_WAIT1" | "FIN_WAIT2"
+    )
+}
+
+/// Upper bound of TIME_WAIT (2 * MSL) from `sysctl net.inet.tcp.msl` (milliseconds)
+fn time_wait_duration() -> Option<u64> {
+    let output = Command::new("sysctl")
+        .args(["-n", "net.inet.tcp.msl"])
+        .output()
+        .ok()?;
+    let msl_ms: u64 = String::from_utf8_lossy(&output.stdout)
+        .trim()
+        .parse()
+        .ok()?;
+    Some(msl_ms * 2 / 1000)
+}
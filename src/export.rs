@@ -0,0 +1,107 @@
+//! `history export`: dump the recorded port sessions for notebooks and spreadsheets
+//!
+//! Columns use the keys of `history/sessions.jsonl` (`port`, `process`, `pid`,
+//! `start`, `end`, `project`); timestamps stay Unix seconds.
+
+use crate::history::{self, SessionRecord};
+use anyhow::{Context, Result};
+use clap::ValueEnum;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ExportFormat {
+    /// Comma-separated values with a header row
+    Csv,
+    /// One JSON object per line
+    Jsonl,
+}
+
+pub const FIELDS: &[&str] = &["port", "process", "pid", "start", "end", "project"];
+
+/// Seconds in an age like `30d`, `12h`, `2w` or `90m`
+pub fn parse_age(age: &str) -> Result<u64> {
+    let age = age.trim();
+    let split = age.find(|c: char| !c.is_ascii_digit()).unwrap_or(age.len());
+    let value: u64 = age[..split]
+        .parse()
+        .with_context(|| format!("Invalid age '{}' (e.g. 30d, 12h, 2w)", age))?;
+    let unit = match &age[split..] {
+        "s" => 1,
+        "m" => 60,
+        "h" => 3600,
+        "d" => 24 * 3600,
+        "w" => 7 * 24 * 3600,
+        other => anyhow::bail!(
+            "Unknown unit '{}' in '{}' (use s, m, h, d or w)",
+            other,
+            age
+        ),
+    };
+    Ok(value * unit)
+}
+
+fn value(record: &SessionRecord, field: &str) -> serde_json::Value {
+    match field {
+        "port" => record.port.into(),
+        "process" => record.process.clone().into(),
+        "pid" => record.pid.clone().into(),
+        "start" => record.start.into(),
+        "end" => record.end.into(),
+        "project" => record.project.clone().into(),
+        _ => serde_json::Value::Null,
+    }
+}
+
+/// Quote a CSV cell when it contains a separator, quote or line break
+fn csv_cell(value: &serde_json::Value) -> String {
+    let text = match value {
+        serde_json::Value::Null => String::new(),
+        serde_json::Value::String(s) => s.clone(),
+        other => other.to_string(),
+    };
+    if text.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text
+    }
+}
+
+pub fn run(format: ExportFormat, since: &str, fields: &[String]) -> Result<()> {
+    let fields: Vec<&str> = if fields.is_empty() {
+        FIELDS.to_vec()
+    } else {
+        fields.iter().map(String::as_str).collect()
+    };
+    for field in &fields {
+        anyhow::ensure!(
+            FIELDS.contains(field),
+            "Unknown field '{}' (fields: {})",
+            field,
+            FIELDS.join(", ")
+        );
+    }
+
+    let cutoff = history::now().saturating_sub(parse_age(since)?);
+    let records: Vec<SessionRecord> = history::read(history::SESSIONS)?;
+    let records = records.iter().filter(|r| r.end >= cutoff);
+
+    match format {
+        ExportFormat::Csv => {
+            println!("{}", fields.join(","));
+            for record in records {
+                let cells: Vec<String> =
+                    fields.iter().map(|f| csv_cell(&value(record, f))).collect();
+                println!("{}", cells.join(","));
+            }
+        }
+        ExportFormat::Jsonl => {
+            for record in records {
+                let object: serde_json::Map<String, serde_json::Value> = fields
+                    .iter()
+                    .map(|f| (f.to_string(), value(record, f)))
+                    .collect();
+                println!("{}", serde_json::Value::Object(object));
+            }
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,34 @@
+//! Recorded command outputs, so the parsers can be tested without the real tools
+//!
+//! With `LWP_FIXTURES=<dir>`, the collector reads `<dir>/lsof.txt` (or `sockstat.txt`,
+//! `fstat.txt`) instead of running the tool, `ps` output comes from `<dir>/ps.txt` and
+//! the interface list from `<dir>/ip.txt`; a missing file counts as empty output.
+//! `LWP_BACKEND` picks the parser, so the BSD backends run on any OS, and
+//! `LWP_PROC_ROOT` points the `/proc` reads at a fake tree. See `tests/fixtures`.
+
+use std::path::PathBuf;
+
+fn dir() -> Option<PathBuf> {
+    std::env::var_os("LWP_FIXTURES")
+        .filter(|d| !d.is_empty())
+        .map(PathBuf::from)
+}
+
+/// Whether commands are replaced by recorded outputs
+pub fn active() -> bool {
+    dir().is_some()
+}
+
+/// Recorded output of `name`, or `None` when not running against fixtures
+pub fn output(name: &str) -> Option<String> {
+    let path = dir()?.join(format!("{}.txt", name));
+    Some(std::fs::read_to_string(path).unwrap_or_default())
+}
+
+/// `/proc`, or `LWP_PROC_ROOT`
+pub fn proc_path(path: &str) -> PathBuf {
+    let root = std::env::var_os("LWP_PROC_ROOT")
+        .filter(|r| !r.is_empty())
+        .map_or_else(|| PathBuf::from("/proc"), PathBuf::from);
+    root.join(path)
+}
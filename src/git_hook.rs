@@ -0,0 +1,104 @@
+//! `hook install`: a git hook that runs `verify` before commits or pushes
+//!
+//! The hook skips itself when the binary isn't on PATH, so clones without
+//! lsof-work-ports are not blocked.
+
+use crate::theme::Themed;
+use anyhow::{Context, Result};
+use clap::ValueEnum;
+use std::path::PathBuf;
+use std::process::Command;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum GitHook {
+    PreCommit,
+    PrePush,
+}
+
+impl GitHook {
+    fn name(self) -> &'static str {
+        match self {
+            GitHook::PreCommit => "pre-commit",
+            GitHook::PrePush => "pre-push",
+        }
+    }
+
+    /// The git command whose `--no-verify` skips the hook
+    fn git_command(self) -> &'static str {
+        match self {
+            GitHook::PreCommit => "commit",
+            GitHook::PrePush => "push",
+        }
+    }
+}
+
+/// Marks hooks written by this command, which may be overwritten without --force
+const MARKER: &str = "# Installed by lsof-work-ports hook install";
+
+fn script(hook: GitHook) -> String {
+    format!(
+        r#"#!/bin/sh
+{MARKER}
+# Checks the ports of compose files and devcontainer.json against ports.toml.
+[ -n "$LWP_SKIP_VERIFY" ] && exit 0
+command -v lsof-work-ports >/dev/null 2>&1 || exit 0
+lsof-work-ports verify && exit 0
+echo >&2
+echo "{name}: ports outside the ranges of ports.toml." >&2
+echo "Move them into the allocated ranges, or skip this check once with" >&2
+echo "  git {git_command} --no-verify" >&2
+echo "  LWP_SKIP_VERIFY=1 git {git_command}" >&2
+exit 1
+"#,
+        name = hook.name(),
+        git_command = hook.git_command(),
+    )
+}
+
+/// `.git/hooks`, or `core.hooksPath` when set
+fn hooks_dir() -> Result<PathBuf> {
+    let output = Command::new("git")
+        .args(["rev-parse", "--git-path", "hooks"])
+        .output()
+        .context("Failed to run git")?;
+    anyhow::ensure!(output.status.success(), "Not inside a git repository");
+    Ok(PathBuf::from(
+        String::from_utf8_lossy(&output.stdout).trim_end(),
+    ))
+}
+
+pub fn install(hook: GitHook, force: bool) -> Result<()> {
+    let dir = hooks_dir()?;
+    let path = dir.join(hook.name());
+    if let Ok(existing) = std::fs::read_to_string(&path) {
+        anyhow::ensure!(
+            force || existing.contains(MARKER),
+            "{} already exists; use --force to replace it",
+            path.display()
+        );
+    }
+    std::fs::create_dir_all(&dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+    std::fs::write(&path, script(hook))
+        .with_context(|| format!("Failed to write {}", path.display()))?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755))?;
+    }
+
+    println!(
+        "{} Installed {} hook at {}",
+        crate::output::glyphs().check.success(),
+        hook.name().accent(),
+        path.display()
+    );
+    println!(
+        "{}",
+        format!(
+            "  Bypass once with `git {} --no-verify` or LWP_SKIP_VERIFY=1",
+            hook.git_command()
+        )
+        .muted()
+    );
+    Ok(())
+}
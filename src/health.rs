@@ -0,0 +1,161 @@
+//! Health checks for the monitored ports (`[[ports]]` entries with `http = "/healthz"`)
+
+use crate::theme::Themed;
+use anyhow::Result;
+use colored::Colorize;
+use std::process::Command;
+
+/// Responses slower than this count as degraded
+const SLOW_RESPONSE_SECS: f64 = 1.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Health {
+    Up,
+    Degraded,
+    Down,
+}
+
+impl Health {
+    pub fn text(self) -> &'static str {
+        match self {
+            Health::Up => "up",
+            Health::Degraded => "degraded",
+            Health::Down => "down",
+        }
+    }
+
+    pub fn label(self) -> colored::ColoredString {
+        match self {
+            Health::Up => self.text().success(),
+            Health::Degraded => self.text().warning(),
+            Health::Down => self.text().error(),
+        }
+    }
+}
+
+/// Probe `http://localhost:<port><path>` with curl, returning the status code and seconds taken
+fn probe(port: u16, path: &str) -> Option<(u16, f64)> {
+    let url = format!("http://localhost:{}{}", port, path);
+    let output = Command::new("curl")
+        .args(["-s", "-o", "/dev/null", "--max-time", "3"])
+        .args(["-w", "%{http_code} %{time_total}", &url])
+        .output()
+        .ok()?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let (code, time) = stdout.trim().split_once(' ')?;
+    let code = code.parse().ok().filter(|&c| c != 0)?;
+    Some((code, time.parse().unwrap_or_default()))
+}
+
+/// Check one listening port against an entry's health check
+fn check(port: u16, entry: &crate::PortEntry) -> (Health, String) {
+    let Some(path) = &entry.http else {
+        return (Health::Up, "listening".to_string());
+    };
+    let expected = entry.status.unwrap_or(200);
+    match probe(port, path) {
+        None => (Health::Down, format!("GET {} failed", path)),
+        Some((code, _)) if code != expected => (
+            Health::Degraded,
+            format!(
+                "GET {} {} {} (expected {})",
+                path,
+                crate::output::glyphs().arrow,
+                code,
+                expected
+            ),
+        ),
+        Some((code, secs)) if secs > SLOW_RESPONSE_SECS => (
+            Health::Degraded,
+            format!(
+                "GET {} {} {} in {:.1}s",
+                path,
+                crate::output::glyphs().arrow,
+                code,
+                secs
+            ),
+        ),
+        Some((code, secs)) => (
+            Health::Up,
+            format!(
+                "GET {} {} {} in {:.0}ms",
+                path,
+                crate::output::glyphs().arrow,
+                code,
+                secs * 1000.0
+            ),
+        ),
+    }
+}
+
+/// Run the health checks of the listeners in `[[ports]]` entries with `http`
+/// concurrently and store the result in `health` (the `--watch` column)
+pub fn annotate<'a>(
+    config: &crate::Config,
+    infos: impl Iterator<Item = &'a mut crate::GroupedPortInfo>,
+) {
+    let entries = config.port_entries();
+    let checked = |port: u16| {
+        entries.iter().copied().find(|entry| {
+            entry.http.is_some() && entry.expand().is_ok_and(|ports| ports.contains(&port))
+        })
+    };
+    let mut infos: Vec<(&mut crate::GroupedPortInfo, &crate::PortEntry)> = infos
+        .filter(|info| {
+            info.protocol == crate::collector::Protocol::Tcp
+                && info.state == crate::collector::LISTEN
+        })
+        .filter_map(|info| {
+            let entry = checked(info.port)?;
+            Some((info, entry))
+        })
+        .collect();
+    let targets: Vec<(u16, &crate::PortEntry)> = infos
+        .iter()
+        .map(|(info, entry)| (info.port, *entry))
+        .collect();
+    let results = crate::pool::map(&targets, |(port, entry)| check(*port, entry).0);
+    for ((info, _), health) in infos.iter_mut().zip(results) {
+        info.health = Some(health);
+    }
+}
+
+/// Report up/degraded/down for every monitored port
+pub fn run() -> Result<()> {
+    let config = crate::Config::load()?;
+    anyhow::ensure!(
+        !config.port_entries().is_empty(),
+        "No monitored ports; add [[ports]] entries to the config"
+    );
+    let bound: Vec<u16> = crate::get_port_info()?.iter().map(|i| i.port).collect();
+
+    for entry in config.port_entries() {
+        let label = entry.name.as_deref().unwrap_or(&entry.ports);
+        let listening: Vec<u16> = entry
+            .expand()?
+            .into_iter()
+            .filter(|p| bound.contains(p))
+            .collect();
+
+        if listening.is_empty() {
+            println!(
+                "{} {}  {}",
+                format!("{:<11}", entry.ports).accent().bold(),
+                label.bold(),
+                Health::Down.label()
+            );
+            continue;
+        }
+        for port in listening {
+            let (health, detail) = check(port, entry);
+            println!(
+                "{} {}  {} {}",
+                format!(":{:<10}", port).accent().bold(),
+                label.bold(),
+                health.label(),
+                detail.muted()
+            );
+        }
+    }
+    Ok(())
+}
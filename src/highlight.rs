@@ -0,0 +1,49 @@
+//! `--highlight`: make matching rows of the table stand out without hiding the others
+//!
+//! A pattern that parses as ports (`3000`, `3000-3010`, `3000,8080`) matches by port,
+//! anything else matches process names like `--process` (case-insensitive substring).
+//! `--watch` also highlights the ports that appeared since its last refresh.
+
+use std::sync::{Arc, Mutex, OnceLock};
+
+enum Pattern {
+    Ports(Vec<u16>),
+    Process(crate::ProcessFilter),
+}
+
+static PATTERNS: OnceLock<Vec<Pattern>> = OnceLock::new();
+
+static APPEARED: Mutex<Vec<u16>> = Mutex::new(Vec::new());
+
+pub fn init(patterns: &[String]) {
+    let patterns = patterns
+        .iter()
+        .map(|pattern| match crate::expand_ports(pattern) {
+            Ok(ports) if !ports.is_empty() => Pattern::Ports(ports),
+            _ => Pattern::Process(crate::ProcessFilter {
+                name: pattern.clone(),
+                exact: false,
+                case_sensitive: false,
+            }),
+        })
+        .collect();
+    let _ = PATTERNS.set(patterns);
+}
+
+/// Highlight these ports too, replacing the ones set before
+pub fn set_appeared(ports: Vec<u16>) {
+    *APPEARED.lock().unwrap() = ports;
+}
+
+/// Whether a row with this port and these processes is highlighted
+pub fn matches(port: u16, processes: &[Arc<str>]) -> bool {
+    if APPEARED.lock().unwrap().contains(&port) {
+        return true;
+    }
+    PATTERNS.get().is_some_and(|patterns| {
+        patterns.iter().any(|pattern| match pattern {
+            Pattern::Ports(ports) => ports.contains(&port),
+            Pattern::Process(filter) => processes.iter().any(|p| filter.matches(p)),
+        })
+    })
+}
@@ -0,0 +1,122 @@
+//! Append-only history kept as JSON lines in the state dir
+
+use anyhow::{Context, Result};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::io::Write;
+
+/// Time a supervised command took from spawn until its port was bound
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StartupRecord {
+    /// Supervisor entry name
+    pub name: String,
+    pub port: u16,
+    /// Unix timestamp of the spawn
+    pub started_at: u64,
+    pub startup_ms: u64,
+}
+
+pub const STARTUP: &str = "startup.jsonl";
+
+/// A period during which a process listened on a port, recorded by the daemon
+#[cfg(any(feature = "daemon", feature = "history"))]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionRecord {
+    pub port: u16,
+    pub process: String,
+    pub pid: String,
+    /// Unix timestamps of the first and last snapshot with the listener
+    pub start: u64,
+    pub end: u64,
+    /// Name of the process's working directory
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub project: Option<String>,
+}
+
+#[cfg(any(feature = "daemon", feature = "history"))]
+pub const SESSIONS: &str = "sessions.jsonl";
+
+/// Open sessions across daemon snapshots; closed ones are appended to `SESSIONS`
+#[cfg(feature = "daemon")]
+#[derive(Default)]
+pub struct Sessions {
+    open: std::collections::HashMap<(u16, std::sync::Arc<str>), SessionRecord>,
+}
+
+#[cfg(feature = "daemon")]
+impl Sessions {
+    pub fn update(&mut self, port_infos: &[crate::PortInfo]) -> Result<()> {
+        let now = now();
+        for info in port_infos {
+            let record = self
+                .open
+                .entry((info.port, info.pid.clone()))
+                .or_insert_with(|| SessionRecord {
+                    port: info.port,
+                    process: info.process.to_string(),
+                    pid: info.pid.to_string(),
+                    start: now,
+                    end: now,
+                    project: crate::get_process_cwd(&info.pid).and_then(|cwd| {
+                        std::path::Path::new(&cwd)
+                            .file_name()
+                            .map(|n| n.to_string_lossy().into_owned())
+                    }),
+                });
+            record.end = now;
+        }
+
+        let closed: Vec<(u16, std::sync::Arc<str>)> = self
+            .open
+            .keys()
+            .filter(|(port, pid)| !port_infos.iter().any(|i| i.port == *port && i.pid == *pid))
+            .cloned()
+            .collect();
+        for key in closed {
+            if let Some(record) = self.open.remove(&key) {
+                append(SESSIONS, &record)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn path(file: &str) -> Result<std::path::PathBuf> {
+    Ok(crate::paths::state_dir()?.join("history").join(file))
+}
+
+/// Append one record
+pub fn append<T: Serialize>(file: &str, record: &T) -> Result<()> {
+    let path = path(file)?;
+    std::fs::create_dir_all(path.parent().context("Invalid history path")?)?;
+    let mut line = serde_json::to_string(record)?;
+    line.push('\n');
+    // A single small append is atomic enough for concurrent writers
+    std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .and_then(|mut f| f.write_all(line.as_bytes()))
+        .with_context(|| format!("Failed to write {}", path.display()))
+}
+
+/// Read every record, skipping lines that don't parse
+pub fn read<T: DeserializeOwned>(file: &str) -> Result<Vec<T>> {
+    let path = path(file)?;
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let content = std::fs::read_to_string(&path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    Ok(content
+        .lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect())
+}
+
+/// Seconds since the Unix epoch
+pub fn now() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
@@ -0,0 +1,137 @@
+//! `hold`: run a dev server in the background, restart it when it crashes and
+//! show which ports it owns in the listing
+
+use crate::theme::Themed;
+use anyhow::{Context, Result};
+use colored::Colorize;
+
+/// Default entry name: the program's basename, plus the port if one is given
+fn default_name(command: &[String], port: Option<u16>) -> String {
+    let program = command
+        .first()
+        .and_then(|c| c.rsplit('/').next())
+        .unwrap_or("hold");
+    match port {
+        Some(port) => format!("{}-{}", program, port),
+        None => program.to_string(),
+    }
+}
+
+/// Start `command` under supervision
+pub fn run(command: &[String], port: Option<u16>, name: Option<&str>, restart: bool) -> Result<()> {
+    anyhow::ensure!(
+        !command.is_empty(),
+        "No command given (usage: hold -- npm run dev)"
+    );
+
+    if let Some(port) = port {
+        let holders = crate::tcp_listeners(port)?;
+        if let Some(holder) = holders.first() {
+            println!(
+                "{}",
+                format!(
+                    "{} Port {} is already in use by {} {}",
+                    crate::output::glyphs().warn,
+                    port,
+                    holder.process,
+                    crate::format_pid(&holder.pid)
+                )
+                .warning()
+            );
+        }
+    }
+
+    let name = name.map_or_else(|| default_name(command, port), str::to_string);
+    let mut entry = crate::supervisor::Entry::new(name, "hold", port, command.to_vec());
+    entry.restart = restart;
+    let entry = crate::supervisor::start(entry)?;
+
+    println!(
+        "{} Holding {} ({}) {}",
+        crate::output::glyphs().check,
+        entry.name.success(),
+        command.join(" "),
+        crate::format_pid(&entry.pid.unwrap_or_default().to_string()).muted()
+    );
+    println!(
+        "Output is logged to {}",
+        crate::supervisor::log_path(&entry.name)?.display()
+    );
+    println!("Stop it with: lsof-work-ports hold --stop {}", entry.name);
+    Ok(())
+}
+
+/// Stop a held command
+pub fn stop(name: &str) -> Result<()> {
+    let entry = crate::supervisor::load()?
+        .into_iter()
+        .find(|e| e.name == name && e.kind == "hold")
+        .with_context(|| format!("{} is not a held command", name))?;
+    crate::supervisor::stop(&entry.name)?;
+    println!(
+        "{} Stopped {}",
+        crate::output::glyphs().check,
+        name.success()
+    );
+    Ok(())
+}
+
+/// Print a listing section for supervised entries of `kind`: each command with
+/// the ports its process group is listening on
+pub fn print_section(
+    title: &str,
+    kind: &str,
+    port_infos: &[crate::PortInfo],
+    header: bool,
+) -> Result<()> {
+    let held: Vec<_> = crate::supervisor::load()?
+        .into_iter()
+        .filter(|e| e.kind == kind)
+        .collect();
+    if held.is_empty() {
+        return Ok(());
+    }
+
+    if header {
+        println!();
+        println!("{}", title.heading().bold());
+    }
+    for entry in &held {
+        let running = entry.is_running();
+        let pids = crate::supervisor::group_pids(entry);
+        let mut ports: Vec<u16> = port_infos
+            .iter()
+            .filter(|i| pids.iter().any(|p| *p == *i.pid))
+            .map(|i| i.port)
+            .collect();
+        ports.sort_unstable();
+        ports.dedup();
+
+        let status = if !running {
+            "stopped".error()
+        } else if entry.port.is_some_and(|p| !ports.contains(&p)) || ports.is_empty() {
+            "starting".warning()
+        } else {
+            "up".success()
+        };
+        let ports = ports
+            .iter()
+            .map(|p| format!(":{}", p))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let restarts = if entry.restarts > 0 {
+            format!(" (restarts: {})", entry.restarts)
+        } else {
+            String::new()
+        };
+        println!(
+            "  {} {}  {} {}{}",
+            entry.name.success(),
+            ports.accent().bold(),
+            entry.command.join(" ").muted(),
+            status,
+            restarts.muted()
+        );
+    }
+    Ok(())
+}
@@ -0,0 +1,108 @@
+//! `hook` in the config: an external command that annotates, re-tags or drops rows
+//!
+//! The command gets one JSON object per port on stdin and prints the rows to keep,
+//! one per line. `label`, `dev_score` and `command` are taken over from its output;
+//! ports it doesn't print are dropped. Any language works, e.g. with jq:
+//!
+//! ```toml
+//! hook = "jq -c 'if .port == 5432 then .label = \"team db\" else . end'"
+//! ```
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Row {
+    port: u16,
+    #[serde(default)]
+    processes: Vec<String>,
+    #[serde(default)]
+    pids: Vec<String>,
+    #[serde(default)]
+    command: Option<String>,
+    #[serde(default)]
+    address: String,
+    #[serde(default)]
+    interface: String,
+    /// "TCP" or "UDP"; rows without it apply to both
+    #[serde(default)]
+    protocol: String,
+    #[serde(default)]
+    dev_score: Option<u32>,
+    #[serde(default)]
+    label: Option<String>,
+}
+
+/// Pass the rows through the hook command
+pub fn apply(
+    hook: &str,
+    infos: Vec<crate::GroupedPortInfo>,
+) -> Result<Vec<crate::GroupedPortInfo>> {
+    let mut input = String::new();
+    for info in &infos {
+        let row = Row {
+            port: info.port,
+            processes: info.processes.iter().map(|p| p.to_string()).collect(),
+            pids: info.pids.iter().map(|p| p.to_string()).collect(),
+            command: Some(info.command.clone()),
+            address: info.address.clone(),
+            interface: info.interface.clone(),
+            protocol: info.protocol.name().to_string(),
+            dev_score: Some(info.dev_score),
+            label: info.label.clone(),
+        };
+        input.push_str(&serde_json::to_string(&row)?);
+        input.push('\n');
+    }
+
+    let mut child = Command::new("sh")
+        .args(["-c", hook])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .with_context(|| format!("Failed to run hook: {}", hook))?;
+    // Write from a thread so a hook that prints early can't deadlock on a full pipe
+    let mut stdin = child.stdin.take().context("Failed to open hook stdin")?;
+    let writer = std::thread::spawn(move || stdin.write_all(input.as_bytes()));
+    let output = child
+        .wait_with_output()
+        .with_context(|| format!("Failed to run hook: {}", hook))?;
+    // A hook that exits without reading its input is fine
+    let _ = writer.join();
+    anyhow::ensure!(
+        output.status.success(),
+        "Hook failed ({}): {}",
+        output.status,
+        String::from_utf8_lossy(&output.stderr).trim()
+    );
+
+    let mut rows = Vec::new();
+    for line in String::from_utf8_lossy(&output.stdout).lines() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let row: Row = serde_json::from_str(line)
+            .with_context(|| format!("Hook printed an invalid row: {}", line))?;
+        rows.push(row);
+    }
+
+    Ok(infos
+        .into_iter()
+        .filter_map(|mut info| {
+            let row = rows.iter().find(|r| {
+                r.port == info.port && (r.protocol.is_empty() || r.protocol == info.protocol.name())
+            })?;
+            if let Some(command) = &row.command {
+                info.command = command.clone();
+            }
+            if let Some(score) = row.dev_score {
+                info.dev_score = score;
+            }
+            info.label = row.label.clone();
+            Some(info)
+        })
+        .collect())
+}
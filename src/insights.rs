@@ -0,0 +1,242 @@
+//! Opt-in usage insights, kept on this machine only
+//!
+//! With `insights = true` in the config, every run appends the subcommand and the
+//! names of the flags given (not their values, except the `--process` filter) to
+//! `history/usage.jsonl` in the state dir. `insights` summarizes the last 30 days and
+//! suggests features that fit how the tool is used. Nothing is ever sent anywhere.
+
+use crate::history;
+use crate::theme::Themed;
+use anyhow::Result;
+use clap::{ArgMatches, CommandFactory};
+use colored::Colorize;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::OnceLock;
+
+const USAGE: &str = "usage.jsonl";
+
+const WINDOW: u64 = 30 * 24 * 3600;
+
+/// Runs needed before a habit is worth a suggestion
+const MIN_RUNS: usize = 5;
+
+/// Share of listings with the same `--process` that suggests a saved query
+const PROCESS_SHARE: usize = 50;
+
+/// (used, related feature, tip), shown while the related feature was never used
+const RELATED: &[(&str, &str, &str)] = &[
+    (
+        "kill",
+        "explain",
+        "`explain <port>` shows the process tree, cwd and clients before you kill anything",
+    ),
+    (
+        "kill",
+        "related",
+        "`related <port>` lists the other ports of the same project, to stop them together",
+    ),
+    (
+        "--port",
+        "explain",
+        "`explain <port>` shows everything about a single port, including TIME_WAIT sockets",
+    ),
+    (
+        "up",
+        "logs",
+        "`logs <port>` shows recent output of the server behind a port",
+    ),
+];
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Usage {
+    time: u64,
+    /// Subcommand, `list` for the plain listing
+    command: String,
+    /// Long names of the flags given on the command line
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    flags: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    process: Option<String>,
+}
+
+impl Usage {
+    fn uses(&self, feature: &str) -> bool {
+        self.command == feature || self.flags.iter().any(|f| f == feature)
+    }
+}
+
+static ENABLED: OnceLock<bool> = OnceLock::new();
+
+/// Take the `insights` switch from an already loaded config instead of reading it again
+pub fn use_config(config: &crate::Config) {
+    let _ = ENABLED.set(config.insights);
+}
+
+fn enabled() -> bool {
+    // A broken config is reported by the commands that need it
+    *ENABLED.get_or_init(|| crate::Config::load().is_ok_and(|c| c.insights))
+}
+
+/// Flags given on the command line, as `--long` names (`-x` when there is no long name)
+fn given_flags(command: &clap::Command, matches: &ArgMatches) -> Vec<String> {
+    command
+        .get_arguments()
+        .filter(|arg| !arg.is_positional())
+        .filter(|arg| {
+            matches.value_source(arg.get_id().as_str())
+                == Some(clap::parser::ValueSource::CommandLine)
+        })
+        .filter_map(|arg| {
+            arg.get_long()
+                .map(|long| format!("--{}", long))
+                .or_else(|| arg.get_short().map(|short| format!("-{}", short)))
+        })
+        .collect()
+}
+
+/// Append this run to the usage log when insights are enabled
+pub fn record(matches: &ArgMatches) {
+    if matches.subcommand_name() == Some("insights") || !enabled() {
+        return;
+    }
+    let command = crate::Cli::command();
+    let mut flags = given_flags(&command, matches);
+    let name = match matches.subcommand() {
+        Some((name, sub_matches)) => {
+            if let Some(sub) = command.find_subcommand(name) {
+                flags.extend(given_flags(sub, sub_matches));
+            }
+            name.to_string()
+        }
+        None => "list".to_string(),
+    };
+    let usage = Usage {
+        time: history::now(),
+        command: name,
+        flags,
+        process: matches.get_one::<String>("process").cloned(),
+    };
+    // Insights must never get in the way of the command itself
+    let _ = history::append(USAGE, &usage);
+}
+
+/// Uses of each feature, most used first
+fn ranked<'a>(features: impl Iterator<Item = &'a str>) -> Vec<(&'a str, usize)> {
+    let mut counts: BTreeMap<&str, usize> = BTreeMap::new();
+    for feature in features {
+        *counts.entry(feature).or_default() += 1;
+    }
+    let mut ranked: Vec<(&str, usize)> = counts.into_iter().collect();
+    ranked.sort_by_key(|(_, count)| std::cmp::Reverse(*count));
+    ranked
+}
+
+fn suggestions(runs: &[Usage]) -> Vec<String> {
+    let mut tips = Vec::new();
+
+    let listings: Vec<&Usage> = runs.iter().filter(|u| u.command == "list").collect();
+    let processes = ranked(listings.iter().filter_map(|u| u.process.as_deref()));
+    if let Some((process, count)) = processes.first() {
+        let share = count * 100 / listings.len();
+        if *count >= MIN_RUNS && share >= PROCESS_SHARE {
+            tips.push(format!(
+                "You filter by --process {} in {}% of listings; save it as a query with \
+                 `{} = 'process =~ \"{}\"'` under [queries] and run `--query {}`",
+                process, share, process, process, process
+            ));
+        }
+    }
+
+    let where_runs = runs.iter().filter(|u| u.uses("--where")).count();
+    if where_runs >= MIN_RUNS && !runs.iter().any(|u| u.uses("--query")) {
+        tips.push(format!(
+            "You typed a --where expression {} times; name the ones you repeat under \
+             [queries] and use `--query <name>`",
+            where_runs
+        ));
+    }
+
+    for (used, related, tip) in RELATED {
+        let uses = runs.iter().filter(|u| u.uses(used)).count();
+        if uses >= MIN_RUNS && !runs.iter().any(|u| u.uses(related)) {
+            tips.push(tip.to_string());
+        }
+    }
+    tips.dedup();
+    tips
+}
+
+pub fn run(clear: bool) -> Result<()> {
+    if clear {
+        let path = crate::paths::state_dir()?.join("history").join(USAGE);
+        if path.exists() {
+            std::fs::remove_file(&path)?;
+        }
+        println!("{} Usage log cleared", crate::output::glyphs().check);
+        return Ok(());
+    }
+
+    let config = crate::Config::load()?;
+    if !config.insights {
+        println!("Usage insights are off.");
+        println!(
+            "{}",
+            format!(
+                "Add `insights = true` to {} to record which commands and flags you use; \
+                 the log stays on this machine.",
+                crate::paths::config_file()?.display()
+            )
+            .muted()
+        );
+        return Ok(());
+    }
+
+    let since = history::now().saturating_sub(WINDOW);
+    let runs: Vec<Usage> = history::read::<Usage>(USAGE)?
+        .into_iter()
+        .filter(|u| u.time >= since)
+        .collect();
+    if runs.is_empty() {
+        println!("No runs recorded in the last 30 days yet.");
+        return Ok(());
+    }
+
+    println!(
+        "{} {}",
+        "Usage insights".bold(),
+        format!("(last 30 days, {} runs)", runs.len()).muted()
+    );
+    println!();
+    println!("Commands:");
+    for (command, count) in ranked(runs.iter().map(|u| u.command.as_str()))
+        .iter()
+        .take(5)
+    {
+        println!(
+            "  {:<14} {:>4}  {}",
+            command.success(),
+            count,
+            format!("{}%", count * 100 / runs.len()).muted()
+        );
+    }
+    let flags = ranked(runs.iter().flat_map(|u| u.flags.iter().map(String::as_str)));
+    if !flags.is_empty() {
+        println!("Flags:");
+        for (flag, count) in flags.iter().take(5) {
+            println!("  {:<14} {:>4}", flag.label(), count);
+        }
+    }
+
+    let tips = suggestions(&runs);
+    println!();
+    if tips.is_empty() {
+        println!("{}", "No suggestions yet.".muted());
+    } else {
+        println!("Suggestions:");
+        for tip in tips {
+            println!("  {} {}", crate::output::glyphs().arrow.warning(), tip);
+        }
+    }
+    Ok(())
+}
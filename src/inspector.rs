@@ -0,0 +1,89 @@
+//! Debugger ports: the Node.js inspector (`--inspect`) and Chrome DevTools
+//! (`--remote-debugging-port`)
+//!
+//! Both speak the DevTools protocol and list their targets at `/json/list`, each with
+//! the `ws://` URL a debugger attaches to. Several `node --inspect` processes all
+//! want 9229, so these ports are labeled instead of passing as the app's own port.
+
+use std::process::Command;
+
+/// Port of `node --inspect` without an explicit port
+pub const NODE_DEFAULT_PORT: u16 = 9229;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Debugger {
+    Node,
+    Chrome,
+}
+
+impl Debugger {
+    pub fn label(self) -> &'static str {
+        match self {
+            Debugger::Node => "Node.js inspector (debugger)",
+            Debugger::Chrome => "Chrome DevTools protocol (debugger)",
+        }
+    }
+}
+
+/// `9229` from `--inspect`, `--inspect=9230`, `--inspect-brk=127.0.0.1:9231`, ...
+fn node_inspect_port(command: &str) -> Option<u16> {
+    let mut port = None;
+    for arg in command.split_whitespace() {
+        let Some(option) = [
+            "--inspect-brk",
+            "--inspect-wait",
+            "--inspect-port",
+            "--inspect",
+        ]
+        .iter()
+        .find(|option| arg == **option || arg.starts_with(&format!("{}=", option))) else {
+            continue;
+        };
+        port = match arg[option.len()..].strip_prefix('=') {
+            // `[host:]port`; port 0 picks a random one, which can't be matched here
+            Some(value) => value.rsplit(':').next().and_then(|p| p.parse().ok()),
+            None => port.or(Some(NODE_DEFAULT_PORT)),
+        };
+    }
+    port.filter(|p| *p != 0)
+}
+
+/// The debugger listening on `port`, judged from the command line of its process
+pub fn detect(port: u16, command: &str) -> Option<Debugger> {
+    if node_inspect_port(command) == Some(port) {
+        return Some(Debugger::Node);
+    }
+    let chrome_port = command
+        .split_whitespace()
+        .find_map(|arg| arg.strip_prefix("--remote-debugging-port="))
+        .and_then(|p| p.parse::<u16>().ok());
+    (chrome_port == Some(port)).then_some(Debugger::Chrome)
+}
+
+/// A debuggable target from `/json/list`
+pub struct Target {
+    pub title: String,
+    pub websocket_url: String,
+}
+
+/// The targets of the debugger on `port` (empty when it doesn't answer)
+pub fn targets(port: u16) -> Vec<Target> {
+    let url = format!("http://127.0.0.1:{}/json/list", port);
+    let Ok(output) = Command::new("curl")
+        .args(["-s", "--max-time", "2", &url])
+        .output()
+    else {
+        return Vec::new();
+    };
+    let Ok(list) = serde_json::from_slice::<Vec<serde_json::Value>>(&output.stdout) else {
+        return Vec::new();
+    };
+    list.iter()
+        .filter_map(|target| {
+            Some(Target {
+                title: target["title"].as_str().unwrap_or_default().to_string(),
+                websocket_url: target["webSocketDebuggerUrl"].as_str()?.to_string(),
+            })
+        })
+        .collect()
+}
@@ -0,0 +1,138 @@
+//! `--interactive`: redraw the listing and toggle entries with single key presses
+//!
+//! Not a full TUI: the terminal is switched to non-canonical mode with `stty` so keys
+//! arrive without Enter, and the screen is cleared before each redraw. Signals are
+//! turned off too, so Ctrl-C arrives as a key and quits like `q`; the saved settings
+//! are restored however the loop ends.
+
+use crate::theme::Themed;
+use anyhow::{Context, Result};
+use std::fs::File;
+use std::io::{IsTerminal, Read, Write};
+use std::process::Command;
+
+/// Run `stty` on the controlling terminal
+fn stty(args: &[&str]) -> Result<String> {
+    let tty = File::open("/dev/tty").context("No terminal to read keys from")?;
+    let output = Command::new("stty")
+        .args(args)
+        .stdin(tty)
+        .output()
+        .context("Failed to run stty")?;
+    anyhow::ensure!(output.status.success(), "stty {} failed", args.join(" "));
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// Terminal settings saved by `stty -g`, restored when dropped (also on errors and
+/// panics)
+struct RawMode {
+    saved: String,
+}
+
+impl RawMode {
+    fn enable() -> Result<Self> {
+        let saved = stty(&["-g"])?;
+        stty(&["-icanon", "-echo", "-isig", "min", "1"])?;
+        Ok(Self { saved })
+    }
+}
+
+impl Drop for RawMode {
+    fn drop(&mut self) {
+        let _ = stty(&[&self.saved]);
+    }
+}
+
+/// Persist section-wide expansion as the default for future listings
+fn save_prefs(expansion: &crate::Expansion) -> Result<()> {
+    let _lock = crate::Config::lock()?;
+    let mut config = crate::Config::load()?;
+    if let Some(&expanded) = expansion.multis.first() {
+        config.display.expand_multis = expanded;
+    }
+    if let Some(&expanded) = expansion.process_groups.first() {
+        config.display.expand_process_groups = expanded;
+    }
+    config.save()
+}
+
+/// `reclaim`: preferred port of a fallback chain, freed by killing its holder with `k`
+pub fn run(
+    expansion: &mut crate::Expansion,
+    reclaim: Option<u16>,
+    mut render: impl FnMut(&crate::Expansion),
+) -> Result<()> {
+    anyhow::ensure!(
+        std::io::stdout().is_terminal(),
+        "--interactive needs a terminal"
+    );
+    expansion.numbered = true;
+
+    let raw_mode = RawMode::enable()?;
+    let result = key_loop(expansion, reclaim, &mut render);
+    drop(raw_mode);
+    println!();
+    match result? {
+        Some(port) => crate::kill::run(
+            &crate::kill::Target::Port(port),
+            &crate::kill::Options {
+                mode: crate::kill::KillMode::Pid,
+                force: false,
+                all: false,
+                timeout: std::time::Duration::from_secs(5),
+            },
+        ),
+        None => Ok(()),
+    }
+}
+
+/// Handle keys until quit; `Some(port)` when the holder of `reclaim` is to be killed
+fn key_loop(
+    expansion: &mut crate::Expansion,
+    reclaim: Option<u16>,
+    render: &mut impl FnMut(&crate::Expansion),
+) -> Result<Option<u16>> {
+    let mut tty = File::open("/dev/tty").context("No terminal to read keys from")?;
+    loop {
+        print!("{}", crate::output::CLEAR_SCREEN);
+        render(expansion);
+        let sep = crate::output::glyphs().sep;
+        let kill_hint = reclaim
+            .map(|port| format!(" {sep} k kill the holder of :{port}"))
+            .unwrap_or_default();
+        print!(
+            "{}",
+            format!(
+                "1-9 toggle entry {sep} m toggle multis {sep} g toggle process groups{kill_hint} {sep} q quit"
+            )
+            .muted()
+        );
+        std::io::stdout().flush()?;
+
+        let mut key = [0u8; 1];
+        if tty.read(&mut key)? == 0 {
+            return Ok(None);
+        }
+        match key[0] {
+            // Ctrl-C and Ctrl-D, which don't raise signals here
+            b'q' | b'\n' | 0x1b | 0x03 | 0x04 => return Ok(None),
+            digit @ b'1'..=b'9' => expansion.toggle((digit - b'1') as usize),
+            b'm' => {
+                let expand = !expansion.multis.iter().all(|e| *e);
+                expansion.multis.iter_mut().for_each(|e| *e = expand);
+                save_prefs(expansion)?;
+            }
+            // The listing is stale once a process is gone, so leave to kill it
+            b'k' if reclaim.is_some() => return Ok(reclaim),
+            b'g' => {
+                let expand = !expansion.process_groups.iter().all(|e| *e);
+                expansion
+                    .process_groups
+                    .iter_mut()
+                    .for_each(|e| *e = expand);
+                save_prefs(expansion)?;
+            }
+            _ => {}
+        }
+    }
+}
@@ -0,0 +1,143 @@
+//! Network interface discovery and mapping listener addresses to interfaces
+
+use std::net::IpAddr;
+use std::process::Command;
+
+/// Label for listeners bound to the wildcard address (every interface)
+pub const ALL_INTERFACES: &str = "all";
+
+#[derive(Debug, Clone)]
+pub struct Interface {
+    pub name: String,
+    pub addr: IpAddr,
+}
+
+/// List interface addresses (best-effort; empty if no tool is available)
+pub fn list() -> Vec<Interface> {
+    if let Some(recorded) = crate::fixtures::output("ip") {
+        parse_ip_output(&recorded)
+    } else if cfg!(target_os = "linux") {
+        list_ip().unwrap_or_default()
+    } else {
+        list_ifconfig().unwrap_or_default()
+    }
+}
+
+/// Linux: `ip -o addr show` prints one address per line,
+/// e.g. `2: eth0    inet 192.168.1.23/24 brd ... scope global eth0`
+fn list_ip() -> Option<Vec<Interface>> {
+    let output = Command::new("ip")
+        .args(["-o", "addr", "show"])
+        .output()
+        .ok()?;
+    Some(parse_ip_output(&String::from_utf8_lossy(&output.stdout)))
+}
+
+fn parse_ip_output(stdout: &str) -> Vec<Interface> {
+    stdout
+        .lines()
+        .filter_map(|line| {
+            let parts: Vec<_> = line.split_whitespace().collect();
+            let (addr, _) = parts.get(3)?.split_once('/')?;
+            Some(Interface {
+                name: parts.get(1)?.to_string(),
+                addr: parse_ip(addr)?,
+            })
+        })
+        .collect()
+}
+
+/// macOS/BSD: `ifconfig` prints an `en0: flags=...` header followed by
+/// indented `inet 192.168.1.23 netmask ...` lines
+fn list_ifconfig() -> Option<Vec<Interface>> {
+    let output = Command::new("ifconfig").output().ok()?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    let mut interfaces = Vec::new();
+    let mut current = None;
+    for line in stdout.lines() {
+        if !line.starts_with(char::is_whitespace) {
+            current = line.split(':').next().map(String::from);
+            continue;
+        }
+        let mut parts = line.split_whitespace();
+        if !matches!(parts.next(), Some("inet" | "inet6")) {
+            continue;
+        }
+        if let (Some(name), Some(addr)) = (&current, parts.next().and_then(parse_ip)) {
+            interfaces.push(Interface {
+                name: name.clone(),
+                addr,
+            });
+        }
+    }
+    Some(interfaces)
+}
+
+/// Parse an address as printed by lsof/ifconfig: `[::1]`, `fe80::1%lo0`, `127.0.0.1`
+pub fn parse_ip(address: &str) -> Option<IpAddr> {
+    let address = address.trim_start_matches('[').trim_end_matches(']');
+    let address = address.split('%').next()?;
+    address.parse().ok()
+}
+
+/// Whether a listener address is the wildcard (bound to every interface)
+pub fn is_wildcard(address: &str) -> bool {
+    address == "*" || parse_ip(address).is_some_and(|ip| ip.is_unspecified())
+}
+
+/// Name of the interface a listener address belongs to (`all` for wildcards)
+pub fn resolve(address: &str, interfaces: &[Interface]) -> String {
+    if is_wildcard(address) {
+        return ALL_INTERFACES.to_string();
+    }
+    let Some(ip) = parse_ip(address) else {
+        return "?".to_string();
+    };
+    interfaces
+        .iter()
+        .find(|i| i.addr == ip)
+        .map(|i| i.name.clone())
+        .unwrap_or_else(|| if ip.is_loopback() { "lo" } else { "?" }.to_string())
+}
+
+/// Who can reach a listener, from narrowest to widest
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Exposure {
+    /// Loopback only
+    Local,
+    /// Peers on a VPN/tailnet (Tailscale, WireGuard), but not the LAN
+    Vpn,
+    /// Any machine on the local network
+    Lan,
+}
+
+/// Classify a listener address by which peers can connect to it
+pub fn exposure(address: &str, interfaces: &[Interface]) -> Exposure {
+    if is_wildcard(address) {
+        return Exposure::Lan;
+    }
+    match parse_ip(address) {
+        Some(ip) if ip.is_loopback() => Exposure::Local,
+        Some(ip) if interfaces.iter().any(|i| i.addr == ip && is_vpn(i)) => Exposure::Vpn,
+        _ => Exposure::Lan,
+    }
+}
+
+/// Whether an interface belongs to Tailscale or WireGuard
+pub fn is_vpn(interface: &Interface) -> bool {
+    let name = interface.name.as_str();
+    name.starts_with("tailscale") || name.starts_with("wg") || is_tailscale_ip(&interface.addr)
+}
+
+/// Tailscale assigns 100.64.0.0/10 (CGNAT) and fd7a:115c:a1e0::/48 addresses;
+/// on macOS its interface is an anonymous `utunN`, so the address is the tell
+fn is_tailscale_ip(ip: &IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(v4) => {
+            let [a, b, ..] = v4.octets();
+            a == 100 && (b & 0xc0) == 64
+        }
+        IpAddr::V6(v6) => v6.segments()[..3] == [0xfd7a, 0x115c, 0xa1e0],
+    }
+}
@@ -0,0 +1,345 @@
+//! Terminating the processes that hold a port
+//!
+//! Dev servers are often started through wrappers (npm, yarn, foreman), so the
+//! PID that lsof reports may be a child whose parent respawns it, or a parent
+//! whose children keep the socket open. The kill modes here let the user choose
+//! how much of the process hierarchy to signal.
+
+use crate::theme::Themed;
+use anyhow::{Context, Result};
+use std::collections::{HashMap, HashSet};
+use std::process::{Command, Stdio};
+use std::sync::Arc;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KillMode {
+    /// Only the PIDs holding the port
+    Pid,
+    /// The PIDs and all of their descendants
+    Tree,
+    /// The whole process group of each PID
+    Pgroup,
+}
+
+pub struct Options {
+    pub mode: KillMode,
+    /// SIGKILL instead of SIGTERM
+    pub force: bool,
+    /// Also kill when unrelated processes share the port
+    pub all: bool,
+    /// How long to wait for the port to be released
+    pub timeout: std::time::Duration,
+}
+
+impl Options {
+    fn signal(&self) -> &'static str {
+        if self.force {
+            "KILL"
+        } else {
+            "TERM"
+        }
+    }
+}
+
+/// What `kill` frees: a port, or every port of some processes (`--pid`)
+pub enum Target {
+    Port(u16),
+    Pids(Vec<u32>),
+}
+
+impl Target {
+    /// The listeners of the target
+    fn holders(&self) -> Result<Vec<crate::PortInfo>> {
+        let infos = crate::get_port_info()?;
+        Ok(match self {
+            Target::Port(port) => crate::filter_port_infos(infos, Some(*port), None, None),
+            Target::Pids(pids) => infos
+                .into_iter()
+                .filter(|info| crate::holds_pid(info, pids))
+                .collect(),
+        })
+    }
+
+    fn describe(&self) -> String {
+        match self {
+            Target::Port(port) => format!("port {}", port),
+            Target::Pids(pids) => {
+                let pids: Vec<String> = pids.iter().map(u32::to_string).collect();
+                format!("the ports of PID {}", pids.join(", "))
+            }
+        }
+    }
+}
+
+/// Poll until the target's listeners are gone or `timeout` elapses
+///
+/// Returns the processes still holding the ports (empty when released).
+fn wait_for_release(target: &Target, timeout: std::time::Duration) -> Result<Vec<crate::PortInfo>> {
+    const POLL_INTERVAL: std::time::Duration = std::time::Duration::from_millis(200);
+
+    let deadline = std::time::Instant::now() + timeout;
+    loop {
+        let holders = target.holders()?;
+        if holders.is_empty() || std::time::Instant::now() >= deadline {
+            return Ok(holders);
+        }
+        std::thread::sleep(POLL_INTERVAL);
+    }
+}
+
+/// Kill the processes bound to the target and check that its ports were released
+pub fn run(target: &Target, options: &Options) -> Result<()> {
+    let infos = target.holders()?;
+    anyhow::ensure!(
+        !infos.is_empty(),
+        "Nothing is listening on {}",
+        target.describe()
+    );
+
+    let pids = crate::deduplicate_pids(&infos);
+    let process_of = |pid: &str| {
+        infos
+            .iter()
+            .find(|i| &*i.pid == pid)
+            .map(|i| &*i.process)
+            .unwrap_or("?")
+    };
+
+    // SO_REUSEPORT or separate IPv4/IPv6 listeners can put two apps on one port
+    if let Target::Port(_) = target {
+        let groups = related_groups(&pids)?;
+        if groups.len() > 1 && !options.all {
+            let owners: Vec<String> = groups
+                .iter()
+                .map(|group| {
+                    let pids: Vec<String> = group.iter().map(|p| crate::format_pid(p)).collect();
+                    format!("{} {}", process_of(&group[0]), pids.join(" "))
+                })
+                .collect();
+            anyhow::bail!(
+                "{} is shared by unrelated processes: {}; pass --all to kill all of them",
+                target.describe(),
+                owners.join(", ")
+            );
+        }
+    }
+
+    let signal = options.signal();
+    // With --tree, a listener can be a descendant of another one and already signaled
+    let mut signaled: HashSet<String> = HashSet::new();
+    for pid in &pids {
+        match options.mode {
+            KillMode::Pid => signal_pid(pid, signal)?,
+            KillMode::Tree => {
+                // Children first, so a supervising parent can't respawn them
+                for child in descendants(pid)?.iter().rev() {
+                    if signaled.insert(child.clone()) {
+                        signal_descendant(child, signal)?;
+                    }
+                }
+                if signaled.insert(pid.to_string()) {
+                    signal_pid(pid, signal)?;
+                }
+            }
+            KillMode::Pgroup => {
+                let pgid = process_group(pid)?;
+                signal_group(&pgid, signal)?;
+                println!("Signaled process group {}", pgid);
+            }
+        }
+        println!(
+            "Sent SIG{} to {} {}",
+            signal,
+            process_of(pid).success(),
+            crate::format_pid(pid)
+        );
+    }
+
+    println!("Waiting for {} to be released...", target.describe());
+    let remaining = wait_for_release(target, options.timeout)?;
+    if remaining.is_empty() {
+        let what = target.describe();
+        println!("{} Released {}", crate::output::glyphs().check, what);
+        return Ok(());
+    }
+
+    let holders: Vec<String> = remaining
+        .iter()
+        .map(|i| format!("{} {}", i.process, crate::format_pid(&i.pid)))
+        .collect();
+    if options.mode == KillMode::Pid {
+        println!("Try --tree or --pgroup to include wrapper processes");
+    }
+    anyhow::bail!(
+        "{} still in use after {}s by {}",
+        target.describe(),
+        options.timeout.as_secs_f32(),
+        holders.join(", ")
+    )
+}
+
+fn signal_pid(pid: &str, signal: &str) -> Result<()> {
+    let status = Command::new("kill")
+        .args([&format!("-{}", signal), pid])
+        .status()
+        .context("Failed to execute kill command")?;
+    anyhow::ensure!(status.success(), "Failed to signal PID {}", pid);
+    Ok(())
+}
+
+/// Like `signal_pid`, but a descendant that exited since it was listed (often because
+/// its parent or one of its children was just signaled) is not an error
+fn signal_descendant(pid: &str, signal: &str) -> Result<()> {
+    let status = Command::new("kill")
+        .args([&format!("-{}", signal), pid])
+        .stderr(Stdio::null())
+        .status()
+        .context("Failed to execute kill command")?;
+    anyhow::ensure!(
+        status.success() || !exists(pid),
+        "Failed to signal PID {}",
+        pid
+    );
+    Ok(())
+}
+
+/// Whether a process with this PID exists (`kill -0` would also fail without permission)
+fn exists(pid: &str) -> bool {
+    Command::new("ps")
+        .args(["-p", pid, "-o", "pid="])
+        .stdout(Stdio::null())
+        .status()
+        .is_ok_and(|s| s.success())
+}
+
+fn signal_group(pgid: &str, signal: &str) -> Result<()> {
+    let target = format!("-{}", pgid);
+    let status = Command::new("kill")
+        .args([&format!("-{}", signal), "--", &target])
+        .status()
+        .context("Failed to execute kill command")?;
+    anyhow::ensure!(status.success(), "Failed to signal process group {}", pgid);
+    Ok(())
+}
+
+/// Resolve the process group of `pid`, refusing groups that would hit a shell
+fn process_group(pid: &str) -> Result<String> {
+    let (pgid, sid) = ps_pgid_sid(pid)?;
+
+    // Signaling our own group would kill this tool (and usually the user's shell job)
+    let own = ps_pgid_sid(&std::process::id().to_string())?.0;
+    anyhow::ensure!(
+        pgid != own,
+        "PID {} is in this shell's process group; use --tree instead",
+        pid
+    );
+
+    // A group led by a session leader is typically a login shell or a daemon
+    anyhow::ensure!(
+        sid.as_deref() != Some(pgid.as_str()),
+        "Process group {} is led by a session leader (a shell or daemon); use --tree instead",
+        pgid
+    );
+    Ok(pgid)
+}
+
+/// Process group and session IDs (the session is unavailable on macOS ps)
+fn ps_pgid_sid(pid: &str) -> Result<(String, Option<String>)> {
+    let output = Command::new("ps")
+        .args(["-p", pid, "-o", "pgid=,sid="])
+        .output()
+        .context("Failed to execute ps command")?;
+    if output.status.success() {
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let mut fields = stdout.split_whitespace();
+        if let Some(pgid) = fields.next() {
+            return Ok((pgid.to_string(), fields.next().map(String::from)));
+        }
+    }
+
+    let output = Command::new("ps")
+        .args(["-p", pid, "-o", "pgid="])
+        .output()
+        .context("Failed to execute ps command")?;
+    let pgid = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    anyhow::ensure!(
+        !pgid.is_empty(),
+        "Failed to resolve process group of PID {}",
+        pid
+    );
+    Ok((pgid, None))
+}
+
+/// (pid, parent pid) of every process
+fn process_parents() -> Result<Vec<(String, String)>> {
+    let output = Command::new("ps")
+        .args(["-A", "-o", "pid=,ppid="])
+        .output()
+        .context("Failed to execute ps command")?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    Ok(stdout
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            Some((fields.next()?.to_string(), fields.next()?.to_string()))
+        })
+        .collect())
+}
+
+/// All descendants of `pid`, parents before children
+pub fn descendants(pid: &str) -> Result<Vec<String>> {
+    let pairs = process_parents()?;
+    let mut result = Vec::new();
+    let mut queue = vec![pid.to_string()];
+    while let Some(parent) = queue.pop() {
+        for (child, ppid) in &pairs {
+            if *ppid == parent && !result.iter().any(|r| r == child) {
+                result.push(child.to_string());
+                queue.push(child.to_string());
+            }
+        }
+    }
+    Ok(result)
+}
+
+/// `pids` split into groups of related processes: one descends from another, or they
+/// are siblings (cluster workers of a master that doesn't listen itself)
+fn related_groups(pids: &[Arc<str>]) -> Result<Vec<Vec<Arc<str>>>> {
+    if pids.len() < 2 {
+        return Ok(vec![pids.to_vec()]);
+    }
+    let parents: HashMap<String, String> = process_parents()?.into_iter().collect();
+    let ancestors = |pid: &str| {
+        let mut chain = Vec::new();
+        let mut current = pid.to_string();
+        while let Some(parent) = parents.get(&current) {
+            if parent == "0" || chain.contains(parent) {
+                break;
+            }
+            chain.push(parent.clone());
+            current = parent.clone();
+        }
+        chain
+    };
+    let related = |a: &str, b: &str| {
+        let (up_a, up_b) = (ancestors(a), ancestors(b));
+        // Children of init (or launchd) are not related through it
+        let sibling = up_a
+            .first()
+            .is_some_and(|p| p != "1" && up_b.first() == Some(p));
+        up_a.iter().any(|p| p == b) || up_b.iter().any(|p| p == a) || sibling
+    };
+
+    let mut groups: Vec<Vec<Arc<str>>> = Vec::new();
+    for pid in pids {
+        let matching: Vec<usize> = (0..groups.len())
+            .filter(|&i| groups[i].iter().any(|other| related(pid, other)))
+            .collect();
+        let mut merged = vec![pid.clone()];
+        for &i in matching.iter().rev() {
+            merged.extend(groups.remove(i));
+        }
+        groups.push(merged);
+    }
+    Ok(groups)
+}
@@ -0,0 +1,125 @@
+//! `leaks -- <command>`: listeners a command (e.g. a test suite) left running
+//!
+//! Ports are snapshotted before and after the command. A new listener is attributed
+//! to the command when its process was seen among the command's descendants while it
+//! ran, or still carries a marker variable the command's environment passed down;
+//! the marker catches servers that daemonized and were reparented to init.
+
+use crate::theme::Themed;
+use anyhow::{Context, Result};
+use colored::Colorize;
+use std::collections::HashSet;
+use std::process::Command;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Variable set for the command; its descendants inherit it
+const MARKER: &str = "LWP_LEAKS_TOKEN";
+
+/// How often the command's process tree is sampled while it runs
+const POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+/// (port, pid) of each listener
+fn listening() -> Result<HashSet<(u16, String)>> {
+    Ok(crate::get_port_info()?
+        .into_iter()
+        .map(|info| (info.port, info.pid.to_string()))
+        .collect())
+}
+
+/// Whether `pid` has `LWP_LEAKS_TOKEN=<token>` in its environment
+fn has_marker(pid: &str, token: &str) -> bool {
+    let entry = format!("{}={}", MARKER, token);
+    if let Ok(environ) = std::fs::read(crate::fixtures::proc_path(&format!("{}/environ", pid))) {
+        return environ
+            .split(|b| *b == 0)
+            .any(|var| var == entry.as_bytes());
+    }
+    // macOS/BSD: `ps -E` appends the environment to the command
+    Command::new("ps")
+        .args(["-E", "-ww", "-o", "command=", "-p", pid])
+        .output()
+        .is_ok_and(|o| String::from_utf8_lossy(&o.stdout).contains(&entry))
+}
+
+pub fn run(command: &[String]) -> Result<()> {
+    let (program, args) = command.split_first().context("No command given")?;
+    let token = format!(
+        "{}-{}",
+        std::process::id(),
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_nanos())
+    );
+
+    let before = listening()?;
+    let mut child = Command::new(program)
+        .args(args)
+        .env(MARKER, &token)
+        .spawn()
+        .with_context(|| format!("Failed to start {}", program))?;
+    let root = child.id().to_string();
+
+    let mut tree: HashSet<String> = HashSet::from([root.clone()]);
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break status;
+        }
+        tree.extend(crate::kill::descendants(&root).unwrap_or_default());
+        std::thread::sleep(POLL_INTERVAL);
+    };
+
+    let infos = crate::get_port_info()?;
+    let (leaked, unrelated): (Vec<_>, Vec<_>) = infos
+        .iter()
+        .filter(|info| !before.contains(&(info.port, info.pid.to_string())))
+        .partition(|info| tree.contains(&*info.pid) || has_marker(&info.pid, &token));
+
+    let name = command.join(" ");
+    if leaked.is_empty() {
+        println!(
+            "{} No listeners left behind by `{}`",
+            crate::output::glyphs().check.success(),
+            name
+        );
+    } else {
+        println!(
+            "{} {} listener(s) survived `{}`:",
+            crate::output::glyphs().warn.warning(),
+            leaked.len(),
+            name
+        );
+        for info in &leaked {
+            println!(
+                "  {:>5}  {} {}  {}",
+                info.port.to_string().accent().bold(),
+                info.process.success(),
+                crate::format_pid(&info.pid).muted(),
+                info.command.muted()
+            );
+        }
+        let mut pids: Vec<&str> = leaked.iter().map(|i| &*i.pid).collect();
+        pids.sort_unstable();
+        pids.dedup();
+        let pids: Vec<String> = pids.iter().map(|pid| format!("--pid {}", pid)).collect();
+        println!("  Stop them with: lsof-work-ports kill {}", pids.join(" "));
+    }
+    if !unrelated.is_empty() {
+        let ports: Vec<String> = unrelated.iter().map(|i| i.port.to_string()).collect();
+        println!(
+            "{}",
+            format!(
+                "  Also new, but not started by the command: {}",
+                ports.join(", ")
+            )
+            .muted()
+        );
+    }
+
+    // The command's own failure comes first; otherwise leaks fail the run (for CI)
+    match status.code() {
+        Some(0) if !leaked.is_empty() => std::process::exit(1),
+        Some(0) => Ok(()),
+        Some(code) => std::process::exit(code),
+        None => anyhow::bail!("`{}` was killed by a signal", name),
+    }
+}
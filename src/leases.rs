@@ -0,0 +1,81 @@
+//! Leases on ports handed out by `alloc`, in `$XDG_STATE_HOME/lsof-work-ports/leases.json`
+//!
+//! A port that was just printed is not bound yet, so a second `alloc` in another shell
+//! would find it free too. Each handed-out port is leased to the calling process until
+//! it expires or that process exits; `alloc` and `up` skip leased ports, and leases
+//! that ran out are dropped whenever the file is updated.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::process::Command;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Lease {
+    pub port: u16,
+    /// Process the port was handed to (the shell or harness that ran `alloc`)
+    pub owner_pid: u32,
+    /// Unix seconds
+    pub expires: u64,
+}
+
+fn path() -> Result<std::path::PathBuf> {
+    Ok(crate::paths::state_dir()?.join("leases.json"))
+}
+
+fn load() -> Result<Vec<Lease>> {
+    let path = path()?;
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let content = std::fs::read_to_string(&path).context("Failed to read port leases")?;
+    serde_json::from_str(&content).context("Failed to parse port leases")
+}
+
+/// Which of `pids` still run, from a single `ps` call
+pub fn alive(pids: &[u32]) -> HashSet<u32> {
+    if pids.is_empty() {
+        return HashSet::new();
+    }
+    let list: Vec<String> = pids.iter().map(u32::to_string).collect();
+    let Ok(output) = Command::new("ps")
+        .args(["-p", &list.join(","), "-o", "pid="])
+        .output()
+    else {
+        // Without ps, keep the leases until they expire
+        return pids.iter().copied().collect();
+    };
+    String::from_utf8_lossy(&output.stdout)
+        .split_whitespace()
+        .filter_map(|pid| pid.parse().ok())
+        .collect()
+}
+
+/// The leases that are neither expired nor held by an exited process
+fn active(leases: Vec<Lease>) -> Vec<Lease> {
+    let now = crate::history::now();
+    let leases: Vec<Lease> = leases.into_iter().filter(|l| l.expires > now).collect();
+    let owners: Vec<u32> = leases.iter().map(|l| l.owner_pid).collect();
+    let alive = alive(&owners);
+    leases
+        .into_iter()
+        .filter(|l| alive.contains(&l.owner_pid))
+        .collect()
+}
+
+/// Ports leased right now
+pub fn leased_ports() -> Result<Vec<u16>> {
+    Ok(active(load()?).iter().map(|l| l.port).collect())
+}
+
+/// Load the active leases, modify and save them while holding the lock
+pub fn update<T>(f: impl FnOnce(&mut Vec<Lease>) -> Result<T>) -> Result<T> {
+    let path = path()?;
+    std::fs::create_dir_all(path.parent().context("Invalid state dir")?)?;
+    let _lock = crate::lock_file(&path.with_extension("json.lock"))?;
+
+    let mut leases = active(load()?);
+    let result = f(&mut leases)?;
+    crate::write_atomic(&path, serde_json::to_string_pretty(&leases)?.as_bytes())?;
+    Ok(result)
+}
@@ -0,0 +1,200 @@
+//! `logs <port>`: find where the process on a port writes its output and tail it
+//!
+//! Sources, in order: the log of a `hold`/`up` supervisor, a docker container
+//! publishing the port, a systemd unit (journald), a regular file on stdout
+//! (nohup, shell redirection), and a tmux pane owning the process's terminal.
+
+use crate::theme::Themed;
+use anyhow::{Context, Result};
+use std::process::Command;
+
+/// Where a process's output can be read from
+enum Source {
+    File(std::path::PathBuf),
+    Docker(String),
+    Journald(String),
+    Tmux(String),
+}
+
+impl Source {
+    fn describe(&self) -> String {
+        match self {
+            Source::File(path) => path.display().to_string(),
+            Source::Docker(id) => format!("docker logs {}", id),
+            Source::Journald(unit) => format!("journalctl -u {}", unit),
+            Source::Tmux(pane) => format!("tmux pane {}", pane),
+        }
+    }
+
+    fn tail(&self, lines: usize, follow: bool) -> Result<()> {
+        let lines = lines.to_string();
+        let mut command = match self {
+            Source::File(path) => {
+                let mut c = Command::new("tail");
+                c.args(["-n", &lines]).arg(path);
+                if follow {
+                    c.arg("-f");
+                }
+                c
+            }
+            Source::Docker(id) => {
+                let mut c = Command::new("docker");
+                c.args(["logs", "--tail", &lines, id]);
+                if follow {
+                    c.arg("-f");
+                }
+                c
+            }
+            Source::Journald(unit) => {
+                let mut c = Command::new("journalctl");
+                c.args(["-u", unit, "-n", &lines, "--no-pager"]);
+                if follow {
+                    c.arg("-f");
+                }
+                c
+            }
+            Source::Tmux(pane) => {
+                anyhow::ensure!(
+                    !follow,
+                    "-f is not supported for tmux panes; attach to {} instead",
+                    pane
+                );
+                let mut c = Command::new("tmux");
+                c.args([
+                    "capture-pane",
+                    "-p",
+                    "-t",
+                    pane,
+                    "-S",
+                    &format!("-{}", lines),
+                ]);
+                c
+            }
+        };
+        let status = command
+            .status()
+            .with_context(|| format!("Failed to read {}", self.describe()))?;
+        anyhow::ensure!(
+            status.success() || status.code().is_none(),
+            "{} failed",
+            self.describe()
+        );
+        Ok(())
+    }
+}
+
+fn stdout_of(command: &mut Command) -> Option<String> {
+    let output = command.output().ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// Log of a `hold`/`up` command whose process group contains `pid`
+fn supervisor_log(pid: &str) -> Option<Source> {
+    let entries = crate::supervisor::load().ok()?;
+    let entry = entries
+        .iter()
+        .find(|e| crate::supervisor::group_pids(e).iter().any(|p| p == pid))?;
+    Some(Source::File(crate::supervisor::log_path(&entry.name).ok()?))
+}
+
+/// Container publishing `port` (the listener is docker-proxy or Docker Desktop's backend)
+fn docker_container(port: u16) -> Option<Source> {
+    let id = stdout_of(Command::new("docker").args([
+        "ps",
+        "--filter",
+        &format!("publish={}", port),
+        "--format",
+        "{{.ID}}",
+    ]))?;
+    let id = id.lines().next()?.to_string();
+    (!id.is_empty()).then_some(Source::Docker(id))
+}
+
+/// systemd service the process belongs to, from its cgroup
+fn systemd_unit(pid: &str) -> Option<Source> {
+    let cgroup =
+        std::fs::read_to_string(crate::fixtures::proc_path(&format!("{}/cgroup", pid))).ok()?;
+    let unit = cgroup
+        .split(['/', '\n'])
+        .find(|part| part.ends_with(".service") && !part.starts_with("user@"))?;
+    Some(Source::Journald(unit.to_string()))
+}
+
+/// Path of the process's stdout (fd 1)
+fn stdout_path(pid: &str) -> Option<String> {
+    if cfg!(target_os = "linux") {
+        let link = std::fs::read_link(crate::fixtures::proc_path(&format!("{}/fd/1", pid))).ok()?;
+        return Some(link.display().to_string());
+    }
+    // lsof -F n prints `p<pid>`, `f1` and `n<name>` lines
+    let output = stdout_of(crate::collector::lsof().args(["-a", "-p", pid, "-d", "1", "-Fn"]))?;
+    output
+        .lines()
+        .find_map(|line| line.strip_prefix('n'))
+        .map(str::to_string)
+}
+
+/// tmux pane whose terminal is `tty`
+fn tmux_pane(tty: &str) -> Option<Source> {
+    let panes = stdout_of(Command::new("tmux").args([
+        "list-panes",
+        "-a",
+        "-F",
+        "#{pane_tty} #{session_name}:#{window_index}.#{pane_index}",
+    ]))?;
+    panes.lines().find_map(|line| {
+        let (pane_tty, pane) = line.split_once(' ')?;
+        (pane_tty == tty).then(|| Source::Tmux(pane.to_string()))
+    })
+}
+
+fn locate(port: u16, info: &crate::PortInfo) -> Option<Source> {
+    if let Some(source) = supervisor_log(&info.pid) {
+        return Some(source);
+    }
+    if info.process.contains("docker") || info.process.starts_with("com.docke") {
+        if let Some(source) = docker_container(port) {
+            return Some(source);
+        }
+    }
+    if let Some(source) = systemd_unit(&info.pid) {
+        return Some(source);
+    }
+    let stdout = stdout_path(&info.pid)?;
+    if stdout.starts_with("/dev/pts/") || stdout.starts_with("/dev/tty") {
+        return tmux_pane(&stdout);
+    }
+    std::path::Path::new(&stdout)
+        .is_file()
+        .then(|| Source::File(stdout.into()))
+}
+
+pub fn run(port: u16, lines: usize, follow: bool) -> Result<()> {
+    let listeners = crate::tcp_listeners(port)?;
+    let info = listeners
+        .first()
+        .with_context(|| format!("No process is listening on port {}", port))?;
+
+    let source = locate(port, info).with_context(|| {
+        format!(
+            "Can't find where {} {} writes its output (not a file, tmux pane, systemd unit or container)",
+            info.process,
+            crate::format_pid(&info.pid)
+        )
+    })?;
+    eprintln!(
+        "{}",
+        format!(
+            "{} {} {} {}",
+            info.process,
+            crate::format_pid(&info.pid),
+            crate::output::glyphs().arrow,
+            source.describe()
+        )
+        .muted()
+    );
+    source.tail(lines, follow)
+}
@@ -1,10 +1,96 @@
 use anyhow::{Context, Result};
-use clap::{Parser, Subcommand};
+use clap::{CommandFactory, FromArgMatches, Parser, Subcommand};
+use output::OutputFormat;
 use colored::Colorize;
+use crate::theme::Themed;
 use serde::{Deserialize, Serialize};
 use std::process::Command;
+use std::sync::Arc;
 use terminal_size::{Width, terminal_size};
 
+mod adb;
+mod advertise;
+#[cfg(feature = "daemon")]
+mod alerts;
+mod alloc;
+mod anonymize;
+mod bug_report;
+mod chains;
+mod check;
+mod collector;
+mod config_edit;
+#[cfg(feature = "daemon")]
+mod daemon;
+mod db_clients;
+mod defunct;
+mod devcontainer;
+mod devlinks;
+mod doctor;
+mod dotenv;
+mod events;
+mod explain;
+mod git_hook;
+#[cfg(feature = "history")]
+mod export;
+mod fixtures;
+mod health;
+mod highlight;
+mod history;
+mod hold;
+mod insights;
+mod inspector;
+#[cfg(feature = "interactive")]
+mod interactive;
+mod interfaces;
+mod kill;
+mod leaks;
+mod leases;
+mod logs;
+mod output;
+#[cfg(feature = "daemon")]
+mod overflow;
+mod pins;
+mod paths;
+mod plan;
+mod pool;
+#[cfg(feature = "probe")]
+mod probe;
+mod procfile;
+mod procfs;
+mod query;
+mod related;
+mod remote;
+#[cfg(feature = "history")]
+mod report;
+mod schema;
+mod secrets;
+mod simulator;
+mod snapshot;
+#[cfg(feature = "history")]
+mod stats;
+mod summary;
+mod supervisor;
+mod tabs;
+mod temp_ports;
+mod template;
+mod theme;
+mod tunnel;
+mod unowned;
+mod url;
+mod verify;
+mod wait;
+mod watch;
+mod well_known;
+mod why_failed;
+#[cfg(all(unix, feature = "daemon"))]
+mod api;
+#[cfg(feature = "hooks")]
+mod hook;
+#[cfg(feature = "self-update")]
+mod self_update;
+#[cfg(feature = "tray")]
+mod tray;
+
 #[derive(Parser)]
 #[command(name = "lsof-work-ports")]
 #[command(about = "Manage ports occupied by processes", long_about = None)]
@@ -20,6 +106,51 @@ struct Cli {
     #[arg(short = 'n', long)]
     process: Option<String>,
 
+    /// Match --process against the whole name (`node` but not `nodemon`)
+    #[arg(long, requires = "process")]
+    process_exact: bool,
+
+    /// Match --process with the given case (`Node` but not `node`)
+    #[arg(long, requires = "process")]
+    case_sensitive: bool,
+
+    /// Only show the ports held by this process (repeatable); includes non-dev processes
+    #[arg(long, value_name = "PID")]
+    pid: Vec<u32>,
+
+    /// Filter by network interface (e.g. en0, lo0); wildcard listeners always match
+    #[arg(short, long)]
+    interface: Option<String>,
+
+    /// Only show TCP listeners
+    #[arg(long, conflicts_with = "udp")]
+    tcp: bool,
+
+    /// Only show bound UDP sockets
+    #[arg(long)]
+    udp: bool,
+
+    /// Show sockets in these states instead of listening ones: listen, established,
+    /// time_wait, close_wait, ... or all (repeatable or comma-separated)
+    #[arg(long, value_name = "STATE", value_delimiter = ',', conflicts_with = "watch")]
+    state: Vec<String>,
+
+    /// Show established connections instead of listening sockets (`--state established`)
+    #[arg(long, conflicts_with_all = ["state", "watch"])]
+    established: bool,
+
+    /// Emphasize rows whose port (3000, 3000-3010) or process name matches, keeping the rest (repeatable)
+    #[arg(long, value_name = "PATTERN")]
+    highlight: Vec<String>,
+
+    /// Filter expression, e.g. 'port in 3000..4000 && process =~ "node|bun"'
+    #[arg(long = "where", value_name = "EXPR")]
+    where_expr: Option<String>,
+
+    /// Apply a filter expression saved in the [queries] table of the config
+    #[arg(long, value_name = "NAME")]
+    query: Option<String>,
+
     /// Show all ports (default: only dev processes)
     #[arg(short, long)]
     all: bool,
@@ -35,44 +166,668 @@ struct Cli {
     /// Sort by recent activity (most recent first)
     #[arg(long)]
     sort_recent: bool,
+
+    /// Use the compact layout (default: automatic on narrow terminals)
+    #[arg(long)]
+    compact: bool,
+
+    /// Output format
+    #[arg(short, long, value_enum, default_value_t = OutputFormat::Table)]
+    output: OutputFormat,
+
+    /// Print JSON instead of the table (same as --output json)
+    #[arg(long, conflicts_with_all = ["output", "format"])]
+    json: bool,
+
+    /// Like --output auto, but never the table: JSON in CI, plain records elsewhere
+    #[arg(long, conflicts_with_all = ["output", "json", "format"])]
+    machine: bool,
+
+    /// Print one line per port from a template, e.g. '{port}\t{process:<20}\t{cwd}'
+    #[arg(long, value_name = "TEMPLATE")]
+    format: Option<String>,
+
+    /// Hide the count line and section headers in table output
+    #[arg(long)]
+    no_header: bool,
+
+    /// Toggle the second lines of multis/process_groups with single keys
+    #[cfg(feature = "interactive")]
+    #[arg(long, conflicts_with_all = [
+        "compact", "output", "json", "machine", "format", "a11y", "watch", "state", "established",
+    ])]
+    interactive: bool,
+
+    /// Time TCP connects to each listener to spot hung servers
+    #[cfg(feature = "probe")]
+    #[arg(long)]
+    latency: bool,
+
+    /// Check the monitored ports for listeners that never accept or never respond
+    #[cfg(feature = "probe")]
+    #[arg(long)]
+    detect_hangs: bool,
+
+    /// Also list ports inside an Android device/emulator and its adb forwards
+    #[arg(long, value_name = "SERIAL")]
+    adb: Option<String>,
+
+    /// Use ASCII symbols instead of Unicode glyphs (same as --glyphs ascii)
+    #[arg(long, conflicts_with = "glyphs")]
+    ascii: bool,
+
+    /// Symbols in messages and the listing (default: unicode, ascii on legacy Windows consoles)
+    #[arg(long, value_enum, value_name = "SET")]
+    glyphs: Option<output::GlyphSet>,
+
+    /// Print how many ports the filters hid, and why
+    #[arg(long)]
+    show_hidden_count: bool,
+
+    /// Replace the count line with socket statistics (protocols, users, hidden ports)
+    #[arg(long, conflicts_with = "no_header")]
+    stats: bool,
+
+    /// Write a bug report (version, OS, collector sample) to a temp file
+    #[arg(long)]
+    bug_report: bool,
+
+    /// Mask usernames, home directories and the hostname, e.g. for screenshots
+    #[arg(long)]
+    anonymize: bool,
+
+    /// Show passwords and tokens in command lines instead of masking them
+    #[arg(long)]
+    show_secrets: bool,
+
+    /// Process lookups and probes to run at once (default: number of cores, at most 8)
+    #[arg(short, long, value_name = "N")]
+    jobs: Option<usize>,
+
+    /// Warn about collector lines that could not be parsed (their ports are missing)
+    #[arg(long)]
+    strict_parse: bool,
+
+    /// With --strict-parse, print the lines that could not be parsed
+    #[arg(short, long, requires = "strict_parse")]
+    verbose: bool,
+
+    /// Color theme (default: the `theme` of the config, else default)
+    #[arg(long, value_enum, value_name = "NAME")]
+    theme: Option<theme::ThemeName>,
+
+    /// Monitor these ports (e.g. 4000-4010) for this shell session only, for a day at most
+    #[arg(long, value_name = "PORTS")]
+    monitor_temp: Option<String>,
+
+    /// Stop monitoring the --monitor-temp ports of this shell session
+    #[arg(long, conflicts_with = "monitor_temp")]
+    monitor_temp_clear: bool,
+
+    /// Screen-reader friendly output: labeled one-line records, no colors or symbols
+    #[arg(long)]
+    a11y: bool,
+
+    /// How listening sockets are collected (default: lsof, sockstat or fstat by OS;
+    /// proc on Linux without lsof; or `LWP_BACKEND`)
+    #[arg(long, value_enum, value_name = "NAME")]
+    backend: Option<collector::Backend>,
+
+    /// Keep the config, state and caches under this directory (or `LWP_ROOT`)
+    #[arg(long, value_name = "DIR")]
+    root: Option<std::path::PathBuf>,
+
+    /// Refresh the listing until interrupted, highlighting ports that appeared
+    #[arg(short, long, conflicts_with_all = ["json", "machine", "output", "format"])]
+    watch: bool,
+
+    /// Seconds between refreshes with --watch
+    #[arg(
+        long,
+        value_name = "SECS",
+        default_value_t = 2.0,
+        requires = "watch",
+        value_parser = parse_interval
+    )]
+    interval: f32,
+
+    /// With --watch, also append listen/close events as JSON lines to this file
+    #[arg(long, value_name = "PATH", requires = "watch")]
+    output_file: Option<std::path::PathBuf>,
+
+    /// Start --output-file over instead of appending to it
+    #[arg(long, requires = "output_file")]
+    truncate: bool,
+
+    /// Rotate --output-file once it reaches this many MiB (0: never)
+    #[arg(long, value_name = "MIB", default_value_t = 10, requires = "output_file")]
+    rotate_size: u64,
+
+    /// Rotated copies of --output-file to keep (file.1, file.2, ...)
+    #[arg(long, value_name = "N", default_value_t = 3, requires = "output_file")]
+    rotate_keep: usize,
 }
 
 #[derive(Subcommand)]
 enum Commands {
     /// Initialize config file
     Init,
+    /// Read or change single config values by dotted path (e.g. display.pinned)
+    Config {
+        #[command(subcommand)]
+        action: ConfigAction,
+    },
+    /// Print the JSON Schema of the config file or of the --json output
+    Schema {
+        #[arg(value_enum)]
+        kind: schema::SchemaKind,
+    },
     /// List port information
+    List {
+        /// Only show each configured [[ports]] range with how many of its ports are in use
+        #[arg(long)]
+        group_only: bool,
+    },
+    /// Summarize the recorded usage and suggest features (opt-in, stays local)
+    Insights {
+        /// Delete the recorded usage
+        #[arg(long)]
+        clear: bool,
+    },
+    /// Always show these ports first, in a pinned block (no ports: list the pinned ones)
+    Pin {
+        /// Ports to pin
+        ports: Vec<u16>,
+    },
+    /// Stop pinning ports
+    Unpin {
+        /// Ports to unpin
+        #[arg(required = true)]
+        ports: Vec<u16>,
+    },
+    /// Remove all files created by this tool (config, state, caches)
+    Purge {
+        /// Delete without asking for confirmation
+        #[arg(short, long)]
+        yes: bool,
+    },
+    /// Explain what is using a port, including sockets still in TIME_WAIT
+    Explain {
+        /// Port to inspect
+        #[arg(required_unless_present = "pid")]
+        port: Option<u16>,
+
+        /// Explain every port held by these processes instead (repeatable)
+        #[arg(long, value_name = "PID", conflicts_with = "port")]
+        pid: Vec<u32>,
+    },
+    /// Check the config and that the collector (lsof, sockstat, fstat) works
+    Doctor,
+    /// Search the system log for recent bind failures on a port
+    WhyFailed {
+        /// Port that failed to bind
+        port: u16,
+
+        /// How far back to search (journald/`log show` syntax, e.g. 30m, 2h, 1d)
+        #[arg(long, default_value = "1h")]
+        since: String,
+    },
+    /// Run a command in the background, restarting it if it crashes
+    Hold {
+        /// Port the command is expected to listen on
+        #[arg(short, long)]
+        port: Option<u16>,
+
+        /// Name to refer to the command by (defaults to the program name)
+        #[arg(long)]
+        name: Option<String>,
+
+        /// Don't restart the command when it exits
+        #[arg(long)]
+        no_restart: bool,
+
+        /// Stop a held command by name
+        #[arg(long, value_name = "NAME", conflicts_with = "command")]
+        stop: Option<String>,
+
+        /// Command to run, after `--` (e.g. `hold -- npm run dev`)
+        #[arg(last = true, required_unless_present = "stop")]
+        command: Vec<String>,
+    },
+    /// Poll ports in the foreground and evaluate the [alerts] rules of the config
+    #[cfg(feature = "daemon")]
+    Daemon {
+        /// Seconds between polls
+        #[arg(long, default_value_t = 5.0, value_parser = parse_interval)]
+        interval: f32,
+
+        /// Also notify when a monitored listener stops accepting or responding
+        #[arg(long)]
+        detect_hangs: bool,
+
+        /// Append port and alert events to events.jsonl in the state directory
+        #[arg(long)]
+        events: bool,
+
+        /// Also write the events as JSON lines to this file
+        #[arg(long, value_name = "PATH")]
+        output_file: Option<std::path::PathBuf>,
+
+        /// Start --output-file over instead of appending to it
+        #[arg(long, requires = "output_file")]
+        truncate: bool,
+
+        /// Rotate --output-file once it reaches this many MiB (0: never)
+        #[arg(long, value_name = "MIB", default_value_t = 10, requires = "output_file")]
+        rotate_size: u64,
+
+        /// Rotated copies of --output-file to keep (file.1, file.2, ...)
+        #[arg(long, value_name = "N", default_value_t = 3, requires = "output_file")]
+        rotate_keep: usize,
+
+        /// Serve ports, events and kill requests on a Unix socket (see the api command)
+        #[arg(long)]
+        api: bool,
+    },
+    /// Talk to a daemon started with --api
+    #[cfg(feature = "daemon")]
+    Api {
+        #[command(subcommand)]
+        action: ApiAction,
+    },
+    /// Report up/degraded/down for the monitored ports, running their health checks
+    Health,
+    /// Show aggregates from the recorded history
+    #[cfg(feature = "history")]
+    Stats {
+        /// Time from process start until its port was bound, per supervised command
+        #[arg(long)]
+        startup: bool,
+        /// Hour-by-weekday occupancy of the monitored ports, from sessions recorded by `daemon`
+        #[arg(long, conflicts_with = "startup")]
+        heatmap: bool,
+        /// Weeks of history covered by --heatmap
+        #[arg(long, default_value_t = 4, requires = "heatmap")]
+        weeks: u64,
+    },
+    /// Summarize the recorded history as Markdown
+    #[cfg(feature = "history")]
+    Report {
+        /// Digest of the last 7 days: ports used, longest-running servers, contested ports
+        #[arg(long)]
+        weekly: bool,
+    },
+    /// Work with the recorded history
+    #[cfg(feature = "history")]
+    History {
+        #[command(subcommand)]
+        action: HistoryAction,
+    },
+    /// Print several free ports at once, e.g. one per parallel test shard
+    Alloc {
+        /// Number of ports
+        #[arg(short, long, default_value_t = 1)]
+        count: usize,
+
+        /// Ports to choose from (`4000-4999`, `5000,5002,6000-6100`)
+        #[arg(long, default_value = alloc::DEFAULT_RANGE)]
+        range: String,
+
+        /// Output format
+        #[arg(long, value_enum, default_value_t = alloc::AllocFormat::Plain)]
+        format: alloc::AllocFormat,
+
+        /// Keep the ports from being handed out as ephemeral ports for about a minute
+        #[arg(long)]
+        reserve: bool,
+
+        /// Seconds other `alloc`/`up` calls skip the ports (0: no lease)
+        #[arg(long, value_name = "SECS", default_value_t = alloc::DEFAULT_LEASE_SECS)]
+        lease: u64,
+
+        /// PID whose exit ends the leases early (default: the calling process)
+        #[arg(long, value_name = "PID")]
+        owner: Option<u32>,
+    },
+    /// Suggest a free contiguous port range for a new project
+    Plan {
+        /// Number of ports in the range
+        #[arg(long, default_value_t = 10)]
+        size: u16,
+
+        /// Name for the range when saving it
+        #[arg(long)]
+        name: Option<String>,
+
+        /// Append the first suggestion to the config as a [[ports]] entry
+        #[arg(long)]
+        save: bool,
+    },
+    /// Start the services of a Procfile, each with a free port exported as PORT
+    Up {
+        /// Procfile to read (falls back to [services] in the config)
+        #[arg(short = 'f', long, default_value = "Procfile")]
+        procfile: std::path::PathBuf,
+
+        /// Only start these services
+        services: Vec<String>,
+    },
+    /// Stop services started with `up` from the current directory
+    Down {
+        /// Only stop these services
+        services: Vec<String>,
+    },
+    /// Show recent output of the process listening on a port
+    Logs {
+        /// Port of the process
+        port: u16,
+
+        /// Number of lines to show
+        #[arg(short = 'n', long, default_value_t = 50)]
+        lines: usize,
+
+        /// Keep printing new output
+        #[arg(short, long)]
+        follow: bool,
+    },
+    /// Match localhost tabs open in Chrome/Edge with the listening ports
+    Tabs {
+        /// Remote debugging port of the browser (--remote-debugging-port)
+        #[arg(long, default_value_t = 9222)]
+        devtools_port: u16,
+    },
+    /// List SSH port forwards (-L/-R/-D) of running ssh clients
+    Tunnels,
+    /// Create and remove supervised SSH port forwards
+    Tunnel {
+        #[command(subcommand)]
+        action: TunnelAction,
+    },
+    /// Run a command (e.g. a test suite) and report the listeners it left running
+    Leaks {
+        /// Command to run, after `--` (e.g. `leaks -- npm test`)
+        #[arg(last = true, required = true)]
+        command: Vec<String>,
+    },
+    /// List every port held by the process on a port and its child processes
+    Related {
+        /// A port of the process
+        port: u16,
+    },
+    /// Exit 0 if a port is free, 1 if it is in use (printing the process)
+    Check {
+        /// Port a service is about to listen on
+        port: u16,
+    },
+    /// Block until a port is free or listening
+    Wait {
+        port: u16,
+
+        /// Wait until nothing holds the port
+        #[arg(long, required_unless_present = "listen", conflicts_with = "listen")]
+        free: bool,
+
+        /// Wait until a process listens on the port
+        #[arg(long)]
+        listen: bool,
+
+        /// Give up (exit 1) after this many seconds (default: wait forever)
+        #[arg(long, value_parser = parse_seconds)]
+        timeout: Option<f32>,
+    },
+    /// Compare devcontainer.json forwardPorts with the listening ports
+    Devcontainer {
+        /// Side the command runs on (default: detected from /.dockerenv and the environment)
+        #[arg(long, value_enum)]
+        side: Option<devcontainer::Side>,
+    },
+    /// Print URLs other devices on the network can use to reach a port
+    Url {
+        /// Port to share
+        port: u16,
+
+        /// Also render the first LAN URL as a QR code (requires qrencode)
+        #[arg(long)]
+        qr: bool,
+    },
+    /// Advertise a port over mDNS/Bonjour while this command runs
+    Advertise {
+        /// Port to advertise
+        port: u16,
+
+        /// Service name (reachable as <name>.local)
+        #[arg(long)]
+        name: String,
+
+        /// DNS-SD service type
+        #[arg(long = "type", default_value = "_http._tcp")]
+        service_type: String,
+    },
+    /// Terminate the processes listening on a port
+    Kill {
+        /// Port to free
+        #[arg(required_unless_present = "pid")]
+        port: Option<u16>,
+
+        /// Kill these processes instead, once their ports are known (repeatable)
+        #[arg(long, value_name = "PID", conflicts_with = "port")]
+        pid: Vec<u32>,
+
+        /// Also kill all descendants of the listening processes
+        #[arg(long, conflicts_with = "pgroup")]
+        tree: bool,
+
+        /// Kill the whole process group of each listening process
+        #[arg(long)]
+        pgroup: bool,
+
+        /// Send SIGKILL instead of SIGTERM
+        #[arg(short, long)]
+        force: bool,
+
+        /// Kill even when unrelated processes share the port
+        #[arg(short, long)]
+        all: bool,
+
+        /// Seconds to wait for the port to be released
+        #[arg(long, default_value = "5", value_parser = parse_seconds)]
+        timeout: f32,
+    },
+    /// Check the ports of compose files and devcontainer.json against ports.toml
+    Verify,
+    /// Manage git hooks that run verify
+    Hook {
+        #[command(subcommand)]
+        action: HookAction,
+    },
+    /// Internal: run the supervisor loop for a background command
+    #[command(name = "__supervise", hide = true)]
+    Supervise { name: String },
+    /// Print port status for a menu bar plugin (xbar, SwiftBar, Argos)
+    #[cfg(feature = "tray")]
+    Tray,
+    /// Update this binary from GitHub releases
+    ///
+    /// Only the SHA-256 checksum from the release's SHA256SUMS is verified; there is no
+    /// signature check, so a tampered release would not be detected.
+    #[cfg(feature = "self-update")]
+    SelfUpdate {
+        /// Release channel to follow
+        #[arg(long, value_enum, default_value_t = self_update::Channel::Stable)]
+        channel: self_update::Channel,
+
+        /// Only report whether an update is available
+        #[arg(long)]
+        check: bool,
+
+        /// Install the latest release even if it is not newer (reinstall or downgrade)
+        #[arg(long)]
+        force: bool,
+    },
+}
+
+#[cfg(feature = "history")]
+#[derive(Subcommand)]
+enum HistoryAction {
+    /// Print the recorded port sessions (port, process, pid, start, end, project)
+    Export {
+        /// Output format
+        #[arg(long, value_enum, default_value = "csv")]
+        format: export::ExportFormat,
+
+        /// Only sessions that ended within this age (e.g. 12h, 30d, 2w)
+        #[arg(long, default_value = "30d")]
+        since: String,
+
+        /// Comma-separated fields to include (default: all)
+        #[arg(long, value_delimiter = ',')]
+        fields: Vec<String>,
+    },
+}
+
+#[cfg(feature = "daemon")]
+#[derive(Subcommand)]
+enum ApiAction {
+    /// Print the listeners of the daemon's latest snapshot as JSON
+    Ports,
+    /// Print listen/close/alert events as JSON lines as they happen
+    Events,
+    /// Print the listeners of a project directory as JSON, for editor extensions
+    Workspace {
+        /// Project directory (default: the current directory)
+        path: Option<std::path::PathBuf>,
+    },
+    /// Kill the processes on a port through the daemon, after confirming them
+    Kill {
+        port: u16,
+
+        /// Don't ask for confirmation
+        #[arg(short, long)]
+        yes: bool,
+
+        /// Send SIGKILL instead of SIGTERM
+        #[arg(short, long)]
+        force: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum ConfigAction {
+    /// Print a value: strings as-is, arrays and numbers as TOML
+    Get {
+        /// Dotted path, e.g. score_threshold, display.pinned, ports.0.name
+        key: String,
+
+        /// Print the value as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Change a value after checking it against the config types
+    Set {
+        /// Dotted path, e.g. score_threshold, display.pinned, ports.0.name
+        key: String,
+
+        /// TOML value (3000, true, [3000, 5432]); anything else is taken as a string
+        value: String,
+    },
+    /// Monitor a port range (adds a [[ports]] entry)
+    Add {
+        /// Ports and ranges, e.g. 3000-3100 or 3000,8080
+        ports: String,
+
+        /// Name shown for the range, e.g. frontend
+        #[arg(long)]
+        name: Option<String>,
+    },
+    /// Stop monitoring a range, given as its ports, its name or a port in it
+    Remove { target: String },
+    /// List the monitored port ranges
     List,
 }
 
+#[derive(Subcommand)]
+enum HookAction {
+    /// Install a hook that runs verify, e.g. before each push
+    Install {
+        #[arg(value_enum)]
+        hook: git_hook::GitHook,
+
+        /// Replace an existing hook not installed by this command
+        #[arg(long)]
+        force: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum TunnelAction {
+    /// Forward a local port through an SSH host, restarting it if it drops
+    Add {
+        /// Local port to listen on
+        port: u16,
+
+        /// Remote endpoint (host:port) as seen from the SSH host
+        target: String,
+
+        /// SSH destination to tunnel through (e.g. bastion, user@host)
+        #[arg(long)]
+        via: String,
+    },
+    /// Stop a supervised tunnel
+    Rm {
+        /// Local port of the tunnel
+        port: u16,
+    },
+}
+
 #[derive(Debug, Clone)]
 struct PortInfo {
     port: u16,
-    process: String,
-    pid: String,
+    process: Arc<str>, // Shared by all sockets of the process
+    pid: Arc<str>,
+    user: String,
     command: String,
     start_time: String, // Process start time from ps
     address: String,    // IP address (e.g., "127.0.0.1", "0.0.0.0", "*")
+    family: String,     // Address family from lsof ("IPv4" or "IPv6")
+    protocol: collector::Protocol,
+    state: String,        // "LISTEN", "UNCONN" (UDP) or the TCP state of a connection
+    peer: Option<String>, // Remote address:port of a connection
+    interface: String,    // Interface the address belongs to ("lo0", "en0", "all")
+    exposure: interfaces::Exposure, // Who can connect (loopback, VPN peers, LAN)
 }
 
 #[derive(Debug, Clone)]
 struct GroupedPortInfo {
     port: u16,
-    processes: Vec<String>,
-    pids: Vec<String>,
+    processes: Vec<Arc<str>>,
+    pids: Vec<Arc<str>>,
     command: String,
     start_time: String, // Most recent start time from the group
     is_local: bool,     // Whether this is a local address (127.0.0.1, 0.0.0.0, etc.)
     dev_score: u32,     // Development process score
+    interface: String,  // Interface of the first listener
+    family: String,     // "IPv4", "IPv6" or "IPv4+IPv6"
+    protocol: collector::Protocol, // TCP and UDP sockets on a port are separate rows
+    state: String,                 // "LISTEN", "UNCONN" or the state of a connection
+    peer: Option<String>,          // Remote address:port; each connection is a row
+    exposure: interfaces::Exposure, // Widest exposure among the listeners
+    label: Option<String>, // Shown instead of the command (SSH forward, iOS Simulator, AirPlay)
+    address: String,       // Address of the first listener
+    #[cfg(feature = "probe")]
+    latency: Option<probe::Latency>, // Connect latency, when probed with --latency
+    port_name: Option<String>,       // Name of the [[ports]] range the port is in
+    forwarding: Option<remote::Forwarding>, // Visibility in Codespaces or over SSH
+    health: Option<health::Health>, // Result of the [[ports]] health check, with --watch
 }
 
 #[derive(Debug, Clone)]
 struct ProcessGroup {
-    process_name: String,
-    port_pid_pairs: Vec<(u16, String)>, // (port, pid) pairs
+    process_name: Arc<str>,
+    port_pid_pairs: Vec<(u16, Arc<str>)>, // (port, pid) pairs
     command: String,
     start_time: String,
     is_local: bool, // Whether this group contains local addresses
+    ports: Vec<GroupedPortInfo>, // Per-port details
 }
 
 // ============================================================================
@@ -159,6 +914,19 @@ const DEFAULT_EXCLUDE_PROCESSES: &[&str] = &[
 /// Minimum score threshold to be considered a dev process
 const DEV_SCORE_THRESHOLD: u32 = 30;
 
+/// Development score of a single socket's process, with the config's lists
+fn dev_score(info: &PortInfo, config: &Config) -> u32 {
+    calc_dev_score(
+        &info.process,
+        &info.command,
+        info.port,
+        &info.address,
+        &config.dev_processes,
+        &config.dev_keywords,
+        &config.exclude_processes,
+    )
+}
+
 /// Calculate development score for a process
 fn calc_dev_score(
     process: &str,
@@ -227,11 +995,46 @@ mod display_config {
     /// Width of the process name column (in characters)
     pub const PROCESS_WIDTH: usize = 20;
 
+    /// Width of the protocol column ("tcp", "udp")
+    pub const PROTOCOL_WIDTH: usize = 3;
+
+    /// Width of the interface column (in characters)
+    pub const INTERFACE_WIDTH: usize = 5;
+
     /// Indicator shown for local addresses (127.0.0.1, 0.0.0.0, localhost, etc.)
     pub const LOCAL_INDICATOR_LOCAL: &str = "L ";
 
     /// Indicator shown for remote/non-local addresses
     pub const LOCAL_INDICATOR_REMOTE: &str = "  ";
+
+    /// Terminal width below which the compact layout is used automatically
+    pub const COMPACT_WIDTH_THRESHOLD: usize = 60;
+}
+
+/// Get the terminal width, defaulting to 80 if unavailable
+fn term_width() -> usize {
+    terminal_size()
+        .map(|(Width(w), _)| w as usize)
+        .unwrap_or(80)
+}
+
+/// Section title, abbreviated in the compact layout
+fn section_title(name: &str, compact: bool) -> &str {
+    if !compact {
+        return name;
+    }
+    match name {
+        "others" => "oth",
+        "multis" => "mul",
+        "process_groups" => "grp",
+        "pinned" => "pin",
+        _ => name,
+    }
+}
+
+/// Truncate a string to at most `max` characters
+fn truncate_chars(s: &str, max: usize) -> String {
+    s.chars().take(max).collect()
 }
 
 /// Format a single PID:port pair
@@ -254,7 +1057,7 @@ fn format_pid_with_port(pid: &str, port: u16) -> String {
 ///
 /// # Returns
 /// Comma-separated list of `[pid]` with truncation if needed
-fn format_pid_list(pids: &[String], max_display: Option<usize>) -> String {
+fn format_pid_list(pids: &[Arc<str>], max_display: Option<usize>) -> String {
     match max_display {
         Some(max) if pids.len() > max => {
             let pairs: Vec<String> = pids[..max].iter().map(|pid| format_pid(pid)).collect();
@@ -296,6 +1099,168 @@ struct Config {
     /// Minimum score to be considered a dev process (default: 30)
     #[serde(default = "default_score_threshold")]
     score_threshold: u32,
+
+    /// Monitored port ranges (`[[ports]]` entries)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    ports: Vec<PortEntry>,
+
+    /// Ports monitored for this shell session only (`--monitor-temp`), never saved
+    #[serde(skip)]
+    temp_ports: Vec<PortEntry>,
+
+    /// Ports from `LWP_MONITOR` (e.g. exported by direnv in `.envrc`), never saved
+    #[serde(skip)]
+    env_ports: Option<PortEntry>,
+
+    /// Listing preferences (toggled from `--interactive`)
+    #[serde(default, skip_serializing_if = "DisplayPrefs::is_default")]
+    display: DisplayPrefs,
+
+    /// Alert rules evaluated by `daemon`
+    #[cfg(feature = "daemon")]
+    #[serde(default, skip_serializing_if = "alerts::AlertsConfig::is_empty")]
+    alerts: alerts::AlertsConfig,
+
+    /// Services started by `up` when there is no Procfile (name → command)
+    #[serde(default, skip_serializing_if = "std::collections::BTreeMap::is_empty")]
+    services: std::collections::BTreeMap<String, String>,
+
+    /// Named `--where` expressions, used with `--query <name>`
+    #[serde(default, skip_serializing_if = "std::collections::BTreeMap::is_empty")]
+    queries: std::collections::BTreeMap<String, String>,
+
+    /// Extra option/variable names whose values are masked in command lines
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    secret_keys: Vec<String>,
+
+    /// Color theme: "default", "deuteranopia-safe", "high-contrast" or "monochrome-bold"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    theme: Option<theme::ThemeName>,
+
+    /// Symbols in messages and the listing: "unicode", "ascii" or "emoji"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    glyphs: Option<output::GlyphSet>,
+
+    /// Record which commands and flags are used, for `insights` (never leaves this machine)
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    insights: bool,
+
+    /// How the listening sockets are collected (`[collector.lsof]`)
+    #[serde(default, skip_serializing_if = "collector::CollectorConfig::is_empty")]
+    collector: collector::CollectorConfig,
+
+    /// Command that receives the rows as JSON lines and prints the ones to show
+    #[cfg(feature = "hooks")]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    hook: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct DisplayPrefs {
+    /// Show all PIDs of multis entries on a second line
+    #[serde(default = "default_true")]
+    expand_multis: bool,
+    /// Show all [pid]:port pairs of process groups on a second line
+    #[serde(default = "default_true")]
+    expand_process_groups: bool,
+    /// Ports listed first in their own block, whatever the sorting (`pin`)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pinned: Vec<u16>,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+impl Default for DisplayPrefs {
+    fn default() -> Self {
+        Self {
+            expand_multis: true,
+            expand_process_groups: true,
+            pinned: Vec::new(),
+        }
+    }
+}
+
+impl DisplayPrefs {
+    fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// A monitored port range, e.g. `ports = "3000-3100,8080"`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct PortEntry {
+    ports: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    name: Option<String>,
+    /// Health check path requested over HTTP, e.g. `/healthz`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    http: Option<String>,
+    /// Expected HTTP status of the health check (default: 200)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    status: Option<u16>,
+}
+
+impl PortEntry {
+    /// Expand the spec into individual ports (`"3000-3002,8080"` → 3000, 3001, 3002, 8080)
+    fn expand(&self) -> Result<Vec<u16>> {
+        expand_ports(&self.ports)
+    }
+}
+
+/// `LWP_MONITOR="3000-3010"` as a monitored range, so a project's `.envrc` can add
+/// its ports while the shell is in that directory
+fn env_ports() -> Result<Option<PortEntry>> {
+    let spec = match std::env::var("LWP_MONITOR") {
+        Ok(spec) if !spec.trim().is_empty() => spec,
+        _ => return Ok(None),
+    };
+    expand_ports(&spec).context("Invalid LWP_MONITOR")?;
+    Ok(Some(PortEntry {
+        ports: spec,
+        name: Some("LWP_MONITOR".to_string()),
+        http: None,
+        status: None,
+    }))
+}
+
+/// Ports of a spec like `"3000-3002,8080"`
+fn expand_ports(spec: &str) -> Result<Vec<u16>> {
+    let mut ports = Vec::new();
+    for part in spec.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+        let parse = |s: &str| {
+            s.trim()
+                .parse::<u16>()
+                .with_context(|| format!("Invalid port '{}' in \"{}\"", s, spec))
+        };
+        match part.split_once('-') {
+            Some((start, end)) => {
+                let (start, end) = (parse(start)?, parse(end)?);
+                anyhow::ensure!(start <= end, "Invalid port range '{}'", part);
+                ports.extend(start..=end);
+            }
+            None => ports.push(parse(part)?),
+        }
+    }
+    Ok(ports)
+}
+
+/// `--timeout` seconds: 0 or more, and small enough for a `Duration`
+fn parse_seconds(text: &str) -> Result<f32, String> {
+    let seconds: f32 = text.parse().map_err(|_| format!("`{}` is not a number", text))?;
+    // Also rejects negative, NaN and values too large for a Duration
+    std::time::Duration::try_from_secs_f32(seconds)
+        .map_err(|_| "must be a number of seconds, 0 or more".to_string())?;
+    Ok(seconds)
+}
+
+/// `--interval` seconds: like `parse_seconds`, but above 0 so polling never spins
+fn parse_interval(text: &str) -> Result<f32, String> {
+    match parse_seconds(text) {
+        Ok(seconds) if seconds > 0.0 => Ok(seconds),
+        _ => Err("must be a number of seconds greater than 0".to_string()),
+    }
 }
 
 fn default_score_threshold() -> u32 {
@@ -310,122 +1275,417 @@ impl Default for Config {
             dev_keywords: DEFAULT_DEV_KEYWORDS.iter().map(|s| s.to_string()).collect(),
             exclude_processes: DEFAULT_EXCLUDE_PROCESSES.iter().map(|s| s.to_string()).collect(),
             score_threshold: DEV_SCORE_THRESHOLD,
+            ports: Vec::new(),
+            temp_ports: Vec::new(),
+            env_ports: None,
+            display: Default::default(),
+            #[cfg(feature = "daemon")]
+            alerts: Default::default(),
+            services: Default::default(),
+            queries: Default::default(),
+            theme: None,
+            glyphs: None,
+            insights: false,
+            secret_keys: Vec::new(),
+            collector: Default::default(),
+            #[cfg(feature = "hooks")]
+            hook: None,
         })
     }
 }
 
 impl Config {
     fn load() -> Result<Self> {
-        let config_path = Self::config_path()?;
-        if !config_path.exists() {
-            return Ok(Self::default());
+        let config_path = paths::config_file()?;
+        let mut config: Self = if config_path.exists() {
+            let content =
+                std::fs::read_to_string(&config_path).context("Failed to read config file")?;
+            toml::from_str(&content).context("Failed to parse config file")?
+        } else {
+            Self::default()
+        };
+        config.temp_ports = temp_ports::entries()?;
+        config.env_ports = env_ports()?;
+        Ok(config)
+    }
+
+    /// Write the config into the file, keeping the keys this build doesn't know
+    fn save(&self) -> Result<()> {
+        let config_path = paths::config_file()?;
+        if let Some(parent) = config_path.parent() {
+            std::fs::create_dir_all(parent)?;
         }
 
-        let content =
-            std::fs::read_to_string(&config_path).context("Failed to read config file")?;
-        toml::from_str(&content).context("Failed to parse config file")
+        let file = if config_path.exists() {
+            let content =
+                std::fs::read_to_string(&config_path).context("Failed to read config file")?;
+            toml::from_str(&content).context("Failed to parse config file")?
+        } else {
+            toml::Table::new()
+        };
+        let document = config_edit::merge(file, self)?;
+        let content = toml::to_string_pretty(&document).context("Failed to serialize config")?;
+        write_atomic(&config_path, content.as_bytes()).context("Failed to write config file")
     }
 
-    fn save(&self) -> Result<()> {
-        let config_path = Self::config_path()?;
+    /// Take an exclusive advisory lock on the config file
+    ///
+    /// Hold the returned guard across read-modify-write sequences so that
+    /// concurrent invocations don't overwrite each other's changes.
+    fn lock() -> Result<std::fs::File> {
+        let config_path = paths::config_file()?;
         if let Some(parent) = config_path.parent() {
             std::fs::create_dir_all(parent)?;
         }
+        lock_file(&config_path.with_extension("toml.lock"))
+    }
+
+    /// The `[[ports]]` entries followed by this session's `--monitor-temp` ports and
+    /// `LWP_MONITOR`
+    fn port_entries(&self) -> Vec<&PortEntry> {
+        self.ports
+            .iter()
+            .chain(&self.temp_ports)
+            .chain(&self.env_ports)
+            .collect()
+    }
+
+    /// Name of the first named range containing `port`, e.g. `frontend`
+    fn port_name(&self, port: u16) -> Option<&str> {
+        self.port_entries()
+            .into_iter()
+            .filter(|entry| entry.expand().is_ok_and(|ports| ports.contains(&port)))
+            .find_map(|entry| entry.name.as_deref())
+    }
+
+    /// Every port in the monitored ranges, in config order without duplicates
+    fn monitored_ports(&self) -> Result<Vec<u16>> {
+        let mut ports = Vec::new();
+        for entry in self.port_entries() {
+            for port in entry.expand()? {
+                if !ports.contains(&port) {
+                    ports.push(port);
+                }
+            }
+        }
+        Ok(ports)
+    }
+}
+
+/// Write a file atomically: write to a temp file in the same directory, then rename
+///
+/// A crash mid-write leaves either the old or the new content, never a truncated file.
+fn write_atomic(path: &std::path::Path, content: &[u8]) -> Result<()> {
+    use std::io::Write;
+
+    let file_name = path.file_name().context("Invalid file path")?;
+    let mut tmp_name = std::ffi::OsString::from(".");
+    tmp_name.push(file_name);
+    tmp_name.push(format!(".{}.tmp", std::process::id()));
+    let tmp_path = path.with_file_name(tmp_name);
+
+    let result = (|| -> Result<()> {
+        let mut file = std::fs::File::create(&tmp_path)?;
+        file.write_all(content)?;
+        file.sync_all()?;
+        std::fs::rename(&tmp_path, path)?;
+        Ok(())
+    })();
+    if result.is_err() {
+        let _ = std::fs::remove_file(&tmp_path);
+    }
+    result
+}
+
+/// Open (creating if needed) and exclusively lock a lock file
+///
+/// The lock is released when the returned file is dropped.
+fn lock_file(path: &std::path::Path) -> Result<std::fs::File> {
+    let file = std::fs::OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(path)
+        .with_context(|| format!("Failed to open lock file {}", path.display()))?;
+    file.lock()
+        .with_context(|| format!("Failed to lock {}", path.display()))?;
+    Ok(file)
+}
+
+/// Remove every file and directory this tool has created, after listing them and
+/// stopping the supervised commands and the daemon that write to them
+fn purge(yes: bool) -> Result<()> {
+    let candidates = [
+        paths::config_dir()?,
+        paths::state_dir()?,
+        paths::cache_dir()?,
+    ];
+    let targets: Vec<_> = candidates.iter().filter(|p| p.exists()).collect();
+
+    if targets.is_empty() {
+        println!("Nothing to purge");
+        return Ok(());
+    }
+
+    // Supervised commands and the daemon keep writing to the state directory
+    let supervised: Vec<supervisor::Entry> = supervisor::load()
+        .unwrap_or_default()
+        .into_iter()
+        .filter(supervisor::Entry::is_running)
+        .collect();
+    #[cfg(feature = "daemon")]
+    let daemon = daemon::running();
+    #[cfg(not(feature = "daemon"))]
+    let daemon: Option<u32> = None;
+
+    if !supervised.is_empty() || daemon.is_some() {
+        println!("The following will be stopped:");
+        for entry in &supervised {
+            println!("  {} ({})", entry.name, entry.command.join(" "));
+        }
+        if let Some(pid) = daemon {
+            println!("  daemon (pid {})", pid);
+        }
+    }
+    println!("The following will be deleted:");
+    for path in &targets {
+        println!("  {}", path.display());
+    }
+
+    if !yes && !confirm("Proceed?")? {
+        println!("Aborted");
+        return Ok(());
+    }
 
-        let content = toml::to_string_pretty(self).context("Failed to serialize config")?;
-        std::fs::write(&config_path, content).context("Failed to write config file")
+    let mut stopped: Vec<u32> = Vec::new();
+    for entry in &supervised {
+        stopped.extend(supervisor::stop(&entry.name)?.pid);
+        println!("{} Stopped {}", output::glyphs().check, entry.name);
+    }
+    if let Some(pid) = daemon {
+        let _ = Command::new("kill")
+            .args(["-TERM", &pid.to_string()])
+            .status();
+        stopped.push(pid);
+        println!("{} Stopped the daemon", output::glyphs().check);
+    }
+    // Deleting while they shut down could leave a freshly written file behind
+    let deadline = std::time::Instant::now() + std::time::Duration::from_secs(5);
+    while !leases::alive(&stopped).is_empty() && std::time::Instant::now() < deadline {
+        std::thread::sleep(std::time::Duration::from_millis(100));
     }
 
-    fn config_path() -> Result<std::path::PathBuf> {
-        let home = std::env::var("HOME").context("HOME environment variable not set")?;
-        Ok(std::path::PathBuf::from(home)
-            .join(".config")
-            .join("lsof-work-ports")
-            .join("config.toml"))
+    for path in targets {
+        if path.is_dir() {
+            std::fs::remove_dir_all(path)
+        } else {
+            std::fs::remove_file(path)
+        }
+        .with_context(|| format!("Failed to delete {}", path.display()))?;
+        println!("{} Deleted {}", output::glyphs().check, path.display());
     }
+    Ok(())
+}
+
+/// Ask a yes/no question on stdin (default: no)
+fn confirm(question: &str) -> Result<bool> {
+    use std::io::Write;
+
+    print!("{} [y/N] ", question);
+    std::io::stdout().flush()?;
+    let mut answer = String::new();
+    std::io::stdin().read_line(&mut answer)?;
+    Ok(matches!(answer.trim(), "y" | "Y" | "yes"))
 }
 
 fn get_port_info() -> Result<Vec<PortInfo>> {
-    let output = Command::new("lsof")
-        .args(["-i", "-P", "-n"])
-        .output()
-        .context("Failed to execute lsof command")?;
+    Ok(enrich(collector::Backend::detect().listeners()?))
+}
 
-    anyhow::ensure!(output.status.success(), "lsof command returned an error");
+/// Sockets in every state, for `--state` and `--established`
+fn get_socket_info() -> Result<Vec<PortInfo>> {
+    Ok(enrich(collector::Backend::detect().sockets()?))
+}
 
-    let stdout = String::from_utf8_lossy(&output.stdout);
+/// Upper-cased states of `--state`/`--established`; `None` lists the listening sockets
+fn requested_states(cli: &Cli) -> Option<Vec<String>> {
+    if cli.established {
+        Some(vec!["ESTABLISHED".to_string()])
+    } else if cli.state.is_empty() {
+        None
+    } else {
+        Some(cli.state.iter().map(|s| s.to_ascii_uppercase()).collect())
+    }
+}
 
-    Ok(stdout
-        .lines()
-        .skip(1)
-        .filter_map(|line| {
-            // Only include LISTEN state (skip ESTABLISHED, etc.)
-            if !line.contains("(LISTEN)") {
-                return None;
-            }
+/// Whether a socket in `state` is asked for; `LISTEN` includes unconnected UDP sockets
+fn state_matches(states: &[String], state: &str) -> bool {
+    states
+        .iter()
+        .any(|s| s == "ALL" || s == state || (s == collector::LISTEN && state == collector::UNCONN))
+}
 
-            let parts: Vec<_> = line.split_whitespace().collect();
-            if parts.len() < 9 {
-                return None;
-            }
+/// State and peer of a connection ("ESTABLISHED → 10.0.0.5:443"), `None` for listeners
+fn connection_text(info: &GroupedPortInfo) -> Option<String> {
+    match (info.state.as_str(), &info.peer) {
+        (collector::LISTEN | collector::UNCONN, _) => None,
+        (state, Some(peer)) => Some(format!("{} {} {}", state, output::glyphs().arrow, peer)),
+        (state, None) => Some(state.to_string()),
+    }
+}
 
-            let process = parts[0];
-            let pid = parts[1];
-            let name_field = parts[8];
-
-            // Get command line and start time
-            let command = get_process_command(pid).unwrap_or_else(|_| process.to_string());
-            let start_time = get_process_start_time(pid).unwrap_or_default();
-
-            extract_port(name_field).and_then(|port_str| {
-                port_str.parse::<u16>().ok().map(|port| PortInfo {
-                    port,
-                    process: process.into(),
-                    pid: pid.into(),
-                    command,
-                    start_time,
-                    address: extract_address(name_field),
-                })
-            })
+/// Listeners with their command line, start time and interface
+fn enrich(listeners: Vec<collector::Listener>) -> Vec<PortInfo> {
+    // Wildcard listeners resolve without the interface list, which costs a process spawn
+    let interfaces = if listeners.iter().all(|l| interfaces::is_wildcard(&l.address)) {
+        Vec::new()
+    } else {
+        interfaces::list()
+    };
+    let mut pids: Vec<&str> = listeners.iter().map(|l| &*l.pid).collect();
+    pids.sort_unstable();
+    pids.dedup();
+    let details = get_process_details(&pids);
+    // Untruncated process names, shared by the sockets of a process
+    let mut full_names: std::collections::HashMap<Arc<str>, Arc<str>> =
+        std::collections::HashMap::new();
+
+    listeners
+        .into_iter()
+        .map(|listener| {
+            // Command line and start time; a process that exited meanwhile has neither
+            let (start_time, command, process) = match details.get(&*listener.pid) {
+                Some((start_time, command)) => {
+                    let process = full_names
+                        .entry(listener.pid.clone())
+                        .or_insert_with(|| {
+                            collector::full_name(&listener.process, command)
+                                .map_or_else(|| listener.process.clone(), Arc::from)
+                        })
+                        .clone();
+                    (start_time.clone(), secrets::mask(command), process)
+                }
+                None => (
+                    String::new(),
+                    listener.process.to_string(),
+                    listener.process.clone(),
+                ),
+            };
+
+            let interface = interfaces::resolve(&listener.address, &interfaces);
+            let exposure = interfaces::exposure(&listener.address, &interfaces);
+
+            PortInfo {
+                port: listener.port,
+                process,
+                pid: listener.pid,
+                user: listener.user,
+                command,
+                start_time,
+                address: listener.address,
+                family: listener.family,
+                protocol: listener.protocol,
+                state: listener.state,
+                peer: listener.peer,
+                interface,
+                exposure,
+            }
         })
-        .collect())
+        .collect()
 }
 
-fn get_process_command(pid: &str) -> Result<String> {
-    let output = Command::new("ps")
-        .args(["-p", pid, "-o", "command="])
-        .output()
-        .context("Failed to execute ps command")?;
-
-    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+/// Number of distinct ports among the listeners
+fn distinct_port_count(port_infos: &[PortInfo]) -> usize {
+    let mut ports: Vec<u16> = port_infos.iter().map(|i| i.port).collect();
+    ports.sort_unstable();
+    ports.dedup();
+    ports.len()
 }
 
-fn get_process_start_time(pid: &str) -> Result<String> {
-    let output = Command::new("ps")
-        .args(["-p", pid, "-o", "lstart="])
+/// Working directory of a process (`/proc` on Linux, lsof elsewhere)
+fn get_process_cwd(pid: &str) -> Option<String> {
+    if let Ok(path) = std::fs::read_link(fixtures::proc_path(&format!("{}/cwd", pid))) {
+        return Some(path.display().to_string());
+    }
+    if fixtures::active() {
+        return None;
+    }
+    let output = collector::lsof()
+        .args(["-a", "-p", pid, "-d", "cwd", "-Fn"])
         .output()
-        .context("Failed to execute ps command")?;
+        .ok()?;
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .find_map(|line| line.strip_prefix('n'))
+        .map(str::to_string)
+}
+
+/// Start time (`lstart`) and command line of each pid, from a single `ps` call
+fn get_process_details(pids: &[&str]) -> std::collections::HashMap<String, (String, String)> {
+    let mut details = std::collections::HashMap::new();
+    if pids.is_empty() {
+        return details;
+    }
+    let stdout = match fixtures::output("ps") {
+        Some(recorded) => recorded,
+        None => {
+            let Ok(output) = Command::new("ps")
+                .args(["-p", &pids.join(","), "-o", "pid=", "-o", "lstart=", "-o", "command="])
+                .output()
+            else {
+                return details;
+            };
+            String::from_utf8_lossy(&output.stdout).into_owned()
+        }
+    };
 
-    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+    // `  123 Mon Oct 14 09:12:01 2024 node server.js`; lstart is always five words
+    fn word(s: &str) -> (&str, &str) {
+        let s = s.trim_start();
+        let end = s.find(char::is_whitespace).unwrap_or(s.len());
+        (&s[..end], &s[end..])
+    }
+    for line in stdout.lines() {
+        let (pid, after_pid) = word(line);
+        let lstart_start = after_pid.len() - after_pid.trim_start().len();
+        let mut rest = after_pid;
+        for _ in 0..5 {
+            rest = word(rest).1;
+        }
+        let start_time = after_pid[lstart_start..after_pid.len() - rest.len()].to_string();
+        details.insert(pid.to_string(), (start_time, rest.trim().to_string()));
+    }
+    details
 }
 
-fn extract_port(name_field: &str) -> Option<&str> {
-    name_field.split(':').last()
+/// `--process` with its `--process-exact`/`--case-sensitive` modifiers
+struct ProcessFilter {
+    name: String,
+    exact: bool,
+    case_sensitive: bool,
 }
 
-fn extract_address(name_field: &str) -> String {
-    // Format examples: "*:8080", "127.0.0.1:3000", "localhost:5000"
-    if let Some(addr) = name_field.split(':').next() {
-        addr.to_string()
-    } else {
-        "*".to_string()
+impl ProcessFilter {
+    /// Case-insensitive substring match unless the modifiers say otherwise
+    fn matches(&self, process: &str) -> bool {
+        let (process, name) = if self.case_sensitive {
+            (process.to_string(), self.name.clone())
+        } else {
+            (process.to_lowercase(), self.name.to_lowercase())
+        };
+        if self.exact {
+            process == name
+        } else {
+            process.contains(&name)
+        }
     }
 }
 
 fn filter_port_infos(
     port_infos: Vec<PortInfo>,
     port_filter: Option<u16>,
-    process_filter: Option<&str>,
+    process_filter: Option<&ProcessFilter>,
+    interface_filter: Option<&str>,
 ) -> Vec<PortInfo> {
     port_infos
         .into_iter()
@@ -439,11 +1699,14 @@ fn filter_port_infos(
 
             // Process name filter
             if let Some(process) = process_filter {
-                if !info
-                    .process
-                    .to_lowercase()
-                    .contains(&process.to_lowercase())
-                {
+                if !process.matches(&info.process) {
+                    return false;
+                }
+            }
+
+            // Interface filter (wildcard listeners are reachable on every interface)
+            if let Some(interface) = interface_filter {
+                if info.interface != interface && info.interface != interfaces::ALL_INTERFACES {
                     return false;
                 }
             }
@@ -453,6 +1716,18 @@ fn filter_port_infos(
         .collect()
 }
 
+/// TCP listeners on `port`: the ones URLs, server logs and held ports are about
+fn tcp_listeners(port: u16) -> Result<Vec<PortInfo>> {
+    let mut listeners = filter_port_infos(get_port_info()?, Some(port), None, None);
+    listeners.retain(|info| info.protocol == collector::Protocol::Tcp);
+    Ok(listeners)
+}
+
+/// Whether `info` belongs to one of `pids`
+fn holds_pid(info: &PortInfo, pids: &[u32]) -> bool {
+    info.pid.parse().is_ok_and(|pid: u32| pids.contains(&pid))
+}
+
 /// Returns a closure that deduplicates items by a key function
 /// Usage: items.iter().filter_map(dedup_by(|item| item.key.clone())).collect()
 fn dedup_by<T, F, K>(mut key_fn: F) -> impl FnMut(&T) -> Option<K>
@@ -472,7 +1747,7 @@ where
     }
 }
 
-fn deduplicate_pids(infos: &[PortInfo]) -> Vec<String> {
+fn deduplicate_pids(infos: &[PortInfo]) -> Vec<Arc<str>> {
     infos
         .iter()
         .filter_map(dedup_by(|i: &PortInfo| i.pid.clone()))
@@ -482,31 +1757,56 @@ fn deduplicate_pids(infos: &[PortInfo]) -> Vec<String> {
 fn group_by_port(port_infos: Vec<PortInfo>, config: &Config) -> Vec<GroupedPortInfo> {
     use std::collections::HashMap;
 
-    let mut grouped: HashMap<u16, Vec<PortInfo>> = HashMap::new();
+    type Key = (u16, collector::Protocol, String, Option<String>);
+    let mut grouped: HashMap<Key, Vec<PortInfo>> = HashMap::new();
     for info in port_infos {
-        grouped.entry(info.port).or_default().push(info);
+        grouped
+            .entry((info.port, info.protocol, info.state.clone(), info.peer.clone()))
+            .or_default()
+            .push(info);
     }
 
     grouped
         .into_iter()
-        .map(|(port, infos)| {
-            let processes: Vec<String> = infos.iter().map(|i| i.process.clone()).collect();
+        .map(|((port, protocol, state, peer), mut infos)| {
+            let processes: Vec<Arc<str>> = infos.iter().map(|i| i.process.clone()).collect();
             let pids = deduplicate_pids(&infos);
-            let command = infos.first().map(|i| i.command.clone()).unwrap_or_default();
-            let start_time = infos
-                .first()
-                .map(|i| i.start_time.clone())
-                .unwrap_or_default();
-            let first = infos.first();
-            let is_local = first.map(|i| is_local_address(&i.address)).unwrap_or(false);
-            let address = first.map(|i| i.address.as_str()).unwrap_or("*");
-            let process = first.map(|i| i.process.as_str()).unwrap_or("");
+            let exposure = infos
+                .iter()
+                .map(|i| i.exposure)
+                .max()
+                .unwrap_or(interfaces::Exposure::Local);
+            let mut families: Vec<&str> = infos.iter().map(|i| i.family.as_str()).collect();
+            families.sort_unstable();
+            families.dedup();
+            let family = families.join("+");
+
+            // Every group has at least one listener; its strings are moved, not cloned
+            let first = infos.swap_remove(0);
+            let command = first.command;
+            let start_time = first.start_time;
+            let is_local = is_local_address(&first.address);
+            let address = first.address;
+            let process = &*first.process;
+            let interface = first.interface;
+            let pid = &*first.pid;
+            let label = if let Some(owner) = defunct::detect(pid, &command) {
+                Some(owner.label(pid))
+            } else if tunnel::is_ssh(process) {
+                tunnel::describe_port(port, &command)
+            } else if let Some(simulator) = simulator::describe(&command) {
+                Some(simulator)
+            } else if well_known::is_airplay_receiver(port, process) {
+                Some(format!("macOS AirPlay Receiver (see `explain {}`)", port))
+            } else {
+                inspector::detect(port, &command).map(|debugger| debugger.label().to_string())
+            };
 
             let dev_score = calc_dev_score(
                 process,
                 &command,
                 port,
-                address,
+                &address,
                 &config.dev_processes,
                 &config.dev_keywords,
                 &config.exclude_processes,
@@ -520,6 +1820,19 @@ fn group_by_port(port_infos: Vec<PortInfo>, config: &Config) -> Vec<GroupedPortI
                 start_time,
                 is_local,
                 dev_score,
+                interface,
+                family,
+                protocol,
+                state,
+                peer,
+                exposure,
+                label,
+                address,
+                #[cfg(feature = "probe")]
+                latency: None,
+                port_name: config.port_name(port).map(str::to_string),
+                forwarding: None,
+                health: None,
             }
         })
         .collect()
@@ -528,7 +1841,7 @@ fn group_by_port(port_infos: Vec<PortInfo>, config: &Config) -> Vec<GroupedPortI
 fn group_by_process(port_infos: Vec<GroupedPortInfo>) -> Vec<ProcessGroup> {
     use std::collections::HashMap;
 
-    let mut grouped: HashMap<String, Vec<GroupedPortInfo>> = HashMap::new();
+    let mut grouped: HashMap<Arc<str>, Vec<GroupedPortInfo>> = HashMap::new();
     for info in port_infos {
         // Use first process name as the group key
         let process_name = info.processes.first().cloned().unwrap_or_default();
@@ -538,7 +1851,7 @@ fn group_by_process(port_infos: Vec<GroupedPortInfo>) -> Vec<ProcessGroup> {
     grouped
         .into_iter()
         .map(|(process_name, infos)| {
-            let mut port_pid_pairs: Vec<(u16, String)> = Vec::new();
+            let mut port_pid_pairs: Vec<(u16, Arc<str>)> = Vec::new();
 
             // Collect all port:pid pairs
             for info in &infos {
@@ -560,6 +1873,7 @@ fn group_by_process(port_infos: Vec<GroupedPortInfo>) -> Vec<ProcessGroup> {
                 command,
                 start_time,
                 is_local,
+                ports: infos,
             }
         })
         .collect()
@@ -568,10 +1882,7 @@ fn group_by_process(port_infos: Vec<GroupedPortInfo>) -> Vec<ProcessGroup> {
 fn display_grouped_port_info(info: &GroupedPortInfo, show_multi_line: bool) {
     use display_config::*;
 
-    // Get terminal width, default to 80 if unavailable
-    let term_width = terminal_size()
-        .map(|(Width(w), _)| w as usize)
-        .unwrap_or(80);
+    let term_width = term_width();
 
     // Local indicator (2 chars: "L " or "  ")
     let local_indicator = if info.is_local {
@@ -583,6 +1894,16 @@ fn display_grouped_port_info(info: &GroupedPortInfo, show_multi_line: bool) {
     // Fixed width for port (6 chars: ":12345"), left-aligned
     let port_str = format!(":{:<5}", info.port);
 
+    // "tcp" or "udp"
+    let protocol_str = info.protocol.name().to_ascii_lowercase();
+
+    // Name of the monitored range after the port, e.g. ":3000  [frontend]"
+    let name_display = info
+        .port_name
+        .as_ref()
+        .map(|name| format!("[{}] ", name))
+        .unwrap_or_default();
+
     // Fixed width for process display
     let process_display = if info.processes.len() == 1 {
         format!("{:<width$}", info.processes[0], width = PROCESS_WIDTH)
@@ -592,6 +1913,14 @@ fn display_grouped_port_info(info: &GroupedPortInfo, show_multi_line: bool) {
         format!("{:<width$}", count_str, width = PROCESS_WIDTH)
     };
 
+    // Fixed width for interface, highlighted when reachable from other machines
+    let interface_str = format!("{:<width$}", info.interface, width = INTERFACE_WIDTH);
+    let interface_display = match info.exposure {
+        interfaces::Exposure::Lan => interface_str.warning(),
+        interfaces::Exposure::Vpn => interface_str.label(),
+        interfaces::Exposure::Local => interface_str.muted(),
+    };
+
     // PID display - limit to first 3 PIDs if too many
     let pid_display = if info.pids.len() <= 3 {
         format_pid_list(&info.pids, None)
@@ -599,26 +1928,93 @@ fn display_grouped_port_info(info: &GroupedPortInfo, show_multi_line: bool) {
         format_pid_list(&info.pids, Some(2))
     };
 
+    // State and peer of a connection
+    let connection_display = connection_text(info)
+        .map(|text| format!("{}  ", text))
+        .unwrap_or_default();
+
+    // Connect latency, only when probed
+    #[cfg(feature = "probe")]
+    let latency_display = info
+        .latency
+        .map(|l| format!("{}  ", l.display()))
+        .unwrap_or_default();
+    #[cfg(not(feature = "probe"))]
+    let latency_display = "";
+
+    // Visibility in a remote dev environment
+    let forwarding_display = info
+        .forwarding
+        .map(|f| format!("{}  ", f.display()))
+        .unwrap_or_default();
+
+    // Health check of a monitored port, only in watch mode
+    let health_display = info
+        .health
+        .map(|h| format!("{}  ", h.label()))
+        .unwrap_or_default();
+
     // Calculate available space for command (account for local_indicator)
-    let prefix_len = 2 + 6 + 1 + PROCESS_WIDTH + 1 + pid_display.chars().count() + 2;
+    let prefix_len = 2
+        + 6
+        + 1
+        + PROTOCOL_WIDTH
+        + 1
+        + INTERFACE_WIDTH
+        + 1
+        + PROCESS_WIDTH
+        + 1
+        + pid_display.chars().count()
+        + 2
+        + connection_display.chars().count()
+        + latency_display.chars().count().min(8)
+        + info.forwarding.map_or(0, |f| f.text().len() + 2)
+        + info.health.map_or(0, |h| h.text().len() + 2)
+        + name_display.chars().count();
     let max_command_len = term_width.saturating_sub(prefix_len);
-    let display_command = if info.command.chars().count() > max_command_len {
-        info.command
-            .chars()
-            .take(max_command_len)
-            .collect::<String>()
-    } else {
-        info.command.clone()
+    let display_command = match &info.label {
+        // SSH tunnels show their forwarding target, system services what they are
+        Some(label) => truncate_chars(label, max_command_len).label(),
+        None => truncate_chars(&info.command, max_command_len).muted(),
     };
 
-    println!(
-        "{}{} {} {}  {}",
-        local_indicator,
-        port_str.cyan().bold(),
-        process_display.green(),
-        pid_display.bright_black(),
-        display_command.bright_black()
-    );
+    if highlight::matches(info.port, &info.processes) {
+        let row = format!(
+            "{} {} {}{} {} {}  {}{}{}{}{}",
+            port_str,
+            protocol_str,
+            name_display,
+            interface_str,
+            process_display,
+            pid_display,
+            connection_display,
+            latency_display,
+            forwarding_display,
+            health_display,
+            display_command.clone().clear()
+        );
+        println!("{}{}", local_indicator, row.highlight());
+    } else {
+        let protocol_display = match info.protocol {
+            collector::Protocol::Tcp => protocol_str.muted(),
+            collector::Protocol::Udp => protocol_str.label(),
+        };
+        println!(
+            "{}{} {} {}{} {} {}  {}{}{}{}{}",
+            local_indicator,
+            port_str.accent().bold(),
+            protocol_display,
+            name_display.label(),
+            interface_display,
+            process_display.success(),
+            pid_display.muted(),
+            connection_display.label(),
+            latency_display,
+            forwarding_display,
+            health_display,
+            display_command
+        );
+    }
 
     // Multi-line display for processes with multiple PIDs
     if show_multi_line && info.pids.len() > 1 {
@@ -626,17 +2022,14 @@ fn display_grouped_port_info(info: &GroupedPortInfo, show_multi_line: bool) {
         let pid_list: Vec<String> = info.pids.iter().map(|pid| format_pid(pid)).collect();
 
         // Display all PIDs on second line
-        println!("{}", pid_list.join(", ").bright_black());
+        println!("{}", pid_list.join(", ").muted());
     }
 }
 
-fn display_process_group(group: &ProcessGroup) {
+fn display_process_group(group: &ProcessGroup, expanded: bool) {
     use display_config::*;
 
-    // Get terminal width, default to 80 if unavailable
-    let term_width = terminal_size()
-        .map(|(Width(w), _)| w as usize)
-        .unwrap_or(80);
+    let term_width = term_width();
 
     // Local indicator (2 chars: "L " or "  ")
     let local_indicator = if group.is_local {
@@ -648,8 +2041,20 @@ fn display_process_group(group: &ProcessGroup) {
     // Fixed width for process display
     let process_display = format!("{:<width$}", group.process_name, width = PROCESS_WIDTH);
 
-    // Count display
-    let count_display = format!("(x{} ports)", group.port_pid_pairs.len());
+    // Count display, with the number of hung ports when probed
+    #[cfg(feature = "probe")]
+    let hung = group
+        .ports
+        .iter()
+        .filter(|p| matches!(p.latency, Some(probe::Latency::Hung)))
+        .count();
+    #[cfg(not(feature = "probe"))]
+    let hung = 0;
+    let count_display = if hung > 0 {
+        format!("(x{} ports, {} hung)", group.port_pid_pairs.len(), hung)
+    } else {
+        format!("(x{} ports)", group.port_pid_pairs.len())
+    };
 
     // Calculate available space for command (account for local_indicator)
     let prefix_len = 2 + PROCESS_WIDTH + 1 + count_display.chars().count() + 2;
@@ -664,58 +2069,1099 @@ fn display_process_group(group: &ProcessGroup) {
         group.command.clone()
     };
 
-    println!(
-        "{}{} {}  {}",
-        local_indicator,
-        process_display.green().bold(),
-        count_display.bright_black(),
-        display_command.bright_black()
-    );
+    if group
+        .ports
+        .iter()
+        .any(|info| highlight::matches(info.port, &info.processes))
+    {
+        let row = format!("{} {}  {}", process_display, count_display, display_command);
+        println!("{}{}", local_indicator, row.highlight());
+    } else {
+        println!(
+            "{}{} {}  {}",
+            local_indicator,
+            process_display.success().bold(),
+            count_display.muted(),
+            display_command.muted()
+        );
+    }
+
+    if !expanded {
+        return;
+    }
 
     // Display all port:pid pairs on second line in [pid]:port format
     let port_pid_strs: Vec<String> = group
         .port_pid_pairs
         .iter()
-        .map(|(port, pid)| format_pid_with_port(pid, *port))
+        .map(|(port, pid)| {
+            #[cfg(feature = "probe")]
+            let latency = group
+                .ports
+                .iter()
+                .find(|p| p.port == *port)
+                .and_then(|p| p.latency)
+                .map(|l| format!(" {}", l.text()))
+                .unwrap_or_default();
+            #[cfg(not(feature = "probe"))]
+            let latency = "";
+            format!("{}{}", format_pid_with_port(pid, *port), latency)
+        })
         .collect();
 
-    println!("{}", port_pid_strs.join(", ").bright_black());
+    println!("{}", port_pid_strs.join(", ").muted());
 }
 
-fn main() -> Result<()> {
-    let cli = Cli::parse();
+/// Which multis/process_groups entries show their second line of PIDs
+struct Expansion {
+    multis: Vec<bool>,
+    process_groups: Vec<bool>,
+    /// Number the expandable entries so they can be toggled interactively
+    numbered: bool,
+}
 
-    if let Some(Commands::Init) = &cli.command {
-        let config = Config::default();
-        config.save()?;
-        println!("✓ Initialized config file: {:?}", Config::config_path()?);
+impl Expansion {
+    fn new(multis: usize, process_groups: usize, prefs: &DisplayPrefs) -> Self {
+        Self {
+            multis: vec![prefs.expand_multis; multis],
+            process_groups: vec![prefs.expand_process_groups; process_groups],
+            numbered: false,
+        }
+    }
+
+    /// Entry number (1-based) and ▸/▾ marker in interactive mode
+    fn print_marker(&self, index: usize, expanded: bool) {
+        if self.numbered {
+            let glyphs = output::glyphs();
+            let marker = if expanded {
+                glyphs.expanded
+            } else {
+                glyphs.collapsed
+            };
+            print!("{}", format!("{:>2}{} ", index + 1, marker).muted());
+        }
+    }
+
+    /// Toggle the entry with the given 0-based index (multis first, then process groups)
+    #[cfg(feature = "interactive")]
+    fn toggle(&mut self, index: usize) {
+        let multis = self.multis.len();
+        if let Some(e) = self.multis.get_mut(index) {
+            *e = !*e;
+        } else if let Some(e) = self.process_groups.get_mut(index - multis) {
+            *e = !*e;
+        }
+    }
+}
+
+/// Print the block of pinned ports above the other sections
+fn print_pinned(pinned: &[GroupedPortInfo], compact: bool, header: bool) {
+    if pinned.is_empty() {
+        return;
+    }
+    if header {
+        println!("{}", section_title("pinned", compact).heading().bold());
+    }
+    for info in pinned {
+        if compact {
+            display_grouped_port_info_compact(info);
+        } else {
+            display_grouped_port_info(info, false);
+        }
+    }
+    if header {
+        println!();
+    }
+}
+
+/// Print the dev/others/multis/process_groups sections of the table
+fn print_sections(
+    dev_processes: &[GroupedPortInfo],
+    others: &[GroupedPortInfo],
+    multis: &[GroupedPortInfo],
+    process_groups: &[ProcessGroup],
+    compact: bool,
+    header: bool,
+    expansion: &Expansion,
+) {
+    // Display dev processes first
+    if !dev_processes.is_empty() {
+        if header {
+            println!("{}", section_title("dev", compact).heading().bold());
+        }
+        for info in dev_processes {
+            if compact {
+                display_grouped_port_info_compact(info);
+            } else {
+                display_grouped_port_info(info, false);
+            }
+        }
+        if header {
+            println!();
+        }
+    }
+
+    // Display single-process others
+    if !others.is_empty() {
+        if header {
+            println!("{}", section_title("others", compact).heading().bold());
+        }
+        for info in others {
+            if compact {
+                display_grouped_port_info_compact(info);
+            } else {
+                display_grouped_port_info(info, false);
+            }
+        }
+        if header {
+            println!();
+        }
+    }
+
+    // Display multi-process same-port with 2-line format
+    if !multis.is_empty() {
+        if header {
+            println!("{}", section_title("multis", compact).heading().bold());
+        }
+        for (i, info) in multis.iter().enumerate() {
+            expansion.print_marker(i, expansion.multis[i]);
+            if compact {
+                display_grouped_port_info_compact(info);
+            } else {
+                display_grouped_port_info(info, expansion.multis[i]);
+            }
+        }
+        if header {
+            println!();
+        }
+    }
+
+    // Display process groups (same process, multiple ports)
+    if !process_groups.is_empty() {
+        if header {
+            println!(
+                "{}",
+                section_title("process_groups", compact).heading().bold()
+            );
+        }
+        for (i, group) in process_groups.iter().enumerate() {
+            let expanded = expansion.process_groups[i];
+            expansion.print_marker(multis.len() + i, expanded);
+            if compact {
+                display_process_group_compact(group);
+            } else {
+                display_process_group(group, expanded);
+            }
+        }
+        if header {
+            println!();
+        }
+    }
+}
+
+/// Compact layout: port and process on the first line, PIDs stacked below, no command
+fn display_grouped_port_info_compact(info: &GroupedPortInfo) {
+    use display_config::*;
+
+    let local_indicator = if info.is_local {
+        LOCAL_INDICATOR_LOCAL
+    } else {
+        LOCAL_INDICATOR_REMOTE
+    };
+    let port_str = format!(":{:<5}", info.port);
+
+    // Process name takes whatever is left after indicator, port and a space
+    let max_process_len = term_width().saturating_sub(2 + 6 + 1).max(1);
+    let process_display = if info.processes.len() == 1 {
+        truncate_chars(&info.processes[0], max_process_len)
+    } else {
+        let count_str = format!("{} (x{})", info.processes[0], info.processes.len());
+        truncate_chars(&count_str, max_process_len)
+    };
+
+    if highlight::matches(info.port, &info.processes) {
+        let row = format!("{} {}", port_str, process_display);
+        println!("{}{}", local_indicator, row.highlight());
+    } else {
+        println!(
+            "{}{} {}",
+            local_indicator,
+            port_str.accent().bold(),
+            process_display.success()
+        );
+    }
+    println!(
+        "  {} {}",
+        format_pid_list(&info.pids, Some(3)).muted(),
+        format!(
+            "@{} {}{}",
+            info.interface,
+            info.protocol.name().to_ascii_lowercase(),
+            connection_text(info)
+                .map(|text| format!(" {}", text))
+                .unwrap_or_default()
+        )
+        .muted()
+    );
+}
+
+/// Compact layout for process groups: name and port count, then port:pid pairs
+fn display_process_group_compact(group: &ProcessGroup) {
+    use display_config::*;
+
+    let local_indicator = if group.is_local {
+        LOCAL_INDICATOR_LOCAL
+    } else {
+        LOCAL_INDICATOR_REMOTE
+    };
+    let max_process_len = term_width().saturating_sub(2).max(1);
+
+    let process_display = truncate_chars(&group.process_name, max_process_len);
+    let count_display = format!("(x{})", group.port_pid_pairs.len());
+    if group
+        .ports
+        .iter()
+        .any(|info| highlight::matches(info.port, &info.processes))
+    {
+        let row = format!("{} {}", process_display, count_display);
+        println!("{}{}", local_indicator, row.highlight());
+    } else {
+        println!(
+            "{}{} {}",
+            local_indicator,
+            process_display.success().bold(),
+            count_display.muted()
+        );
+    }
+    for (port, pid) in &group.port_pid_pairs {
+        println!("  {}", format_pid_with_port(pid, *port).muted());
+    }
+}
+
+/// Print one tab-separated record per port:
+/// port, process, pids, section, command, interface, family, port name, protocol,
+/// state, peer
+///
+/// PIDs are comma-separated. Fields never contain tabs, so the output can be
+/// split with `cut -f` or `awk -F'\t'`.
+fn print_plain_records(
+    dev_processes: &[GroupedPortInfo],
+    others: &[GroupedPortInfo],
+    multis: &[GroupedPortInfo],
+    process_groups: &[ProcessGroup],
+) {
+    let sanitize = |s: &str| s.replace(['\t', '\n'], " ");
+    // Scripts select columns by position: new ones go at the end
+    let sections = [("dev", dev_processes), ("others", others), ("multis", multis)];
+
+    for (section, infos) in sections {
+        for info in infos {
+            println!(
+                "{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}",
+                info.port,
+                sanitize(&info.processes.join(",")),
+                info.pids.join(","),
+                section,
+                sanitize(&info.command),
+                info.interface,
+                info.family,
+                sanitize(info.port_name.as_deref().unwrap_or_default()),
+                info.protocol.name(),
+                info.state,
+                info.peer.as_deref().unwrap_or_default()
+            );
+        }
+    }
+
+    for group in process_groups {
+        for info in &group.ports {
+            println!(
+                "{}\t{}\t{}\tprocess_groups\t{}\t{}\t{}\t{}\t{}\t{}\t{}",
+                info.port,
+                sanitize(&group.process_name),
+                info.pids.join(","),
+                sanitize(&info.command),
+                info.interface,
+                info.family,
+                sanitize(info.port_name.as_deref().unwrap_or_default()),
+                info.protocol.name(),
+                info.state,
+                info.peer.as_deref().unwrap_or_default()
+            );
+        }
+    }
+}
+
+/// `--a11y`: one labeled sentence per port, without colors or column alignment
+///
+/// Everything the table conveys through color or position (section, exposure, hung
+/// servers) is spelled out, so screen readers and braille displays read it in order.
+fn print_a11y_records(
+    pinned: &[GroupedPortInfo],
+    dev_processes: &[GroupedPortInfo],
+    others: &[GroupedPortInfo],
+    multis: &[GroupedPortInfo],
+    process_groups: &[ProcessGroup],
+    unowned: &[unowned::Unowned],
+) {
+    let describe = |kind: &str, process: &str, info: &GroupedPortInfo| {
+        let mut fields = vec![format!("{} port {}", kind, info.port)];
+        if info.protocol == collector::Protocol::Udp {
+            fields.push("UDP".to_string());
+        }
+        if connection_text(info).is_some() {
+            fields.push(info.state.to_ascii_lowercase().replace('_', " "));
+        }
+        if let Some(peer) = &info.peer {
+            fields.push(format!("peer {}", peer));
+        }
+        if let Some(name) = &info.port_name {
+            fields.push(format!("named {}", name));
+        }
+        fields.push(format!("process {}", process));
+        fields.push(match info.pids.as_slice() {
+            [pid] => format!("pid {}", pid),
+            pids => format!("{} pids {}", pids.len(), pids.join(", ")),
+        });
+        let address = if interfaces::is_wildcard(&info.address) {
+            "any"
+        } else {
+            &info.address
+        };
+        fields.push(format!("address {} {}", address, info.family));
+        fields.push(
+            match info.exposure {
+                interfaces::Exposure::Local => "reachable from this machine only",
+                interfaces::Exposure::Vpn => "reachable over the VPN",
+                interfaces::Exposure::Lan => "reachable from the local network",
+            }
+            .to_string(),
+        );
+        #[cfg(feature = "probe")]
+        match &info.latency {
+            Some(probe::Latency::Hung) => fields.push("not accepting connections".to_string()),
+            Some(latency) => fields.push(format!("connects in {}", latency.text())),
+            None => {}
+        }
+        match info.forwarding {
+            Some(remote::Forwarding::SshTunnel) => {
+                fields.push("reachable only through ssh -L".to_string())
+            }
+            Some(
+                forwarding @ (remote::Forwarding::Public
+                | remote::Forwarding::Org
+                | remote::Forwarding::Private),
+            ) => fields.push(format!("forwarded as {}", forwarding.text())),
+            Some(forwarding) => fields.push(forwarding.text().to_string()),
+            None => {}
+        }
+        if let Some(health) = info.health {
+            fields.push(format!("health {}", health.text()));
+        }
+        fields.push(match &info.label {
+            Some(label) => format!("service {}", label),
+            None => format!("command {}", info.command),
+        });
+        if highlight::matches(info.port, &info.processes) {
+            fields.push("highlighted".to_string());
+        }
+        println!("{}.", fields.join(", "));
+    };
+
+    for info in pinned {
+        describe("Pinned", &info.processes.join(", "), info);
+    }
+    for info in dev_processes {
+        describe("Dev server on", &info.processes.join(", "), info);
+    }
+    for info in others {
+        describe("Other listener on", &info.processes.join(", "), info);
+    }
+    for info in multis {
+        describe("Shared", &info.processes.join(", "), info);
+    }
+    for group in process_groups {
+        for info in &group.ports {
+            describe("Process group member on", &group.process_name, info);
+        }
+    }
+    for socket in unowned {
+        println!(
+            "Listener without visible owner on port {}, address {}.",
+            socket.port, socket.local
+        );
+    }
+}
+
+/// One object per port in a JSON array, in the order of the table sections
+#[derive(Serialize)]
+struct JsonRecord<'a> {
+    port: u16,
+    process: &'a str,
+    pids: Vec<&'a str>,
+    command: &'a str,
+    start_time: &'a str,
+    /// In a `[[ports]]` range of the config
+    monitored: bool,
+    /// Table section: dev, others, multis or process_groups
+    section: &'static str,
+    address: &'a str,
+    interface: &'a str,
+    family: &'a str,
+    /// "TCP" or "UDP"
+    protocol: &'static str,
+    /// "LISTEN", "UNCONN" (UDP) or the state of a connection
+    state: &'a str,
+    /// Remote address:port of a connection
+    #[serde(skip_serializing_if = "Option::is_none")]
+    peer: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    label: Option<&'a str>,
+    /// Name of the `[[ports]]` range the port is in
+    #[serde(skip_serializing_if = "Option::is_none")]
+    port_name: Option<&'a str>,
+    /// Visibility in Codespaces or over SSH
+    #[serde(skip_serializing_if = "Option::is_none")]
+    forwarding: Option<&'static str>,
+}
+
+impl<'a> JsonRecord<'a> {
+    fn new(
+        info: &'a GroupedPortInfo,
+        process: &'a str,
+        section: &'static str,
+        monitored: bool,
+    ) -> Self {
+        Self {
+            port: info.port,
+            process,
+            pids: info.pids.iter().map(|p| &**p).collect(),
+            command: &info.command,
+            start_time: &info.start_time,
+            monitored,
+            section,
+            address: &info.address,
+            interface: &info.interface,
+            family: &info.family,
+            protocol: info.protocol.name(),
+            state: &info.state,
+            peer: info.peer.as_deref(),
+            label: info.label.as_deref(),
+            port_name: info.port_name.as_deref(),
+            forwarding: info.forwarding.map(|f| f.text()),
+        }
+    }
+}
+
+fn json_records<'a>(
+    dev_processes: &'a [GroupedPortInfo],
+    others: &'a [GroupedPortInfo],
+    multis: &'a [GroupedPortInfo],
+    process_groups: &'a [ProcessGroup],
+    monitored: &[u16],
+) -> Vec<JsonRecord<'a>> {
+    let sections = [("dev", dev_processes), ("others", others), ("multis", multis)];
+    let mut records = Vec::new();
+    for (section, infos) in sections {
+        for info in infos {
+            let process = info.processes.first().map_or("", |p| &**p);
+            let is_monitored = monitored.contains(&info.port);
+            records.push(JsonRecord::new(info, process, section, is_monitored));
+        }
+    }
+    for group in process_groups {
+        for info in &group.ports {
+            let is_monitored = monitored.contains(&info.port);
+            records.push(JsonRecord::new(
+                info,
+                &group.process_name,
+                "process_groups",
+                is_monitored,
+            ));
+        }
+    }
+    records
+}
+
+/// One `cwd:1:1: ...` line per port for Vim's quickfix list; ports whose working
+/// directory can't be read are left out
+fn print_quickfix_records(
+    dev_processes: &[GroupedPortInfo],
+    others: &[GroupedPortInfo],
+    multis: &[GroupedPortInfo],
+    process_groups: &[ProcessGroup],
+) {
+    let mut rows: Vec<(&GroupedPortInfo, &str)> = Vec::new();
+    for info in dev_processes.iter().chain(others).chain(multis) {
+        rows.push((info, info.processes.first().map_or("", |p| &**p)));
+    }
+    for group in process_groups {
+        rows.extend(group.ports.iter().map(|info| (info, &*group.process_name)));
+    }
+
+    let cwds = pool::map(&rows, |(info, _)| {
+        info.pids.first().and_then(|pid| get_process_cwd(pid))
+    });
+    for ((info, process), cwd) in rows.iter().zip(cwds) {
+        let Some(cwd) = cwd else { continue };
+        println!(
+            "{}:1:1: port {} {} (pid {}){}",
+            anonymize::text(&cwd),
+            info.port,
+            process,
+            info.pids.join(","),
+            info.port_name
+                .as_deref()
+                .map_or(String::new(), |name| format!(" [{}]", name))
+        );
+    }
+}
+
+/// One `--format` line per port, in the order of the table sections
+fn print_template_records(
+    template: &template::Template,
+    dev_processes: &[GroupedPortInfo],
+    others: &[GroupedPortInfo],
+    multis: &[GroupedPortInfo],
+    process_groups: &[ProcessGroup],
+) {
+    let sections = [("dev", dev_processes), ("others", others), ("multis", multis)];
+    let mut rows: Vec<(&GroupedPortInfo, &str)> = Vec::new();
+    for (section, infos) in sections {
+        rows.extend(infos.iter().map(|info| (info, section)));
+    }
+    for group in process_groups {
+        rows.extend(group.ports.iter().map(|info| (info, "process_groups")));
+    }
+
+    let cwds: Vec<String> = if template.uses("cwd") {
+        pool::map(&rows, |(info, _)| {
+            info.pids
+                .first()
+                .and_then(|pid| get_process_cwd(pid))
+                .unwrap_or_default()
+        })
+    } else {
+        vec![String::new(); rows.len()]
+    };
+    for ((info, section), cwd) in rows.iter().zip(&cwds) {
+        println!("{}", template.render(info, section, cwd));
+    }
+}
+
+fn main() -> Result<()> {
+    bug_report::install_panic_hook();
+    let matches = Cli::command().get_matches();
+    let cli = Cli::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+    paths::init(cli.root.clone());
+    output::init_colors(cli.a11y);
+    theme::init(cli.theme);
+    highlight::init(&cli.highlight);
+    output::init_glyphs(if cli.ascii || cli.a11y {
+        Some(output::GlyphSet::Ascii)
+    } else {
+        cli.glyphs
+    });
+    anonymize::init(cli.anonymize);
+    pool::init(cli.jobs);
+    secrets::init(cli.show_secrets);
+    collector::init_strict(cli.strict_parse, cli.verbose);
+    collector::init_backend(cli.backend);
+    // Listings record their run once the config is loaded below
+    if !matches!(cli.command, None | Some(Commands::List { .. })) {
+        insights::record(&matches);
+    }
+
+    if cli.monitor_temp_clear {
+        temp_ports::clear()?;
+    }
+    if let Some(spec) = &cli.monitor_temp {
+        temp_ports::add(spec)?;
+    }
+
+    if cli.bug_report {
+        return bug_report::generate(collector::Backend::detect().name());
+    }
+
+    #[cfg(feature = "probe")]
+    if cli.detect_hangs {
+        return probe::run_detect_hangs();
+    }
+
+    if let Some(Commands::Init) = &cli.command {
+        let _lock = Config::lock()?;
+        let config = Config::default();
+        config.save()?;
+        println!(
+            "{} Initialized config file: {:?}",
+            output::glyphs().check,
+            paths::config_file()?
+        );
         return Ok(());
     }
 
+    if let Some(Commands::Config { action }) = &cli.command {
+        return match action {
+            ConfigAction::Get { key, json } => config_edit::get(key, *json),
+            ConfigAction::Set { key, value } => config_edit::set(key, value),
+            ConfigAction::Add { ports, name } => config_edit::add(ports, name.as_deref()),
+            ConfigAction::Remove { target } => config_edit::remove(target),
+            ConfigAction::List => config_edit::list(),
+        };
+    }
+
+    if let Some(Commands::Schema { kind }) = &cli.command {
+        return schema::run(*kind);
+    }
+
+    if let Some(Commands::Verify) = &cli.command {
+        return verify::run();
+    }
+
+    if let Some(Commands::Hook { action }) = &cli.command {
+        return match action {
+            HookAction::Install { hook, force } => git_hook::install(*hook, *force),
+        };
+    }
+
+    if let Some(Commands::Pin { ports }) = &cli.command {
+        return pins::pin(ports);
+    }
+
+    if let Some(Commands::Unpin { ports }) = &cli.command {
+        return pins::unpin(ports);
+    }
+
+    if let Some(Commands::Purge { yes }) = &cli.command {
+        return purge(*yes);
+    }
+
+    if let Some(Commands::Explain { port, pid }) = &cli.command {
+        return match port {
+            Some(port) => explain::run(*port),
+            None => explain::run_pids(pid),
+        };
+    }
+
+    if let Some(Commands::Doctor) = &cli.command {
+        return doctor::run();
+    }
+
+    if let Some(Commands::WhyFailed { port, since }) = &cli.command {
+        return why_failed::run(*port, since);
+    }
+
+    if let Some(Commands::Hold {
+        port,
+        name,
+        no_restart,
+        stop,
+        command,
+    }) = &cli.command
+    {
+        return match stop {
+            Some(name) => hold::stop(name),
+            None => hold::run(command, *port, name.as_deref(), !no_restart),
+        };
+    }
+
+    #[cfg(feature = "daemon")]
+    if let Some(Commands::Daemon {
+        interval,
+        detect_hangs,
+        events,
+        output_file,
+        truncate,
+        rotate_size,
+        rotate_keep,
+        api,
+    }) = &cli.command
+    {
+        let output_file = output_file
+            .clone()
+            .map(|path| events::Sink::file(path, *truncate, *rotate_size, *rotate_keep))
+            .transpose()?;
+        return daemon::run(
+            std::time::Duration::from_secs_f32(*interval),
+            *detect_hangs,
+            *events,
+            output_file,
+            *api,
+        );
+    }
+
+    #[cfg(feature = "daemon")]
+    if let Some(Commands::Api { action }) = &cli.command {
+        #[cfg(unix)]
+        return match action {
+            ApiAction::Ports => api::print_ports(),
+            ApiAction::Events => api::print_events(),
+            ApiAction::Workspace { path } => api::print_workspace(path.as_deref()),
+            ApiAction::Kill { port, yes, force } => api::kill_port(*port, *yes, *force),
+        };
+        #[cfg(not(unix))]
+        anyhow::bail!("The daemon API needs Unix sockets");
+    }
+
+    if let Some(Commands::Health) = &cli.command {
+        return health::run();
+    }
+
+    #[cfg(feature = "history")]
+    if let Some(Commands::Stats {
+        startup,
+        heatmap,
+        weeks,
+    }) = &cli.command
+    {
+        if *heatmap {
+            return stats::run_heatmap(*weeks);
+        }
+        anyhow::ensure!(*startup, "Choose what to report: --startup or --heatmap");
+        return stats::run_startup();
+    }
+
+    if let Some(Commands::Insights { clear }) = &cli.command {
+        return insights::run(*clear);
+    }
+
+    #[cfg(feature = "history")]
+    if let Some(Commands::Report { weekly }) = &cli.command {
+        anyhow::ensure!(*weekly, "Choose what to report: --weekly");
+        return report::run_weekly();
+    }
+
+    #[cfg(feature = "history")]
+    if let Some(Commands::History { action }) = &cli.command {
+        return match action {
+            HistoryAction::Export {
+                format,
+                since,
+                fields,
+            } => export::run(*format, since, fields),
+        };
+    }
+
+    if let Some(Commands::Alloc {
+        count,
+        range,
+        format,
+        reserve,
+        lease,
+        owner,
+    }) = &cli.command
+    {
+        let options = alloc::Options {
+            count: *count,
+            format: *format,
+            reserve: *reserve,
+            lease_secs: *lease,
+            owner: *owner,
+        };
+        return alloc::run(range, &options);
+    }
+
+    if let Some(Commands::Plan { size, name, save }) = &cli.command {
+        return plan::run(*size, name.as_deref(), *save);
+    }
+
+    if let Some(Commands::Up { procfile, services }) = &cli.command {
+        return procfile::up(procfile, services);
+    }
+
+    if let Some(Commands::Down { services }) = &cli.command {
+        return procfile::down(services);
+    }
+
+    if let Some(Commands::Logs {
+        port,
+        lines,
+        follow,
+    }) = &cli.command
+    {
+        return logs::run(*port, *lines, *follow);
+    }
+
+    if let Some(Commands::Tabs { devtools_port }) = &cli.command {
+        return tabs::run(*devtools_port);
+    }
+
+    if let Some(Commands::Tunnels) = &cli.command {
+        return tunnel::run_list();
+    }
+
+    if let Some(Commands::Tunnel { action }) = &cli.command {
+        return match action {
+            TunnelAction::Add { port, target, via } => tunnel::add(*port, target, via),
+            TunnelAction::Rm { port } => tunnel::rm(*port),
+        };
+    }
+
+    if let Some(Commands::Supervise { name }) = &cli.command {
+        return supervisor::supervise(name);
+    }
+
+    if let Some(Commands::Leaks { command }) = &cli.command {
+        return leaks::run(command);
+    }
+
+    if let Some(Commands::Related { port }) = &cli.command {
+        return related::run(*port);
+    }
+
+    if let Some(Commands::Check { port }) = &cli.command {
+        return check::run(*port);
+    }
+
+    if let Some(Commands::Wait {
+        port,
+        listen,
+        timeout,
+        ..
+    }) = &cli.command
+    {
+        let timeout = timeout.map(std::time::Duration::from_secs_f32);
+        return wait::run(*port, *listen, timeout);
+    }
+
+    if let Some(Commands::Devcontainer { side }) = &cli.command {
+        return devcontainer::run(*side);
+    }
+
+    if let Some(Commands::Url { port, qr }) = &cli.command {
+        return url::run(*port, *qr);
+    }
+
+    if let Some(Commands::Advertise {
+        port,
+        name,
+        service_type,
+    }) = &cli.command
+    {
+        return advertise::run(*port, name, service_type);
+    }
+
+    if let Some(Commands::Kill {
+        port,
+        pid,
+        tree,
+        pgroup,
+        force,
+        all,
+        timeout,
+    }) = &cli.command
+    {
+        let mode = if *tree {
+            kill::KillMode::Tree
+        } else if *pgroup {
+            kill::KillMode::Pgroup
+        } else {
+            kill::KillMode::Pid
+        };
+        let target = match port {
+            Some(port) => kill::Target::Port(*port),
+            None => kill::Target::Pids(pid.clone()),
+        };
+        let options = kill::Options {
+            mode,
+            force: *force,
+            all: *all,
+            timeout: std::time::Duration::from_secs_f32(*timeout),
+        };
+        return kill::run(&target, &options);
+    }
+
+    #[cfg(feature = "tray")]
+    if let Some(Commands::Tray) = &cli.command {
+        return tray::run();
+    }
+
+    #[cfg(feature = "self-update")]
+    if let Some(Commands::SelfUpdate {
+        channel,
+        check,
+        force,
+    }) = &cli.command
+    {
+        return self_update::run(*channel, *check, *force);
+    }
+
     let config = Config::load()?;
-    let port_infos = get_port_info()?;
+    secrets::use_config(&config);
+    collector::use_config(&config.collector);
+    output::use_config(&config);
+    theme::use_config(&config);
+    insights::use_config(&config);
+    insights::record(&matches);
+    let template = cli
+        .format
+        .as_deref()
+        .map(template::Template::parse)
+        .transpose()?;
+
+    if cli.watch {
+        let output_file = cli
+            .output_file
+            .clone()
+            .map(|path| events::Sink::file(path, cli.truncate, cli.rotate_size, cli.rotate_keep))
+            .transpose()?;
+        return watch::run(
+            std::time::Duration::from_secs_f32(cli.interval),
+            output_file,
+            |port_infos| print_listing(&cli, &config, template.as_ref(), port_infos),
+        );
+    }
+    let port_infos = if requested_states(&cli).is_some() {
+        get_socket_info()?
+    } else {
+        get_port_info()?
+    };
+    print_listing(&cli, &config, template.as_ref(), port_infos)
+}
 
-    let filtered = filter_port_infos(port_infos, cli.port, cli.process.as_deref());
+/// The listing asked for on the command line, from already collected ports
+fn print_listing(
+    cli: &Cli,
+    config: &Config,
+    template: Option<&template::Template>,
+    port_infos: Vec<PortInfo>,
+) -> Result<()> {
+    let all_port_infos = port_infos.clone();
+
+    if let Some(Commands::List { group_only: true }) = &cli.command {
+        return summary::print_range_utilization(config, &all_port_infos);
+    }
+
+    let process_filter = cli.process.clone().map(|name| ProcessFilter {
+        name,
+        exact: cli.process_exact,
+        case_sensitive: cli.case_sensitive,
+    });
+    let mut filtered = filter_port_infos(
+        port_infos,
+        cli.port,
+        process_filter.as_ref(),
+        cli.interface.as_deref(),
+    );
+    let where_filter = query::from_args(cli.where_expr.as_deref(), cli.query.as_deref(), config)?;
+    if let Some(expr) = &where_filter {
+        filtered.retain(|info| expr.matches(info));
+    }
+    if !cli.pid.is_empty() {
+        filtered.retain(|info| holds_pid(info, &cli.pid));
+    }
+    if cli.tcp || cli.udp {
+        let protocol = if cli.udp {
+            collector::Protocol::Udp
+        } else {
+            collector::Protocol::Tcp
+        };
+        filtered.retain(|info| info.protocol == protocol);
+    }
+    let states = requested_states(cli);
+    if let Some(states) = &states {
+        filtered.retain(|info| state_matches(states, &info.state));
+    }
+    if cli.anonymize {
+        for info in &mut filtered {
+            info.command = anonymize::text(&info.command);
+            info.user = anonymize::user(&info.user);
+        }
+    }
 
+    // Sockets lsof couldn't attribute; they can't match a process or interface filter
+    let unowned: Vec<unowned::Unowned> = if cli.process.is_none()
+        && cli.interface.is_none()
+        && !cli.udp
+        && states.is_none()
+        && where_filter.is_none()
+        && cli.pid.is_empty()
+    {
+        unowned::collect(&all_port_infos)
+            .into_iter()
+            .filter(|u| cli.port.is_none_or(|p| p == u.port))
+            .collect()
+    } else {
+        Vec::new()
+    };
+    let (unowned, unowned_hidden) = if cli.all || cli.port.is_some() {
+        (unowned, 0)
+    } else {
+        (Vec::new(), unowned.len())
+    };
+
+    let output = if cli.json {
+        OutputFormat::Json
+    } else if cli.machine {
+        OutputFormat::Auto.resolve(true)
+    } else {
+        cli.output.resolve(false)
+    };
     if filtered.is_empty() {
-        println!("{}", "No ports found".yellow());
+        if output == OutputFormat::Json {
+            println!("[]");
+        }
+        if output == OutputFormat::Lua {
+            println!("return {{}}");
+        }
+        if output == OutputFormat::Table {
+            if unowned.is_empty() {
+                println!("{}", "No ports found".warning());
+            } else {
+                unowned::print_section(&unowned, cli.compact, !cli.no_header);
+            }
+            if cli.show_hidden_count {
+                summary::HiddenCounts {
+                    filtered: distinct_port_count(&all_port_infos),
+                    permission_denied: unowned_hidden,
+                    ..Default::default()
+                }
+                .print_note();
+            }
+        }
         return Ok(());
     }
 
-    let grouped = group_by_port(filtered, &config);
+    let grouped = group_by_port(filtered, config);
+    #[cfg(feature = "hooks")]
+    let grouped = match &config.hook {
+        Some(hook) => hook::apply(hook, grouped)?,
+        None => grouped,
+    };
+    let mut hidden = summary::HiddenCounts {
+        filtered: distinct_port_count(&all_port_infos).saturating_sub(grouped.len()),
+        permission_denied: unowned_hidden,
+        ..Default::default()
+    };
+
+    // Pinned ports get their own block at the top of the table, whatever the sorting
+    let (mut pinned, grouped): (Vec<_>, Vec<_>) =
+        if output == OutputFormat::Table && template.is_none() {
+            grouped
+                .into_iter()
+                .partition(|info| config.display.pinned.contains(&info.port))
+        } else {
+            (Vec::new(), grouped)
+        };
+    pinned.sort_by_key(|info| config.display.pinned.iter().position(|p| *p == info.port));
 
     // Separate into categories: dev (score >= threshold), non-dev
     let (dev_processes, non_dev): (Vec<_>, Vec<_>) = grouped
         .into_iter()
         .partition(|info| info.dev_score >= config.score_threshold);
 
-    // If --all is not set, only show dev processes
-    let non_dev = if cli.all { non_dev } else { vec![] };
+    // If --all is not set, only show dev processes (a --pid is asked for explicitly)
+    let non_dev = if cli.all || !cli.pid.is_empty() {
+        non_dev
+    } else {
+        hidden.not_dev = non_dev.len();
+        vec![]
+    };
 
     // Group non-dev by process name to detect multi-port processes
     let (mut others, mut multis, process_group_items): (Vec<_>, Vec<_>, Vec<_>) = {
         use std::collections::HashMap;
-        let mut by_process: HashMap<String, Vec<GroupedPortInfo>> = HashMap::new();
+        let mut by_process: HashMap<Arc<str>, Vec<GroupedPortInfo>> = HashMap::new();
 
         for item in non_dev {
             let proc_name = item.processes.first().cloned().unwrap_or_default();
@@ -760,56 +3206,211 @@ fn main() -> Result<()> {
         process_groups.sort_by(|a, b| b.start_time.cmp(&a.start_time));
     } else {
         // Default: sort by dev_score (descending), then port number
-        dev_processes.sort_by(|a, b| b.dev_score.cmp(&a.dev_score).then(a.port.cmp(&b.port)));
-        others.sort_by_key(|info| info.port);
-        multis.sort_by_key(|info| info.port);
+        // Listeners (without a peer) before the connections on their port
+        let socket = |info: &GroupedPortInfo| (info.port, info.protocol, info.peer.clone());
+        dev_processes.sort_by(|a, b| b.dev_score.cmp(&a.dev_score).then(socket(a).cmp(&socket(b))));
+        others.sort_by_key(socket);
+        multis.sort_by_key(socket);
         process_groups.sort_by_key(|g| g.process_name.clone());
     }
 
     // Apply limit
     let limit = if cli.limit > 0 { cli.limit } else { usize::MAX };
-    let dev_processes: Vec<_> = dev_processes.into_iter().take(limit).collect();
-    let others: Vec<_> = others.into_iter().take(limit).collect();
-    let multis: Vec<_> = multis.into_iter().take(limit).collect();
-    let process_groups: Vec<_> = process_groups.into_iter().take(limit).collect();
+    hidden.over_limit = dev_processes.len().saturating_sub(limit)
+        + others.len().saturating_sub(limit)
+        + multis.len().saturating_sub(limit)
+        + process_groups.iter().skip(limit).map(|g| g.ports.len()).sum::<usize>();
+    let mut dev_processes: Vec<_> = dev_processes.into_iter().take(limit).collect();
+    let mut others: Vec<_> = others.into_iter().take(limit).collect();
+    let mut multis: Vec<_> = multis.into_iter().take(limit).collect();
+    let mut process_groups: Vec<_> = process_groups.into_iter().take(limit).collect();
+
+    remote::annotate(
+        pinned
+            .iter_mut()
+            .chain(dev_processes.iter_mut())
+            .chain(others.iter_mut())
+            .chain(multis.iter_mut())
+            .chain(process_groups.iter_mut().flat_map(|g| g.ports.iter_mut())),
+    );
 
-    let total_count = dev_processes.len() + others.len() + multis.len() + process_groups.len();
-    println!("\n{} port(s) detected:\n", total_count);
+    if cli.watch {
+        health::annotate(
+            config,
+            pinned
+                .iter_mut()
+                .chain(dev_processes.iter_mut())
+                .chain(others.iter_mut())
+                .chain(multis.iter_mut())
+                .chain(process_groups.iter_mut().flat_map(|g| g.ports.iter_mut())),
+        );
+    }
 
-    // Display dev processes first
-    if !dev_processes.is_empty() {
-        println!("{}", "dev".bright_blue().bold());
-        for info in &dev_processes {
-            display_grouped_port_info(info, false);
-        }
-        println!();
+    #[cfg(feature = "probe")]
+    if cli.latency {
+        probe::probe_all(
+            pinned
+                .iter_mut()
+                .chain(dev_processes.iter_mut())
+                .chain(others.iter_mut())
+                .chain(multis.iter_mut())
+                .chain(process_groups.iter_mut().flat_map(|g| g.ports.iter_mut())),
+        );
     }
 
-    // Display single-process others
-    if !others.is_empty() {
-        println!("{}", "others".bright_blue().bold());
-        for info in &others {
-            display_grouped_port_info(info, false);
+    if let Some(template) = &template {
+        print_template_records(template, &dev_processes, &others, &multis, &process_groups);
+        return Ok(());
+    }
+
+    if output == OutputFormat::Plain {
+        print_plain_records(&dev_processes, &others, &multis, &process_groups);
+        return Ok(());
+    }
+
+    if output == OutputFormat::Quickfix {
+        print_quickfix_records(&dev_processes, &others, &multis, &process_groups);
+        return Ok(());
+    }
+
+    if output == OutputFormat::Json || output == OutputFormat::Lua {
+        let monitored = config.monitored_ports()?;
+        let records = json_records(
+            &dev_processes,
+            &others,
+            &multis,
+            &process_groups,
+            &monitored,
+        );
+        if output == OutputFormat::Lua {
+            println!("return {}", output::lua(&serde_json::to_value(&records)?));
+        } else {
+            println!("{}", serde_json::to_string_pretty(&records)?);
         }
-        println!();
+        return Ok(());
     }
 
-    // Display multi-process same-port with 2-line format
-    if !multis.is_empty() {
-        println!("{}", "multis".bright_blue().bold());
-        for info in &multis {
-            display_grouped_port_info(info, true);
+    // Use the compact layout when requested or when the terminal is too narrow
+    let compact = cli.compact || term_width() < display_config::COMPACT_WIDTH_THRESHOLD;
+    let header = !cli.no_header;
+
+    let total_count = pinned.len()
+        + dev_processes.len()
+        + others.len()
+        + multis.len()
+        + process_groups.len()
+        + unowned.len();
+    let expansion = Expansion::new(multis.len(), process_groups.len(), &config.display);
+
+    // Instances that fell back to the next port because the preferred one was taken
+    let shown: Vec<GroupedPortInfo> = pinned
+        .iter()
+        .chain(&dev_processes)
+        .chain(&others)
+        .chain(&multis)
+        .chain(process_groups.iter().flat_map(|g| &g.ports))
+        .cloned()
+        .collect();
+    let chains = chains::detect(&shown);
+
+    #[cfg(feature = "interactive")]
+    if cli.interactive {
+        let mut expansion = expansion;
+        let reclaim = chains.first().map(|c| c.preferred);
+        return interactive::run(&mut expansion, reclaim, |expansion| {
+            println!("{} port(s) detected:\n", total_count);
+            print_pinned(&pinned, false, header);
+            print_sections(
+                &dev_processes,
+                &others,
+                &multis,
+                &process_groups,
+                false,
+                header,
+                expansion,
+            );
+            chains::print_section(&chains, header);
+        });
+    }
+    if cli.stats {
+        let shown = pinned.len()
+            + dev_processes.len()
+            + others.len()
+            + multis.len()
+            + process_groups.iter().map(|g| g.ports.len()).sum::<usize>()
+            + unowned.len();
+        let stats = summary::SocketStats::collect()?;
+        summary::print(&stats, shown, hidden.total());
+    } else if header {
+        if cli.a11y {
+            println!("{} port(s) detected.", total_count);
+        } else if compact {
+            println!("{} port(s)", total_count);
+        } else {
+            println!("\n{} port(s) detected:\n", total_count);
         }
+    }
+
+    if cli.a11y {
+        print_a11y_records(
+            &pinned,
+            &dev_processes,
+            &others,
+            &multis,
+            &process_groups,
+            &unowned,
+        );
+    } else {
+        print_pinned(&pinned, compact, header);
+        print_sections(
+            &dev_processes,
+            &others,
+            &multis,
+            &process_groups,
+            compact,
+            header,
+            &expansion,
+        );
+        unowned::print_section(&unowned, compact, header);
+    }
+    chains::print_section(&chains, header);
+
+    // Dev servers holding the port of an installed (but stopped) system service
+    let conflicts: Vec<String> = dev_processes
+        .iter()
+        .filter_map(|info| {
+            let process = info.processes.first().map(|p| &**p).unwrap_or_default();
+            well_known::conflict_warning(info.port, process)
+        })
+        .collect();
+    if !conflicts.is_empty() {
         println!();
+        for warning in &conflicts {
+            println!("{}", warning.warning());
+        }
     }
 
-    // Display process groups (same process, multiple ports)
-    if !process_groups.is_empty() {
-        println!("{}", "process_groups".bright_blue().bold());
-        for group in &process_groups {
-            display_process_group(group);
+    // PORT in the project's .env files that its processes don't listen on
+    let env_warnings = dotenv::mismatch_warnings(&all_port_infos);
+    if !env_warnings.is_empty() {
+        println!();
+        for warning in &env_warnings {
+            println!("{}", warning.warning());
         }
     }
 
+    if cli.show_hidden_count {
+        hidden.print_note();
+    }
+
+    // Supervised commands and tunnels with their status
+    hold::print_section("services", "service", &all_port_infos, header)?;
+    hold::print_section("held", "hold", &all_port_infos, header)?;
+    tunnel::print_managed_section(&all_port_infos, header)?;
+
+    if let Some(serial) = &cli.adb {
+        adb::print_section(serial, &all_port_infos)?;
+    }
+
     Ok(())
 }
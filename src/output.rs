@@ -0,0 +1,388 @@
+//! Output policy: which format to print and whether to emit ANSI colors
+
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use std::io::IsTerminal;
+use std::sync::OnceLock;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    /// Table on a terminal, plain records when piped or running in CI
+    Auto,
+    /// Colored table grouped into sections
+    Table,
+    /// One tab-separated record per port, no headers or colors
+    Plain,
+    /// JSON array with one object per port, for jq and other tools
+    Json,
+    /// Lua table with the fields of the JSON output, for Neovim's `load()`
+    Lua,
+    /// `cwd:1:1: port ...` lines for Vim's quickfix list
+    Quickfix,
+}
+
+/// What the output policy depends on: whether stdout is a terminal, and the
+/// environment variables
+struct Environment<F> {
+    terminal: bool,
+    var: F,
+}
+
+impl Environment<fn(&str) -> Option<String>> {
+    fn current() -> Self {
+        Self {
+            terminal: std::io::stdout().is_terminal(),
+            var: |name| std::env::var(name).ok(),
+        }
+    }
+}
+
+impl<F: Fn(&str) -> Option<String>> Environment<F> {
+    /// Set to something other than empty or `0`
+    fn is_set(&self, name: &str) -> bool {
+        (self.var)(name).is_some_and(|v| !v.is_empty() && v != "0")
+    }
+
+    /// `CI=true`, set by GitHub Actions, GitLab CI, CircleCI, ...
+    fn is_ci(&self) -> bool {
+        self.is_set("CI") && (self.var)("CI").is_some_and(|v| v != "false")
+    }
+}
+
+impl OutputFormat {
+    /// Resolve `auto` for the current environment; `machine` (`--machine`) asks for
+    /// records instead of the table, as JSON in CI
+    pub fn resolve(self, machine: bool) -> Self {
+        self.resolve_in(&Environment::current(), machine)
+    }
+
+    fn resolve_in<F: Fn(&str) -> Option<String>>(
+        self,
+        env: &Environment<F>,
+        machine: bool,
+    ) -> Self {
+        match self {
+            OutputFormat::Auto if machine && env.is_ci() => OutputFormat::Json,
+            OutputFormat::Auto if machine || env.is_ci() || !env.terminal => OutputFormat::Plain,
+            OutputFormat::Auto => OutputFormat::Table,
+            format => format,
+        }
+    }
+}
+
+/// The decision of `init_colors` for a given environment
+fn colors_enabled<F: Fn(&str) -> Option<String>>(env: &Environment<F>, a11y: bool) -> bool {
+    if a11y {
+        false
+    } else if env.is_set("CLICOLOR_FORCE") {
+        true
+    } else {
+        !env.is_set("NO_COLOR") && (env.var)("TERM").is_none_or(|t| t != "dumb") && env.terminal
+    }
+}
+
+/// `value` as a Lua literal: arrays and objects become tables (one array item per
+/// line at the top level), null becomes `nil`
+pub fn lua(value: &serde_json::Value) -> String {
+    match value {
+        serde_json::Value::Array(items) if !items.is_empty() => {
+            let items: Vec<String> = items
+                .iter()
+                .map(|item| format!("  {},\n", lua_inline(item)))
+                .collect();
+            format!("{{\n{}}}", items.concat())
+        }
+        value => lua_inline(value),
+    }
+}
+
+fn lua_inline(value: &serde_json::Value) -> String {
+    use serde_json::Value;
+    match value {
+        Value::Null => "nil".to_string(),
+        Value::Bool(b) => b.to_string(),
+        Value::Number(n) => n.to_string(),
+        Value::String(s) => lua_string(s),
+        Value::Array(items) if items.is_empty() => "{}".to_string(),
+        Value::Array(items) => {
+            let items: Vec<String> = items.iter().map(lua_inline).collect();
+            format!("{{ {} }}", items.join(", "))
+        }
+        Value::Object(fields) => {
+            let fields: Vec<String> = fields
+                .iter()
+                .filter(|(_, value)| !value.is_null())
+                .map(|(key, value)| {
+                    let is_name = key.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+                        && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+                        && !LUA_KEYWORDS.contains(&key.as_str());
+                    if is_name {
+                        format!("{} = {}", key, lua_inline(value))
+                    } else {
+                        format!("[{}] = {}", lua_string(key), lua_inline(value))
+                    }
+                })
+                .collect();
+            format!("{{ {} }}", fields.join(", "))
+        }
+    }
+}
+
+const LUA_KEYWORDS: &[&str] = &[
+    "and", "break", "do", "else", "elseif", "end", "false", "for", "function", "goto", "if", "in",
+    "local", "nil", "not", "or", "repeat", "return", "then", "true", "until", "while",
+];
+
+fn lua_string(text: &str) -> String {
+    let mut quoted = String::from("\"");
+    for c in text.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            c if c.is_ascii_control() => quoted.push_str(&format!("\\{:03}", c as u8)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// Clears the terminal and moves the cursor home, before each `--watch`/`--interactive` redraw
+pub const CLEAR_SCREEN: &str = "\x1b[2J\x1b[H";
+
+/// Symbols used in messages and the listing
+pub struct Glyphs {
+    pub check: &'static str,
+    pub warn: &'static str,
+    pub arrow: &'static str,
+    pub back_arrow: &'static str,
+    pub sep: &'static str,
+    /// Between a message and a hint, e.g. `... — press Ctrl-C to stop`
+    pub dash: &'static str,
+    pub expanded: &'static str,
+    pub collapsed: &'static str,
+    /// Heatmap cells from empty to full
+    #[cfg_attr(not(feature = "history"), allow(dead_code))]
+    pub shades: [&'static str; 5],
+    /// Menu bar dots for a range with and without listeners, `--watch` changes
+    pub up: &'static str,
+    pub down: &'static str,
+}
+
+pub const UNICODE: Glyphs = Glyphs {
+    check: "✓",
+    warn: "⚠",
+    arrow: "→",
+    back_arrow: "←",
+    sep: "·",
+    dash: "—",
+    expanded: "▾",
+    collapsed: "▸",
+    shades: [" ", "░", "▒", "▓", "█"],
+    up: "●",
+    down: "○",
+};
+
+/// For consoles whose fonts lack the Unicode symbols (legacy Windows conhost), logs
+/// and CI consoles that garble them
+pub const ASCII: Glyphs = Glyphs {
+    check: "OK",
+    warn: "!",
+    arrow: "->",
+    back_arrow: "<-",
+    sep: "|",
+    dash: "-",
+    expanded: "-",
+    collapsed: "+",
+    shades: [" ", ".", ":", "*", "#"],
+    up: "+",
+    down: "-",
+};
+
+/// Colorful status symbols; the markers and heatmap cells stay one column wide
+pub const EMOJI: Glyphs = Glyphs {
+    check: "✅",
+    warn: "⚠️",
+    arrow: "➡️",
+    back_arrow: "⬅️",
+    sep: "·",
+    dash: "—",
+    expanded: "▾",
+    collapsed: "▸",
+    shades: [" ", "░", "▒", "▓", "█"],
+    up: "🟢",
+    down: "🔴",
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GlyphSet {
+    Unicode,
+    Ascii,
+    Emoji,
+}
+
+impl GlyphSet {
+    pub fn glyphs(self) -> &'static Glyphs {
+        match self {
+            GlyphSet::Unicode => &UNICODE,
+            GlyphSet::Ascii => &ASCII,
+            GlyphSet::Emoji => &EMOJI,
+        }
+    }
+
+    /// The set picked with `--glyphs`/`--ascii`, else the `glyphs` of the config
+    pub fn chosen(config: &crate::Config) -> Option<Self> {
+        GLYPH_FLAG.get().copied().flatten().or(config.glyphs)
+    }
+}
+
+static GLYPH_FLAG: OnceLock<Option<GlyphSet>> = OnceLock::new();
+
+static GLYPHS: OnceLock<&'static Glyphs> = OnceLock::new();
+
+/// Unicode, except on a Windows console outside Windows Terminal (which sets `WT_SESSION`)
+fn default_glyphs() -> &'static Glyphs {
+    let legacy_console = cfg!(windows) && std::env::var_os("WT_SESSION").is_none();
+    if legacy_console {
+        &ASCII
+    } else {
+        &UNICODE
+    }
+}
+
+/// Remember the set given on the command line; it wins over the config
+pub fn init_glyphs(set: Option<GlyphSet>) {
+    let _ = GLYPH_FLAG.set(set);
+    if let Some(set) = set {
+        let _ = GLYPHS.set(set.glyphs());
+    }
+}
+
+/// Take `glyphs` from an already loaded config instead of reading it again
+pub fn use_config(config: &crate::Config) {
+    let _ = GLYPHS.set(GlyphSet::chosen(config).map_or_else(default_glyphs, GlyphSet::glyphs));
+}
+
+pub fn glyphs() -> &'static Glyphs {
+    GLYPHS.get_or_init(|| {
+        // A broken config is reported by the commands that need it
+        crate::Config::load()
+            .ok()
+            .and_then(|c| c.glyphs)
+            .map_or_else(default_glyphs, GlyphSet::glyphs)
+    })
+}
+
+/// Decide once whether colors are emitted
+///
+/// `CLICOLOR_FORCE` forces them on; `NO_COLOR`, `TERM=dumb` or a non-terminal stdout
+/// turn them off. With `--a11y` they are always off.
+pub fn init_colors(a11y: bool) {
+    let enabled = colors_enabled(&Environment::current(), a11y);
+    colored::control::set_override(enabled);
+
+    // Windows consoles interpret ANSI sequences only once virtual terminal processing is on
+    #[cfg(windows)]
+    if enabled && colored::control::set_virtual_terminal(true).is_err() {
+        colored::control::set_override(false);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn env(terminal: bool, vars: &[(&str, &str)]) -> Environment<impl Fn(&str) -> Option<String>> {
+        let vars: Vec<(String, String)> = vars
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        Environment {
+            terminal,
+            var: move |name: &str| vars.iter().find(|(k, _)| k == name).map(|(_, v)| v.clone()),
+        }
+    }
+
+    #[test]
+    fn auto_is_the_table_on_a_terminal() {
+        let tty = env(true, &[]);
+        assert_eq!(
+            OutputFormat::Auto.resolve_in(&tty, false),
+            OutputFormat::Table
+        );
+    }
+
+    #[test]
+    fn auto_is_plain_when_piped_or_in_ci() {
+        let pipe = env(false, &[]);
+        assert_eq!(
+            OutputFormat::Auto.resolve_in(&pipe, false),
+            OutputFormat::Plain
+        );
+        let ci = env(true, &[("CI", "true")]);
+        assert_eq!(
+            OutputFormat::Auto.resolve_in(&ci, false),
+            OutputFormat::Plain
+        );
+    }
+
+    #[test]
+    fn machine_output_is_json_in_ci_only() {
+        let ci = env(false, &[("CI", "true")]);
+        assert_eq!(OutputFormat::Auto.resolve_in(&ci, true), OutputFormat::Json);
+        let tty = env(true, &[]);
+        assert_eq!(
+            OutputFormat::Auto.resolve_in(&tty, true),
+            OutputFormat::Plain
+        );
+    }
+
+    #[test]
+    fn ci_is_off_when_empty_zero_or_false() {
+        for value in ["", "0", "false"] {
+            assert!(!env(false, &[("CI", value)]).is_ci(), "CI={:?}", value);
+        }
+        assert!(env(false, &[("CI", "1")]).is_ci());
+    }
+
+    #[test]
+    fn explicit_formats_are_kept() {
+        let ci = env(false, &[("CI", "true")]);
+        for format in [OutputFormat::Table, OutputFormat::Plain, OutputFormat::Json] {
+            assert_eq!(format.resolve_in(&ci, true), format);
+        }
+    }
+
+    #[test]
+    fn lua_literals() {
+        let value = serde_json::json!([
+            { "port": 3000, "pids": ["1", "2"], "label": null, "end": true },
+            "a \"b\"\\\n\u{7}",
+        ]);
+        assert_eq!(
+            lua(&value),
+            "{\n  { [\"end\"] = true, pids = { \"1\", \"2\" }, port = 3000 },\n  \"a \\\"b\\\"\\\\\\n\\007\",\n}"
+        );
+        assert_eq!(lua(&serde_json::json!([])), "{}");
+    }
+
+    #[test]
+    fn colors_only_on_a_terminal() {
+        assert!(colors_enabled(&env(true, &[]), false));
+        assert!(!colors_enabled(&env(false, &[]), false));
+        assert!(colors_enabled(
+            &env(false, &[("CLICOLOR_FORCE", "1")]),
+            false
+        ));
+        assert!(!colors_enabled(&env(true, &[("NO_COLOR", "1")]), false));
+        assert!(!colors_enabled(&env(true, &[("TERM", "dumb")]), false));
+        assert!(!colors_enabled(
+            &env(true, &[("CLICOLOR_FORCE", "1")]),
+            true
+        ));
+    }
+}
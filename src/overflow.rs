@@ -0,0 +1,96 @@
+//! Accept-queue overflow monitoring for the daemon (Linux)
+//!
+//! The kernel only counts overflows globally (`ListenOverflows`/`ListenDrops` in
+//! `/proc/net/netstat`). When the counters grow between two snapshots, the drops are
+//! attributed to the listeners whose accept queue is full at that moment.
+
+use crate::explain::ListenQueue;
+use std::collections::HashSet;
+
+/// `TcpExt` counters from `/proc/net/netstat`: (ListenOverflows, ListenDrops)
+fn counters() -> Option<(u64, u64)> {
+    let content = std::fs::read_to_string(crate::fixtures::proc_path("net/netstat")).ok()?;
+    let mut lines = content.lines().filter(|l| l.starts_with("TcpExt:"));
+    let names: Vec<&str> = lines.next()?.split_whitespace().collect();
+    let values: Vec<&str> = lines.next()?.split_whitespace().collect();
+    let value = |name: &str| -> Option<u64> {
+        let index = names.iter().position(|n| *n == name)?;
+        values.get(index)?.parse().ok()
+    };
+    Some((value("ListenOverflows")?, value("ListenDrops")?))
+}
+
+/// Overflow state across daemon snapshots
+#[derive(Default)]
+pub struct Monitor {
+    last: Option<(u64, u64)>,
+    /// Listeners reported as overflowing and not yet recovered
+    overflowing: HashSet<String>,
+}
+
+impl Monitor {
+    /// Compare with the previous snapshot and return messages for new and recovered overflows
+    pub fn check(&mut self, port_infos: &[crate::PortInfo]) -> Vec<String> {
+        let Some(current) = counters() else {
+            return Vec::new();
+        };
+        let previous = self.last.replace(current);
+        let dropped = previous.map_or(0, |(overflows, drops)| {
+            (current.0 - overflows.min(current.0)).max(current.1 - drops.min(current.1))
+        });
+
+        let full: Vec<ListenQueue> = crate::explain::listen_queues()
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|q| q.is_full())
+            .collect();
+        let mut messages = Vec::new();
+        let glyphs = crate::output::glyphs();
+
+        if dropped > 0 {
+            for queue in &full {
+                if !self.overflowing.insert(queue.local.clone()) {
+                    continue;
+                }
+                messages.push(format!(
+                    "{} accept queue of {}{} is overflowing ({} connections dropped, backlog {})",
+                    glyphs.warn,
+                    queue.local,
+                    owner(queue.port, port_infos),
+                    dropped,
+                    queue.backlog
+                ));
+            }
+            if full.is_empty() {
+                messages.push(format!(
+                    "{} {} connections dropped by a full accept queue (listener already recovered)",
+                    glyphs.warn, dropped
+                ));
+            }
+        }
+
+        let recovered: Vec<String> = self
+            .overflowing
+            .iter()
+            .filter(|local| !full.iter().any(|q| &&q.local == local))
+            .cloned()
+            .collect();
+        for local in recovered {
+            self.overflowing.remove(&local);
+            messages.push(format!(
+                "{} accept queue of {} recovered",
+                glyphs.check, local
+            ));
+        }
+        messages
+    }
+}
+
+/// ` (process [pid])` of the listener on `port`, when known
+fn owner(port: u16, port_infos: &[crate::PortInfo]) -> String {
+    port_infos
+        .iter()
+        .find(|i| i.port == port)
+        .map(|i| format!(" ({} {})", i.process, crate::format_pid(&i.pid)))
+        .unwrap_or_default()
+}
@@ -0,0 +1,72 @@
+//! Where the config, state and caches live
+//!
+//! By default the config is `~/.config/lsof-work-ports/config.toml`, state such as the
+//! history goes to `$XDG_STATE_HOME/lsof-work-ports` and caches to
+//! `$XDG_CACHE_HOME/lsof-work-ports`. `--root <dir>` (or `LWP_ROOT`) moves all of them
+//! under one directory, for tests and throwaway containers.
+
+use anyhow::{Context, Result};
+use std::path::PathBuf;
+use std::sync::OnceLock;
+
+const APP_DIR: &str = "lsof-work-ports";
+
+static ROOT: OnceLock<Option<PathBuf>> = OnceLock::new();
+
+/// Use `root` for everything; `None` falls back to `LWP_ROOT`, then to the home directory
+pub fn init(root: Option<PathBuf>) {
+    let root = root.or_else(|| {
+        std::env::var_os("LWP_ROOT")
+            .filter(|r| !r.is_empty())
+            .map(PathBuf::from)
+    });
+    let _ = ROOT.set(root);
+}
+
+/// The `--root`/`LWP_ROOT` directory, if one is in use
+pub fn root() -> Option<&'static PathBuf> {
+    ROOT.get().and_then(Option::as_ref)
+}
+
+fn home() -> Result<PathBuf> {
+    let home = std::env::var("HOME").context("HOME environment variable not set")?;
+    Ok(PathBuf::from(home))
+}
+
+fn xdg_dir(env_var: &str, home_fallback: &[&str]) -> Result<PathBuf> {
+    let base = match std::env::var(env_var) {
+        Ok(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => home_fallback
+            .iter()
+            .fold(home()?, |path, part| path.join(part)),
+    };
+    Ok(base.join(APP_DIR))
+}
+
+/// Directory of the config file (`<root>/config`)
+pub fn config_dir() -> Result<PathBuf> {
+    match root() {
+        Some(root) => Ok(root.join("config")),
+        None => Ok(home()?.join(".config").join(APP_DIR)),
+    }
+}
+
+pub fn config_file() -> Result<PathBuf> {
+    Ok(config_dir()?.join("config.toml"))
+}
+
+/// Directory for persistent state such as history (`<root>/state`)
+pub fn state_dir() -> Result<PathBuf> {
+    match root() {
+        Some(root) => Ok(root.join("state")),
+        None => xdg_dir("XDG_STATE_HOME", &[".local", "state"]),
+    }
+}
+
+/// Directory for disposable caches (`<root>/cache`)
+pub fn cache_dir() -> Result<PathBuf> {
+    match root() {
+        Some(root) => Ok(root.join("cache")),
+        None => xdg_dir("XDG_CACHE_HOME", &[".cache"]),
+    }
+}
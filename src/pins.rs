@@ -0,0 +1,54 @@
+//! `pin`/`unpin`: ports listed first in the table, kept in `[display] pinned`
+
+use anyhow::Result;
+
+pub fn pin(ports: &[u16]) -> Result<()> {
+    if ports.is_empty() {
+        let config = crate::Config::load()?;
+        if config.display.pinned.is_empty() {
+            println!("No pinned ports; pin one with `lsof-work-ports pin <port>`");
+        }
+        for port in &config.display.pinned {
+            println!("{}", port);
+        }
+        return Ok(());
+    }
+
+    let _lock = crate::Config::lock()?;
+    let mut config = crate::Config::load()?;
+    for port in ports {
+        if !config.display.pinned.contains(port) {
+            config.display.pinned.push(*port);
+        }
+    }
+    config.save()?;
+    println!(
+        "{} Pinned: {}",
+        crate::output::glyphs().check,
+        join(&config.display.pinned)
+    );
+    Ok(())
+}
+
+pub fn unpin(ports: &[u16]) -> Result<()> {
+    let _lock = crate::Config::lock()?;
+    let mut config = crate::Config::load()?;
+    let before = config.display.pinned.len();
+    config.display.pinned.retain(|p| !ports.contains(p));
+    anyhow::ensure!(
+        config.display.pinned.len() < before,
+        "Not pinned: {}",
+        join(ports)
+    );
+    config.save()?;
+    println!("{} Unpinned {}", crate::output::glyphs().check, join(ports));
+    Ok(())
+}
+
+fn join(ports: &[u16]) -> String {
+    ports
+        .iter()
+        .map(u16::to_string)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
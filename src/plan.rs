@@ -0,0 +1,99 @@
+//! `plan`: suggest a contiguous free port range for a new project
+
+use crate::theme::Themed;
+use anyhow::{Context, Result};
+use colored::Colorize;
+
+/// Ranges are searched for within the usual dev server area
+const SEARCH_START: u16 = 3000;
+const SEARCH_END: u16 = 9999;
+
+/// How many alternatives to print
+const SUGGESTIONS: usize = 3;
+
+/// Why a port can't be part of a new range, if it can't
+fn conflict(port: u16, config: &crate::Config, used: &[u16]) -> Result<Option<String>> {
+    for entry in &config.ports {
+        if entry.expand()?.contains(&port) {
+            let name = entry.name.as_deref().unwrap_or(&entry.ports);
+            return Ok(Some(format!("configured for {}", name)));
+        }
+    }
+    if let Some(service) = crate::well_known::service(port) {
+        return Ok(Some(format!("well-known port of {}", service)));
+    }
+    if used.contains(&port) {
+        return Ok(Some("in use now or recently".to_string()));
+    }
+    Ok(None)
+}
+
+/// Print free ranges of `size` ports, optionally saving the first under `name`
+pub fn run(size: u16, name: Option<&str>, save: bool) -> Result<()> {
+    anyhow::ensure!(size > 0, "--size must be at least 1");
+    anyhow::ensure!(!save || name.is_some(), "--save requires --name");
+
+    let config = crate::Config::load()?;
+    // Ports bound right now, and ports recorded in the startup history
+    let mut used: Vec<u16> = crate::get_port_info()?.iter().map(|i| i.port).collect();
+    let history: Vec<crate::history::StartupRecord> =
+        crate::history::read(crate::history::STARTUP)?;
+    used.extend(history.iter().map(|r| r.port));
+
+    // Align starts to round numbers so ranges are easy to remember
+    let align = if size <= 10 { 10 } else { 100 };
+    let mut suggestions = Vec::new();
+    let mut start = SEARCH_START;
+    while start.saturating_add(size - 1) <= SEARCH_END && suggestions.len() < SUGGESTIONS {
+        let end = start + size - 1;
+        let mut blocked = None;
+        for port in start..=end {
+            if let Some(reason) = conflict(port, &config, &used)? {
+                blocked = Some((port, reason));
+                break;
+            }
+        }
+        match blocked {
+            None => suggestions.push((start, end)),
+            Some((port, reason)) if suggestions.is_empty() => {
+                println!(
+                    "{}",
+                    format!("  skip {}-{}: {} {}", start, end, port, reason).muted()
+                );
+            }
+            Some(_) => {}
+        }
+        start += align;
+    }
+
+    let (first_start, first_end) = *suggestions.first().with_context(|| {
+        format!(
+            "No free range of {} ports between {} and {}",
+            size, SEARCH_START, SEARCH_END
+        )
+    })?;
+    for (start, end) in &suggestions {
+        println!("{}", format!("{}-{}", start, end).accent().bold());
+    }
+
+    if save {
+        let name = name.unwrap_or_default();
+        let _lock = crate::Config::lock()?;
+        let mut config = crate::Config::load()?;
+        config.ports.push(crate::PortEntry {
+            ports: format!("{}-{}", first_start, first_end),
+            name: Some(name.to_string()),
+            http: None,
+            status: None,
+        });
+        config.save()?;
+        println!(
+            "{} Added {} as {} to {}",
+            crate::output::glyphs().check,
+            format!("{}-{}", first_start, first_end).accent().bold(),
+            name.success(),
+            crate::paths::config_file()?.display()
+        );
+    }
+    Ok(())
+}
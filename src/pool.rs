@@ -0,0 +1,59 @@
+//! Bounded concurrency for per-PID lookups and probes (`--jobs`)
+//!
+//! Each lookup mostly waits on a child process or the network, so running a few at
+//! once cuts the listing time on machines with hundreds of sockets, while the bound
+//! keeps us from spawning hundreds of `lsof`s at the same time.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::OnceLock;
+
+/// Upper bound of the default, however many cores there are
+const MAX_DEFAULT_JOBS: usize = 8;
+
+static JOBS: OnceLock<usize> = OnceLock::new();
+
+/// Set the worker count; `None` uses the number of cores (at most 8)
+pub fn init(jobs: Option<usize>) {
+    let default = || {
+        std::thread::available_parallelism()
+            .map_or(1, usize::from)
+            .min(MAX_DEFAULT_JOBS)
+    };
+    let _ = JOBS.set(jobs.unwrap_or_else(default).max(1));
+}
+
+pub fn jobs() -> usize {
+    JOBS.get().copied().unwrap_or(1)
+}
+
+/// `f` applied to every item on at most `jobs()` threads, results in input order
+pub fn map<T: Sync, R: Send>(items: &[T], f: impl Fn(&T) -> R + Sync) -> Vec<R> {
+    let workers = jobs().min(items.len());
+    if workers <= 1 {
+        return items.iter().map(f).collect();
+    }
+
+    let next = AtomicUsize::new(0);
+    let mut results: Vec<(usize, R)> = std::thread::scope(|scope| {
+        let handles: Vec<_> = (0..workers)
+            .map(|_| {
+                scope.spawn(|| {
+                    let mut done = Vec::new();
+                    loop {
+                        let i = next.fetch_add(1, Ordering::Relaxed);
+                        let Some(item) = items.get(i) else {
+                            return done;
+                        };
+                        done.push((i, f(item)));
+                    }
+                })
+            })
+            .collect();
+        handles
+            .into_iter()
+            .flat_map(|h| h.join().unwrap_or_else(|e| std::panic::resume_unwind(e)))
+            .collect()
+    });
+    results.sort_unstable_by_key(|(i, _)| *i);
+    results.into_iter().map(|(_, r)| r).collect()
+}
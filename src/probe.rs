@@ -0,0 +1,214 @@
+//! TCP connect latency probe (`--latency`) and hang detection (`--detect-hangs`)
+//!
+//! A listener that is bound but never completes the handshake (a hung event loop,
+//! a full accept queue, a process stopped with SIGSTOP) looks healthy in lsof. Timing
+//! a few connects tells it apart.
+
+use crate::theme::Themed;
+use colored::{ColoredString, Colorize};
+use std::io::{Read, Write};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, TcpStream};
+use std::time::{Duration, Instant};
+
+/// Connects per listener; the median is shown
+const SAMPLES: usize = 3;
+
+/// A connect that takes longer than this counts as hung
+pub const TIMEOUT: Duration = Duration::from_millis(500);
+
+/// Latencies at or above this are highlighted as slow
+const SLOW: Duration = Duration::from_millis(100);
+
+#[derive(Debug, Clone, Copy)]
+pub enum Latency {
+    Connected(Duration),
+    /// No connect completed within `TIMEOUT`
+    Hung,
+}
+
+impl Latency {
+    pub fn text(&self) -> String {
+        match self {
+            Latency::Connected(d) if *d < Duration::from_millis(1) => "<1ms".to_string(),
+            Latency::Connected(d) => format!("{}ms", d.as_millis()),
+            Latency::Hung => "hung".to_string(),
+        }
+    }
+
+    /// Right-aligned in 6 columns, colored by speed
+    pub fn display(&self) -> ColoredString {
+        let text = format!("{:>6}", self.text());
+        match self {
+            Latency::Connected(d) if *d >= SLOW => text.warning(),
+            Latency::Connected(_) => text.success(),
+            Latency::Hung => text.error().bold(),
+        }
+    }
+}
+
+/// Address to connect to for a listener bound to `address` (`*`, `[::]`, `127.0.0.1`...)
+pub fn target(address: &str, family: &str, port: u16) -> SocketAddr {
+    let address = address.trim_start_matches('[').trim_end_matches(']');
+    let ip = match address.parse::<IpAddr>() {
+        Ok(ip) if !ip.is_unspecified() => ip,
+        Ok(IpAddr::V6(_)) => IpAddr::V6(Ipv6Addr::LOCALHOST),
+        _ if family == "IPv6" => IpAddr::V6(Ipv6Addr::LOCALHOST),
+        _ => IpAddr::V4(Ipv4Addr::LOCALHOST),
+    };
+    SocketAddr::new(ip, port)
+}
+
+/// Median connect time over `SAMPLES` attempts, or `Hung` if most of them time out
+pub fn measure(target: SocketAddr) -> Latency {
+    let mut times: Vec<Duration> = (0..SAMPLES)
+        .filter_map(|_| {
+            let started = Instant::now();
+            TcpStream::connect_timeout(&target, TIMEOUT)
+                .ok()
+                .map(|_| started.elapsed())
+        })
+        .collect();
+    if times.len() * 2 <= SAMPLES {
+        return Latency::Hung;
+    }
+    times.sort_unstable();
+    Latency::Connected(times[times.len() / 2])
+}
+
+/// Probe every listener concurrently and store the result in `latency`
+pub fn probe_all<'a>(infos: impl Iterator<Item = &'a mut crate::GroupedPortInfo>) {
+    // UDP has no handshake to time
+    let mut infos: Vec<&mut crate::GroupedPortInfo> = infos
+        .filter(|info| info.protocol == crate::collector::Protocol::Tcp)
+        .collect();
+    let targets: Vec<SocketAddr> = infos
+        .iter()
+        .map(|info| target(&info.address, &info.family, info.port))
+        .collect();
+    let latencies = crate::pool::map(&targets, |target| measure(*target));
+    for (info, latency) in infos.iter_mut().zip(latencies) {
+        info.latency = Some(latency);
+    }
+}
+
+/// Connect and response timeout for hang detection
+const HANG_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Why a listener looks hung, or `None` if it completes the handshake (and answers
+/// `GET http_path` when given)
+pub fn detect_hang(target: SocketAddr, http_path: Option<&str>) -> Option<String> {
+    let mut stream = match TcpStream::connect_timeout(&target, HANG_TIMEOUT) {
+        Ok(stream) => stream,
+        Err(e) if e.kind() == std::io::ErrorKind::TimedOut => {
+            return Some("bound, but never completes the TCP handshake".to_string());
+        }
+        // Refused: the listener went away, which is not a hang
+        Err(_) => return None,
+    };
+    let path = http_path?;
+
+    let request = format!(
+        "GET {} HTTP/1.0\r\nHost: {}\r\nConnection: close\r\n\r\n",
+        path, target
+    );
+    let mut byte = [0u8; 1];
+    let answered = stream.set_read_timeout(Some(HANG_TIMEOUT)).is_ok()
+        && stream.write_all(request.as_bytes()).is_ok()
+        && matches!(stream.read(&mut byte), Ok(n) if n > 0);
+    (!answered).then(|| {
+        format!(
+            "accepts connections, but GET {} gets no response within {}s",
+            path,
+            HANG_TIMEOUT.as_secs()
+        )
+    })
+}
+
+/// Monitored listeners (`[[ports]]`) that look hung, as (port, label, reason)
+pub fn hung_listeners(
+    config: &crate::Config,
+    port_infos: &[crate::PortInfo],
+) -> anyhow::Result<Vec<(u16, String, String)>> {
+    let mut checks = Vec::new();
+    for entry in config.port_entries() {
+        let label = entry.name.clone().unwrap_or_else(|| entry.ports.clone());
+        for port in entry.expand()? {
+            if let Some(info) = port_infos
+                .iter()
+                .find(|i| i.port == port && i.protocol == crate::collector::Protocol::Tcp)
+            {
+                let target = target(&info.address, &info.family, port);
+                checks.push((port, label.clone(), target, entry.http.as_deref()));
+            }
+        }
+    }
+
+    Ok(crate::pool::map(&checks, |(port, label, target, http)| {
+        detect_hang(*target, *http).map(|reason| (*port, label.clone(), reason))
+    })
+    .into_iter()
+    .flatten()
+    .collect())
+}
+
+/// `--detect-hangs`: check every monitored listener once and fail if any is hung
+pub fn run_detect_hangs() -> anyhow::Result<()> {
+    let config = crate::Config::load()?;
+    anyhow::ensure!(
+        !config.port_entries().is_empty(),
+        "No monitored ports; add [[ports]] entries to the config"
+    );
+    let hung = hung_listeners(&config, &crate::get_port_info()?)?;
+    if hung.is_empty() {
+        println!(
+            "{} No hung listeners on the monitored ports",
+            crate::output::glyphs().check
+        );
+        return Ok(());
+    }
+    for (port, label, reason) in &hung {
+        println!(
+            "{} {} {}  {}",
+            crate::output::glyphs().warn.error(),
+            format!(":{}", port).accent().bold(),
+            label.bold(),
+            reason.warning()
+        );
+    }
+    anyhow::bail!("{} hung listener(s)", hung.len())
+}
+
+/// Hung listeners across daemon snapshots, to notify only on transitions
+#[cfg(feature = "daemon")]
+#[derive(Default)]
+pub struct HangMonitor {
+    hung: std::collections::HashSet<u16>,
+}
+
+#[cfg(feature = "daemon")]
+impl HangMonitor {
+    pub fn check(&mut self, config: &crate::Config, port_infos: &[crate::PortInfo]) -> Vec<String> {
+        let hung = match hung_listeners(config, port_infos) {
+            Ok(hung) => hung,
+            Err(e) => return vec![format!("{:#}", e)],
+        };
+        let glyphs = crate::output::glyphs();
+        let mut messages = Vec::new();
+        for (port, label, reason) in &hung {
+            if self.hung.insert(*port) {
+                messages.push(format!(
+                    "{} {} :{} hung: {}",
+                    glyphs.warn, label, port, reason
+                ));
+            }
+        }
+        self.hung.retain(|port| {
+            let still = hung.iter().any(|(p, _, _)| p == port);
+            if !still {
+                messages.push(format!("{} :{} responds again", glyphs.check, port));
+            }
+            still
+        });
+        messages
+    }
+}
@@ -0,0 +1,155 @@
+//! `up`/`down`: run the services of a Procfile under supervision, each with a
+//! free port from the monitored ranges exported as `PORT`
+
+use crate::theme::Themed;
+use anyhow::{Context, Result};
+use colored::Colorize;
+use std::path::Path;
+
+/// Ports handed out when no `[[ports]]` ranges are configured (foreman's default base)
+const FALLBACK_PORTS: std::ops::RangeInclusive<u16> = 5000..=5999;
+
+/// Parse `name: command` lines, skipping blanks and comments
+fn parse(content: &str) -> Result<Vec<(String, String)>> {
+    let mut services = Vec::new();
+    for (number, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let (name, command) = line
+            .split_once(':')
+            .with_context(|| format!("Procfile line {}: expected `name: command`", number + 1))?;
+        let name = name.trim();
+        anyhow::ensure!(
+            !name.is_empty()
+                && name
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_'),
+            "Procfile line {}: invalid service name '{}'",
+            number + 1,
+            name
+        );
+        services.push((name.to_string(), command.trim().to_string()));
+    }
+    Ok(services)
+}
+
+/// Services from the Procfile, or from `[services]` in the config when it doesn't exist
+fn load_services(procfile: &Path, config: &crate::Config) -> Result<Vec<(String, String)>> {
+    if procfile.exists() {
+        let content = std::fs::read_to_string(procfile)
+            .with_context(|| format!("Failed to read {}", procfile.display()))?;
+        return parse(&content);
+    }
+    anyhow::ensure!(
+        !config.services.is_empty(),
+        "No {} found and no [services] in the config",
+        procfile.display()
+    );
+    Ok(config
+        .services
+        .iter()
+        .map(|(name, command)| (name.clone(), command.clone()))
+        .collect())
+}
+
+/// Supervisor entries are named `<project>-<hash>.<service>`: the project is the
+/// current directory, and the hash of its full path tells apart projects of the same
+/// name (`~/a/web` and `~/b/web`)
+fn project_prefix() -> Result<String> {
+    let cwd = std::env::current_dir().context("Failed to read current directory")?;
+    let cwd = cwd.canonicalize().unwrap_or(cwd);
+    let project = cwd
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "root".to_string());
+    let hash = crate::anonymize::stable_hash(&cwd.to_string_lossy());
+    Ok(format!("{}-{:08x}.", project, hash & 0xffff_ffff))
+}
+
+/// Start the services (all, or only those named)
+pub fn up(procfile: &Path, only: &[String]) -> Result<()> {
+    let config = crate::Config::load()?;
+    let services = load_services(procfile, &config)?;
+    for name in only {
+        anyhow::ensure!(
+            services.iter().any(|(n, _)| n == name),
+            "Unknown service '{}'",
+            name
+        );
+    }
+
+    let mut candidates = config.monitored_ports()?;
+    if candidates.is_empty() {
+        candidates = FALLBACK_PORTS.collect();
+    }
+    // Ports that are bound, leased by `alloc` or promised to a running service are taken
+    let mut taken: Vec<u16> = crate::get_port_info()?.iter().map(|i| i.port).collect();
+    taken.extend(crate::leases::leased_ports()?);
+    let entries = crate::supervisor::load()?;
+    taken.extend(
+        entries
+            .iter()
+            .filter(|e| e.is_running())
+            .filter_map(|e| e.port),
+    );
+
+    let prefix = project_prefix()?;
+    for (service, command) in &services {
+        if !only.is_empty() && !only.contains(service) {
+            continue;
+        }
+        let name = format!("{}{}", prefix, service);
+        if entries.iter().any(|e| e.name == name && e.is_running()) {
+            println!("{} is already running", service.success());
+            continue;
+        }
+
+        let port = candidates
+            .iter()
+            .copied()
+            .find(|p| !taken.contains(p))
+            .context("No free port left in the monitored ranges")?;
+        taken.push(port);
+
+        let argv = vec!["sh".to_string(), "-c".to_string(), command.clone()];
+        let mut entry = crate::supervisor::Entry::new(name, "service", Some(port), argv);
+        entry.env.push(("PORT".to_string(), port.to_string()));
+        crate::supervisor::start(entry)?;
+
+        println!(
+            "{} {} {} {}",
+            crate::output::glyphs().check,
+            service.success(),
+            format!(":{}", port).accent().bold(),
+            command.muted()
+        );
+    }
+    Ok(())
+}
+
+/// Stop this project's services (all, or only those named)
+pub fn down(only: &[String]) -> Result<()> {
+    let prefix = project_prefix()?;
+    let mut stopped = 0;
+    for entry in crate::supervisor::load()? {
+        let Some(service) = entry.name.strip_prefix(&prefix) else {
+            continue;
+        };
+        if entry.kind != "service" || (!only.is_empty() && !only.iter().any(|s| s == service)) {
+            continue;
+        }
+        crate::supervisor::stop(&entry.name)?;
+        println!(
+            "{} Stopped {}",
+            crate::output::glyphs().check,
+            service.success()
+        );
+        stopped += 1;
+    }
+    if stopped == 0 {
+        println!("{}", "No services running for this project".warning());
+    }
+    Ok(())
+}
@@ -0,0 +1,195 @@
+//! `proc` backend: sockets straight from `/proc`, for containers without lsof
+//!
+//! `/proc/net/{tcp,udp}` and their `6` variants list every socket with its inode; the
+//! owner is the process with a `/proc/<pid>/fd/*` link to `socket:[<inode>]`. Like lsof
+//! without root, sockets of other users' processes have no visible owner and are left out.
+
+use crate::collector::{Interner, Listener, Parsed, Protocol, LISTEN, UNCONN};
+use anyhow::{Context, Result};
+use std::collections::HashMap;
+use std::net::{Ipv4Addr, Ipv6Addr};
+
+/// TCP state of an `st` value, as lsof names it; UDP sockets use TCP_CLOSE (`07`)
+/// when unconnected and TCP_ESTABLISHED (`01`) with a peer
+fn state(protocol: Protocol, st: &str) -> Option<&'static str> {
+    Some(match (protocol, st) {
+        (_, "01") => "ESTABLISHED",
+        (Protocol::Udp, "07") => UNCONN,
+        (Protocol::Udp, _) => return None,
+        (_, "02") => "SYN_SENT",
+        (_, "03") => "SYN_RECV",
+        (_, "04") => "FIN_WAIT1",
+        (_, "05") => "FIN_WAIT2",
+        (_, "06") => "TIME_WAIT",
+        (_, "07") => "CLOSED",
+        (_, "08") => "CLOSE_WAIT",
+        (_, "09") => "LAST_ACK",
+        (_, "0A") => LISTEN,
+        (_, "0B") => "CLOSING",
+        _ => return None,
+    })
+}
+
+/// The TCP and UDP socket tables, one after the other with their header lines
+pub fn tables() -> Result<String> {
+    read_tables(&["net/tcp", "net/tcp6", "net/udp", "net/udp6"])
+}
+
+/// The named tables (`net/udp`, ...) one after the other
+pub fn read_tables(names: &[&str]) -> Result<String> {
+    let mut tables = String::new();
+    for name in names {
+        let path = crate::fixtures::proc_path(name);
+        match std::fs::read_to_string(&path) {
+            Ok(table) => tables.push_str(&table),
+            // Kernels without IPv6 have no tcp6/udp6
+            Err(_) if name.ends_with('6') => {}
+            Err(e) => return Err(e).with_context(|| format!("Failed to read {}", path.display())),
+        }
+    }
+    Ok(tables)
+}
+
+/// `0100007F:0BB8` → ("127.0.0.1", 3000); the kernel prints each 32-bit word of the
+/// address in host byte order
+fn decode_address(field: &str) -> Option<(String, String, u16)> {
+    let (address, port) = field.split_once(':')?;
+    let port = u16::from_str_radix(port, 16).ok()?;
+    let words = (0..address.len() / 8)
+        .map(|i| u32::from_str_radix(address.get(i * 8..i * 8 + 8)?, 16).ok())
+        .collect::<Option<Vec<u32>>>()?;
+    let bytes: Vec<u8> = words.iter().flat_map(|w| w.to_ne_bytes()).collect();
+    let (family, address) = match address.len() {
+        8 => {
+            let ip = Ipv4Addr::from(<[u8; 4]>::try_from(bytes).ok()?);
+            let address = if ip.is_unspecified() {
+                "*".to_string()
+            } else {
+                ip.to_string()
+            };
+            ("IPv4", address)
+        }
+        32 => {
+            let ip = Ipv6Addr::from(<[u8; 16]>::try_from(bytes).ok()?);
+            let address = if ip.is_unspecified() {
+                "*".to_string()
+            } else {
+                format!("[{}]", ip)
+            };
+            ("IPv6", address)
+        }
+        _ => return None,
+    };
+    Some((family.to_string(), address, port))
+}
+
+/// Socket inode → pid, from the fd links of every process we may read
+fn socket_owners() -> HashMap<String, String> {
+    let mut owners = HashMap::new();
+    let Ok(entries) = std::fs::read_dir(crate::fixtures::proc_path("")) else {
+        return owners;
+    };
+    for entry in entries.flatten() {
+        let pid = entry.file_name().to_string_lossy().into_owned();
+        if !pid.bytes().all(|b| b.is_ascii_digit()) {
+            continue;
+        }
+        let Ok(fds) = std::fs::read_dir(entry.path().join("fd")) else {
+            continue;
+        };
+        for fd in fds.flatten() {
+            let Ok(target) = std::fs::read_link(fd.path()) else {
+                continue;
+            };
+            if let Some(inode) = target
+                .to_str()
+                .and_then(|t| t.strip_prefix("socket:["))
+                .and_then(|t| t.strip_suffix(']'))
+            {
+                owners
+                    .entry(inode.to_string())
+                    .or_insert_with(|| pid.clone());
+            }
+        }
+    }
+    owners
+}
+
+/// uid → user name from `/etc/passwd`
+pub fn user_names() -> HashMap<String, String> {
+    std::fs::read_to_string("/etc/passwd")
+        .unwrap_or_default()
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split(':');
+            let name = fields.next()?;
+            let uid = fields.nth(1)?;
+            Some((uid.to_string(), name.to_string()))
+        })
+        .collect()
+}
+
+/// `sl local_address rem_address st tx_queue:rx_queue tr:tm->when retrnsmt uid timeout
+/// inode ...`, e.g. `0: 0100007F:0BB8 00000000:0000 0A ... 1000 0 412345 ...`
+pub fn parse(tables: &str) -> Parsed {
+    let mut strings = Interner::default();
+    let mut parsed = Parsed::default();
+    let owners = socket_owners();
+    let users = user_names();
+    let mut names: HashMap<String, String> = HashMap::new();
+    let mut protocol = Protocol::Tcp;
+
+    for line in tables.lines() {
+        let parts: Vec<&str> = line.split_whitespace().collect();
+        // Header lines start with `sl`; only the UDP tables have a `drops` column
+        if parts.first() == Some(&"sl") {
+            protocol = if parts.contains(&"drops") {
+                Protocol::Udp
+            } else {
+                Protocol::Tcp
+            };
+            continue;
+        }
+        let Some(state) = parts.get(3).and_then(|st| state(protocol, st)) else {
+            continue;
+        };
+        let (
+            Some((family, address, port)),
+            Some((_, peer_address, peer_port)),
+            Some(uid),
+            Some(inode),
+        ) = (
+            decode_address(parts[1]),
+            parts.get(2).and_then(|field| decode_address(field)),
+            parts.get(7),
+            parts.get(9),
+        )
+        else {
+            parsed.skipped.push(line.trim().to_string());
+            continue;
+        };
+        // Sockets closed by their process (TIME_WAIT) have no owner, and unbound UDP
+        // sockets have port 0
+        let Some(pid) = owners.get(*inode).filter(|_| port != 0) else {
+            continue;
+        };
+        let process = names.entry(pid.clone()).or_insert_with(|| {
+            std::fs::read_to_string(crate::fixtures::proc_path(&format!("{}/comm", pid)))
+                .map(|comm| comm.trim_end().to_string())
+                .unwrap_or_default()
+        });
+        parsed.sockets.push(Listener {
+            process: strings.intern(process),
+            pid: strings.intern(pid),
+            user: users.get(*uid).cloned().unwrap_or_else(|| uid.to_string()),
+            family,
+            address,
+            port,
+            protocol,
+            state: state.to_string(),
+            peer: (peer_port != 0).then(|| format!("{}:{}", peer_address, peer_port)),
+            socket: inode.to_string(),
+        });
+    }
+    parsed
+}
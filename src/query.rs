@@ -0,0 +1,363 @@
+//! `--where` filter expressions and `[queries]` saved in the config
+//!
+//! ```text
+//! port in 3000..4000 && process =~ "node|bun" && user == me
+//! !(address == "127.0.0.1") || port >= 8000
+//! ```
+//!
+//! Fields: `port`, `pid`, `process`, `command`, `user`, `address`, `interface`,
+//! `family`, `protocol` (`tcp`/`udp`) and `state` (`listen`, `unconn` for UDP, or the
+//! state of a connection with `--state`). `=~` matches case-insensitive
+//! substrings; alternatives are separated by `|` and may be anchored with `^`/`$`.
+//! `==#`, `!=#`, `=~#` and `!~#` are the case-sensitive variants (`process ==# Node`).
+//! `in a..b` excludes `b`, `in a..=b` includes it. The value `me` is the current user.
+
+use anyhow::{Context, Result};
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Ident(String),
+    Str(String),
+    Num(u64),
+    Op(&'static str),
+    LParen,
+    RParen,
+}
+
+const OPERATORS: &[&str] = &[
+    "&&", "||", "..=", "..", "==#", "!=#", "=~#", "!~#", "==", "!=", "=~", "!~", "<=", ">=", "<",
+    ">", "!",
+];
+
+/// Comparisons between a field and a value; `#` makes text comparisons case-sensitive
+const COMPARISONS: &[&str] = &[
+    "==", "!=", "=~", "!~", "==#", "!=#", "=~#", "!~#", "<", "<=", ">", ">=",
+];
+
+fn tokenize(input: &str) -> Result<Vec<Token>> {
+    let mut tokens = Vec::new();
+    let mut rest = input.trim_start();
+    while !rest.is_empty() {
+        let c = rest.chars().next().unwrap_or_default();
+        if c == '(' || c == ')' {
+            tokens.push(if c == '(' {
+                Token::LParen
+            } else {
+                Token::RParen
+            });
+            rest = &rest[1..];
+        } else if c == '"' || c == '\'' {
+            let end = rest[1..]
+                .find(c)
+                .with_context(|| format!("Unterminated string in --where: {}", rest))?;
+            tokens.push(Token::Str(rest[1..=end].to_string()));
+            rest = &rest[end + 2..];
+        } else if c.is_ascii_digit() {
+            // A number, or an unquoted address like 127.0.0.1 (but not the `..` of a range)
+            let bytes = rest.as_bytes();
+            let mut end = 0;
+            while end < bytes.len()
+                && (bytes[end].is_ascii_digit()
+                    || (bytes[end] == b'.' && bytes.get(end + 1).is_some_and(u8::is_ascii_digit)))
+            {
+                end += 1;
+            }
+            let word = &rest[..end];
+            tokens.push(match word.parse() {
+                Ok(n) => Token::Num(n),
+                Err(_) => Token::Ident(word.to_string()),
+            });
+            rest = &rest[end..];
+        } else if c.is_alphanumeric() || c == '_' {
+            let end = rest
+                .find(|c: char| !(c.is_alphanumeric() || c == '_' || c == '-' || c == '.'))
+                .unwrap_or(rest.len());
+            tokens.push(Token::Ident(rest[..end].to_string()));
+            rest = &rest[end..];
+        } else if let Some(op) = OPERATORS.iter().find(|op| rest.starts_with(*op)) {
+            tokens.push(Token::Op(op));
+            rest = &rest[op.len()..];
+        } else {
+            anyhow::bail!("Unexpected '{}' in --where expression", c);
+        }
+        rest = rest.trim_start();
+    }
+    Ok(tokens)
+}
+
+#[derive(Debug, Clone)]
+pub enum Value {
+    Num(u64),
+    Str(String),
+}
+
+#[derive(Debug, Clone)]
+pub enum Expr {
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Not(Box<Expr>),
+    Compare {
+        field: String,
+        op: &'static str,
+        value: Value,
+    },
+    InRange {
+        field: String,
+        start: u64,
+        end: u64,
+    },
+}
+
+const FIELDS: &[&str] = &[
+    "port",
+    "pid",
+    "process",
+    "command",
+    "user",
+    "address",
+    "interface",
+    "family",
+    "protocol",
+    "state",
+];
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn eat(&mut self, op: &str) -> bool {
+        let found = matches!(self.peek(), Some(Token::Op(o)) if *o == op);
+        if found {
+            self.pos += 1;
+        }
+        found
+    }
+
+    fn or(&mut self) -> Result<Expr> {
+        let mut left = self.and()?;
+        while self.eat("||") {
+            left = Expr::Or(Box::new(left), Box::new(self.and()?));
+        }
+        Ok(left)
+    }
+
+    fn and(&mut self) -> Result<Expr> {
+        let mut left = self.unary()?;
+        while self.eat("&&") {
+            left = Expr::And(Box::new(left), Box::new(self.unary()?));
+        }
+        Ok(left)
+    }
+
+    fn unary(&mut self) -> Result<Expr> {
+        if self.eat("!") {
+            return Ok(Expr::Not(Box::new(self.unary()?)));
+        }
+        if self.peek() == Some(&Token::LParen) {
+            self.pos += 1;
+            let expr = self.or()?;
+            anyhow::ensure!(self.next() == Some(Token::RParen), "Missing ')' in --where");
+            return Ok(expr);
+        }
+        self.comparison()
+    }
+
+    fn comparison(&mut self) -> Result<Expr> {
+        let field = match self.next() {
+            Some(Token::Ident(field)) if FIELDS.contains(&field.as_str()) => field,
+            Some(Token::Ident(field)) => anyhow::bail!(
+                "Unknown field '{}' in --where (fields: {})",
+                field,
+                FIELDS.join(", ")
+            ),
+            other => anyhow::bail!("Expected a field name in --where, found {:?}", other),
+        };
+
+        if self.peek() == Some(&Token::Ident("in".to_string())) {
+            self.pos += 1;
+            let start = self.number()?;
+            let inclusive = match self.next() {
+                Some(Token::Op("..")) => false,
+                Some(Token::Op("..=")) => true,
+                _ => anyhow::bail!("Expected a range like 3000..4000 after `{} in`", field),
+            };
+            let end = self.number()? + u64::from(inclusive);
+            return Ok(Expr::InRange { field, start, end });
+        }
+
+        let op = match self.next() {
+            Some(Token::Op(op)) if COMPARISONS.contains(&op) => op,
+            other => anyhow::bail!("Expected an operator after '{}', found {:?}", field, other),
+        };
+        let value = match self.next() {
+            Some(Token::Num(n)) => Value::Num(n),
+            Some(Token::Str(s)) | Some(Token::Ident(s)) => Value::Str(s),
+            other => anyhow::bail!(
+                "Expected a value after '{} {}', found {:?}",
+                field,
+                op,
+                other
+            ),
+        };
+        Ok(Expr::Compare { field, op, value })
+    }
+
+    fn number(&mut self) -> Result<u64> {
+        match self.next() {
+            Some(Token::Num(n)) => Ok(n),
+            other => anyhow::bail!("Expected a number in --where, found {:?}", other),
+        }
+    }
+}
+
+/// Parse a `--where` expression
+pub fn parse(input: &str) -> Result<Expr> {
+    let mut parser = Parser {
+        tokens: tokenize(input)?,
+        pos: 0,
+    };
+    let expr = parser.or()?;
+    anyhow::ensure!(
+        parser.pos >= parser.tokens.len(),
+        "Unexpected {:?} in --where expression",
+        parser.tokens[parser.pos]
+    );
+    Ok(expr)
+}
+
+/// `|`-separated alternatives, each optionally anchored with `^`/`$`
+fn pattern_matches(pattern: &str, text: &str, case_sensitive: bool) -> bool {
+    let (pattern, text) = if case_sensitive {
+        (pattern.to_string(), text.to_string())
+    } else {
+        (pattern.to_lowercase(), text.to_lowercase())
+    };
+    pattern.split('|').any(|alt| {
+        let (start, alt) = alt.strip_prefix('^').map_or((false, alt), |a| (true, a));
+        let (end, alt) = alt.strip_suffix('$').map_or((false, alt), |a| (true, a));
+        match (start, end) {
+            (true, true) => text == alt,
+            (true, false) => text.starts_with(alt),
+            (false, true) => text.ends_with(alt),
+            (false, false) => text.contains(alt),
+        }
+    })
+}
+
+fn current_user() -> String {
+    std::env::var("USER")
+        .or_else(|_| std::env::var("USERNAME"))
+        .unwrap_or_default()
+}
+
+impl Expr {
+    pub fn matches(&self, info: &crate::PortInfo) -> bool {
+        match self {
+            Expr::And(a, b) => a.matches(info) && b.matches(info),
+            Expr::Or(a, b) => a.matches(info) || b.matches(info),
+            Expr::Not(e) => !e.matches(info),
+            Expr::InRange { field, start, end } => {
+                numeric(field, info).is_some_and(|n| (*start..*end).contains(&n))
+            }
+            Expr::Compare { field, op, value } => {
+                let (op, case_sensitive) = op.strip_suffix('#').map_or((*op, false), |o| (o, true));
+                if let (Some(left), Value::Num(right)) = (numeric(field, info), value) {
+                    return match op {
+                        "==" => left == *right,
+                        "!=" => left != *right,
+                        "<" => left < *right,
+                        "<=" => left <= *right,
+                        ">" => left > *right,
+                        ">=" => left >= *right,
+                        _ => false,
+                    };
+                }
+                let left = text(field, info);
+                let right = match value {
+                    Value::Num(n) => n.to_string(),
+                    Value::Str(s) if field == "user" && s == "me" => current_user(),
+                    Value::Str(s) => s.clone(),
+                };
+                let equal = || {
+                    if case_sensitive {
+                        left == right
+                    } else {
+                        left.eq_ignore_ascii_case(&right)
+                    }
+                };
+                match op {
+                    "==" => equal(),
+                    "!=" => !equal(),
+                    "=~" => pattern_matches(&right, &left, case_sensitive),
+                    "!~" => !pattern_matches(&right, &left, case_sensitive),
+                    "<" => left < right,
+                    "<=" => left <= right,
+                    ">" => left > right,
+                    ">=" => left >= right,
+                    _ => false,
+                }
+            }
+        }
+    }
+}
+
+fn numeric(field: &str, info: &crate::PortInfo) -> Option<u64> {
+    match field {
+        "port" => Some(u64::from(info.port)),
+        "pid" => info.pid.parse().ok(),
+        _ => None,
+    }
+}
+
+fn text(field: &str, info: &crate::PortInfo) -> String {
+    match field {
+        "port" => info.port.to_string(),
+        "pid" => info.pid.to_string(),
+        "process" => info.process.to_string(),
+        "command" => info.command.clone(),
+        "user" => info.user.clone(),
+        "address" => info.address.clone(),
+        "interface" => info.interface.clone(),
+        "family" => info.family.clone(),
+        "protocol" => info.protocol.name().to_string(),
+        "state" => info.state.clone(),
+        _ => String::new(),
+    }
+}
+
+/// Combine `--where` and `--query <name>` (from `[queries]`) into one filter
+pub fn from_args(
+    expression: Option<&str>,
+    query: Option<&str>,
+    config: &crate::Config,
+) -> Result<Option<Expr>> {
+    let saved = match query {
+        Some(name) => {
+            let source = config.queries.get(name).with_context(|| {
+                format!(
+                    "No saved query '{}' in the [queries] table of the config",
+                    name
+                )
+            })?;
+            Some(parse(source).with_context(|| format!("In saved query '{}'", name))?)
+        }
+        None => None,
+    };
+    let inline = expression.map(parse).transpose()?;
+    Ok(match (saved, inline) {
+        (Some(a), Some(b)) => Some(Expr::And(Box::new(a), Box::new(b))),
+        (a, b) => a.or(b),
+    })
+}
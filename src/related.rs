@@ -0,0 +1,70 @@
+//! `related <port>`: every port held by the process on a port and its children
+//!
+//! Frameworks rarely stop at one port: a dev server opens its HTTP port plus an HMR
+//! websocket, an inspector or a metrics endpoint, often from a child process.
+
+use crate::theme::Themed;
+use anyhow::Result;
+use colored::Colorize;
+
+pub fn run(port: u16) -> Result<()> {
+    let infos = crate::get_port_info()?;
+    let owners = crate::deduplicate_pids(&crate::filter_port_infos(
+        infos.clone(),
+        Some(port),
+        None,
+        None,
+    ));
+    anyhow::ensure!(
+        !owners.is_empty(),
+        "No process is listening on port {}",
+        port
+    );
+
+    // The owners first, then their children (which may be owners themselves)
+    let mut family: Vec<String> = owners.iter().map(|pid| pid.to_string()).collect();
+    for owner in &owners {
+        for child in crate::kill::descendants(owner)? {
+            if !family.contains(&child) {
+                family.push(child);
+            }
+        }
+    }
+
+    let mut related: Vec<&crate::PortInfo> = infos
+        .iter()
+        .filter(|info| family.iter().any(|pid| *pid == *info.pid))
+        .collect();
+    related.sort_by_key(|info| info.port);
+    related.dedup_by_key(|info| (info.port, info.pid.clone()));
+
+    println!(
+        "{} {}",
+        format!("Port {}", port).accent().bold(),
+        format!("({} port(s) in the process tree)", related.len()).muted()
+    );
+    for info in related {
+        let mut notes = Vec::new();
+        if info.port == port {
+            notes.push("this port".to_string());
+        }
+        if !owners.contains(&info.pid) {
+            notes.push("child process".to_string());
+        }
+        if let Some(service) = crate::well_known::service(info.port) {
+            notes.push(service.to_string());
+        }
+        let mut line = format!(
+            "  {:>5}  {} {}  {}",
+            info.port.to_string().accent().bold(),
+            info.process.success(),
+            crate::format_pid(&info.pid).muted(),
+            info.address
+        );
+        if !notes.is_empty() {
+            line.push_str(&format!("  {}", notes.join(", ").muted()));
+        }
+        println!("{}", line);
+    }
+    Ok(())
+}
@@ -0,0 +1,130 @@
+//! Port visibility in remote dev environments: GitHub Codespaces and SSH sessions
+//!
+//! There, "listening on 127.0.0.1" says little about who can reach a port. Codespaces
+//! forwards ports with a visibility (private, org, public), read from `gh codespace
+//! ports` when the CLI is available; over SSH, loopback ports need `ssh -L`.
+
+use crate::interfaces::Exposure;
+use crate::theme::Themed;
+use colored::ColoredString;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::process::Command;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Forwarding {
+    /// Anyone with the URL
+    Public,
+    /// Members of the codespace's organization
+    Org,
+    /// Only the codespace's owner
+    Private,
+    /// Codespaces forwards new ports on its own; the CLI couldn't say how
+    Auto,
+    /// Not in the codespace's forwarded ports (yet)
+    NotForwarded,
+    /// Loopback on the remote host: reachable from the laptop only through `ssh -L`
+    SshTunnel,
+}
+
+impl Forwarding {
+    pub fn text(&self) -> &'static str {
+        match self {
+            Forwarding::Public => "public",
+            Forwarding::Org => "org",
+            Forwarding::Private => "private",
+            Forwarding::Auto => "auto-forwarded",
+            Forwarding::NotForwarded => "not forwarded",
+            Forwarding::SshTunnel => "ssh -L only",
+        }
+    }
+
+    /// Public ports stand out: they are open to the internet
+    pub fn display(&self) -> ColoredString {
+        match self {
+            Forwarding::Public => self.text().warning(),
+            Forwarding::NotForwarded | Forwarding::SshTunnel => self.text().muted(),
+            _ => self.text().label(),
+        }
+    }
+}
+
+enum Environment {
+    Codespaces { name: String },
+    Ssh,
+}
+
+fn environment() -> Option<Environment> {
+    if std::env::var("CODESPACES").is_ok_and(|v| v == "true") {
+        if let Ok(name) = std::env::var("CODESPACE_NAME") {
+            return Some(Environment::Codespaces { name });
+        }
+    }
+    if std::env::var_os("SSH_CONNECTION").is_some() || std::env::var_os("SSH_CLIENT").is_some() {
+        return Some(Environment::Ssh);
+    }
+    None
+}
+
+/// An entry of `gh codespace ports --json sourcePort,visibility`
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CodespacePort {
+    source_port: u16,
+    visibility: String,
+}
+
+/// Visibility of the codespace's forwarded ports, or `None` without a working `gh`
+fn codespace_ports(name: &str) -> Option<HashMap<u16, Forwarding>> {
+    let output = Command::new("gh")
+        .args(["codespace", "ports", "--codespace", name])
+        .args(["--json", "sourcePort,visibility"])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())?;
+    let ports: Vec<CodespacePort> = serde_json::from_slice(&output.stdout).ok()?;
+    Some(
+        ports
+            .into_iter()
+            .map(|port| {
+                let forwarding = match port.visibility.as_str() {
+                    "public" => Forwarding::Public,
+                    "org" => Forwarding::Org,
+                    _ => Forwarding::Private,
+                };
+                (port.source_port, forwarding)
+            })
+            .collect(),
+    )
+}
+
+/// Set `forwarding` on listening TCP ports when running in a remote environment
+pub fn annotate<'a>(infos: impl Iterator<Item = &'a mut crate::GroupedPortInfo>) {
+    let Some(environment) = environment() else {
+        return;
+    };
+    let codespace = match &environment {
+        Environment::Codespaces { name } => codespace_ports(name),
+        Environment::Ssh => None,
+    };
+    for info in infos {
+        if info.protocol != crate::collector::Protocol::Tcp
+            || info.state != crate::collector::LISTEN
+        {
+            continue;
+        }
+        info.forwarding = match (&environment, &codespace) {
+            (Environment::Codespaces { .. }, Some(ports)) => Some(
+                ports
+                    .get(&info.port)
+                    .copied()
+                    .unwrap_or(Forwarding::NotForwarded),
+            ),
+            (Environment::Codespaces { .. }, None) => Some(Forwarding::Auto),
+            (Environment::Ssh, _) if info.exposure == Exposure::Local => {
+                Some(Forwarding::SshTunnel)
+            }
+            (Environment::Ssh, _) => None,
+        };
+    }
+}
@@ -0,0 +1,128 @@
+//! `report --weekly`: a Markdown digest of the port sessions recorded by `daemon`
+
+use crate::history::{self, SessionRecord};
+use anyhow::Result;
+use std::collections::BTreeMap;
+
+const WEEK: u64 = 7 * 24 * 3600;
+/// Rows per table
+const TOP: usize = 5;
+/// Unconfigured ports used in at least this many sessions are suggested for the config
+const SUGGEST_SESSIONS: usize = 3;
+
+fn format_duration(secs: u64) -> String {
+    match secs {
+        s if s >= 24 * 3600 => format!("{}d {}h", s / (24 * 3600), s % (24 * 3600) / 3600),
+        s if s >= 3600 => format!("{}h {}m", s / 3600, s % 3600 / 60),
+        s => format!("{}m", s / 60),
+    }
+}
+
+/// Who held a port: the project when known, else the process name
+fn holder(session: &SessionRecord) -> &str {
+    session.project.as_deref().unwrap_or(&session.process)
+}
+
+pub fn run_weekly() -> Result<()> {
+    let config = crate::Config::load()?;
+    let configured = config.monitored_ports()?;
+    let since = history::now().saturating_sub(WEEK);
+    let sessions: Vec<SessionRecord> = history::read(history::SESSIONS)?
+        .into_iter()
+        .filter(|s: &SessionRecord| s.end >= since)
+        .collect();
+
+    println!("# Weekly port report");
+    println!();
+    if sessions.is_empty() {
+        println!(
+            "No port sessions recorded in the last 7 days; \
+             they are recorded while `lsof-work-ports daemon` runs."
+        );
+        return Ok(());
+    }
+
+    let mut by_port: BTreeMap<u16, Vec<&SessionRecord>> = BTreeMap::new();
+    for session in &sessions {
+        by_port.entry(session.port).or_default().push(session);
+    }
+    println!(
+        "- **{}** unique ports used in **{}** sessions",
+        by_port.len(),
+        sessions.len()
+    );
+    println!();
+
+    println!("## Longest-running servers");
+    println!();
+    println!("| Port | Process | Project | Ran for |");
+    println!("| ---: | --- | --- | ---: |");
+    let mut longest: Vec<&SessionRecord> = sessions.iter().collect();
+    longest.sort_by_key(|s| std::cmp::Reverse(s.end - s.start.max(since)));
+    for session in longest.iter().take(TOP) {
+        println!(
+            "| {} | {} | {} | {} |",
+            session.port,
+            session.process,
+            session.project.as_deref().unwrap_or("-"),
+            format_duration(session.end - session.start.max(since))
+        );
+    }
+    println!();
+
+    // Ports that changed hands between projects/processes are where conflicts happen
+    let mut contested: Vec<(u16, Vec<&str>, usize)> = by_port
+        .iter()
+        .map(|(port, sessions)| {
+            let mut holders: Vec<&str> = sessions.iter().map(|s| holder(s)).collect();
+            holders.sort_unstable();
+            holders.dedup();
+            (*port, holders, sessions.len())
+        })
+        .filter(|(_, holders, _)| holders.len() > 1)
+        .collect();
+    contested.sort_by_key(|(port, holders, count)| {
+        (
+            std::cmp::Reverse(holders.len()),
+            std::cmp::Reverse(*count),
+            *port,
+        )
+    });
+    println!("## Most conflict-prone ports");
+    println!();
+    if contested.is_empty() {
+        println!("Every port was used by a single project this week.");
+    } else {
+        println!("| Port | Used by | Sessions |");
+        println!("| ---: | --- | ---: |");
+        for (port, holders, count) in contested.iter().take(TOP) {
+            println!("| {} | {} | {} |", port, holders.join(", "), count);
+        }
+    }
+    println!();
+
+    let suggestions: Vec<(u16, &str)> = by_port
+        .iter()
+        .filter(|(port, sessions)| sessions.len() >= SUGGEST_SESSIONS && !configured.contains(port))
+        .map(|(port, sessions)| (*port, holder(sessions[sessions.len() - 1])))
+        .collect();
+    println!("## Suggestions");
+    println!();
+    if suggestions.is_empty() {
+        println!("All regularly used ports are in the config.");
+    } else {
+        println!("Regularly used ports that are not in the config yet:");
+        println!();
+        println!("```toml");
+        for (i, (port, name)) in suggestions.iter().enumerate() {
+            if i > 0 {
+                println!();
+            }
+            println!("[[ports]]");
+            println!("ports = \"{}\"", port);
+            println!("name = \"{}\"", name);
+        }
+        println!("```");
+    }
+    Ok(())
+}
@@ -0,0 +1,370 @@
+//! `schema config` / `schema output`: JSON Schema documents for the config file and the
+//! `--json` listing, for editor validation (taplo, Even Better TOML) and code generation
+//!
+//! The documents mirror the serde types field by field; enum values and default lists
+//! are taken from the types themselves, and the tests below check that every field the
+//! types serialize is described.
+
+use anyhow::Result;
+use clap::ValueEnum;
+use serde_json::{json, Value};
+
+const DRAFT: &str = "https://json-schema.org/draft/2020-12/schema";
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum SchemaKind {
+    /// config.toml
+    Config,
+    /// The array printed by `--json`
+    Output,
+}
+
+/// Names of a `ValueEnum` as written in the config (same as on the command line)
+fn variants<T: ValueEnum>() -> Vec<String> {
+    T::value_variants()
+        .iter()
+        .filter_map(|v| v.to_possible_value())
+        .map(|v| v.get_name().to_string())
+        .collect()
+}
+
+fn port() -> Value {
+    json!({ "type": "integer", "minimum": 0, "maximum": 65535 })
+}
+
+fn strings(description: &str) -> Value {
+    json!({
+        "type": "array",
+        "items": { "type": "string" },
+        "description": description,
+    })
+}
+
+fn string_map(description: &str) -> Value {
+    json!({
+        "type": "object",
+        "additionalProperties": { "type": "string" },
+        "description": description,
+    })
+}
+
+fn config_schema() -> Result<Value> {
+    let defaults = serde_json::to_value(crate::Config::default())?;
+    let port_entry = json!({
+        "type": "object",
+        "required": ["ports"],
+        "additionalProperties": false,
+        "properties": {
+            "ports": {
+                "type": "string",
+                "description": "Ports and ranges, e.g. \"3000-3100,8080\"",
+            },
+            "name": { "type": "string" },
+            "http": {
+                "type": "string",
+                "description": "Health check path requested over HTTP, e.g. /healthz",
+            },
+            "status": {
+                "type": "integer",
+                "minimum": 100,
+                "maximum": 599,
+                "description": "Expected HTTP status of the health check (default: 200)",
+            },
+        },
+    });
+    let rule = json!({
+        "type": "object",
+        "additionalProperties": false,
+        "properties": {
+            "name": {
+                "type": "string",
+                "description": "Label used in notifications",
+            },
+            "port": port(),
+            "down_for": {
+                "type": "integer",
+                "minimum": 0,
+                "description": "Seconds nothing listens on `port` before alerting",
+            },
+            "process": { "type": "string" },
+            "max_processes": {
+                "type": "integer",
+                "minimum": 0,
+                "description": "Alert when more processes with the name `process` hold ports",
+            },
+        },
+    });
+    let notifier = json!({
+        "oneOf": [
+            {
+                "type": "object",
+                "required": ["type"],
+                "additionalProperties": false,
+                "properties": { "type": { "enum": ["stdout", "desktop"] } },
+            },
+            {
+                "type": "object",
+                "required": ["type", "command"],
+                "additionalProperties": false,
+                "properties": {
+                    "type": { "const": "command" },
+                    "command": {
+                        "type": "string",
+                        "description": "Shell command run with the message in $LWP_ALERT",
+                    },
+                },
+            },
+        ],
+    });
+
+    Ok(json!({
+        "$schema": DRAFT,
+        "title": "lsof-work-ports config",
+        "type": "object",
+        "additionalProperties": false,
+        "properties": {
+            "dev_processes": {
+                "type": "array",
+                "items": { "type": "string" },
+                "description": "Process names that indicate development",
+                "default": defaults["dev_processes"],
+            },
+            "dev_keywords": {
+                "type": "array",
+                "items": { "type": "string" },
+                "description": "Keywords in command lines that indicate development",
+                "default": defaults["dev_keywords"],
+            },
+            "exclude_processes": {
+                "type": "array",
+                "items": { "type": "string" },
+                "description": "Process names to exclude from dev detection",
+                "default": defaults["exclude_processes"],
+            },
+            "score_threshold": {
+                "type": "integer",
+                "minimum": 0,
+                "description": "Minimum score to be considered a dev process",
+                "default": defaults["score_threshold"],
+            },
+            "ports": {
+                "type": "array",
+                "items": port_entry,
+                "description": "Monitored port ranges",
+            },
+            "display": {
+                "type": "object",
+                "additionalProperties": false,
+                "description": "Listing preferences (toggled from --interactive)",
+                "properties": {
+                    "expand_multis": { "type": "boolean", "default": true },
+                    "expand_process_groups": { "type": "boolean", "default": true },
+                    "pinned": {
+                        "type": "array",
+                        "items": port(),
+                        "description": "Ports listed first in their own block",
+                    },
+                },
+            },
+            "alerts": {
+                "type": "object",
+                "additionalProperties": false,
+                "description": "Alert rules evaluated by `daemon`",
+                "properties": {
+                    "rules": { "type": "array", "items": rule },
+                    "notifiers": {
+                        "type": "array",
+                        "items": notifier,
+                        "description": "Where alerts are sent (default: stdout)",
+                    },
+                },
+            },
+            "services": string_map("Services started by `up` without a Procfile (name → command)"),
+            "queries": string_map("Named --where expressions, used with --query <name>"),
+            "secret_keys": strings("Extra option/variable names whose values are masked"),
+            "theme": { "enum": variants::<crate::theme::ThemeName>() },
+            "glyphs": { "enum": variants::<crate::output::GlyphSet>() },
+            "insights": {
+                "type": "boolean",
+                "default": false,
+                "description": "Record which commands and flags are used, for `insights`",
+            },
+            "collector": {
+                "type": "object",
+                "additionalProperties": false,
+                "properties": {
+                    "lsof": {
+                        "type": "object",
+                        "additionalProperties": false,
+                        "properties": {
+                            "binary": {
+                                "type": "string",
+                                "description": "Program run instead of lsof",
+                            },
+                            "extra_args": strings("Arguments placed before ours"),
+                        },
+                    },
+                },
+            },
+            "hook": {
+                "type": "string",
+                "description": "Receives the rows as JSON lines and prints the ones to show",
+            },
+        },
+    }))
+}
+
+fn output_schema() -> Value {
+    json!({
+        "$schema": DRAFT,
+        "title": "lsof-work-ports --json output",
+        "type": "array",
+        "items": {
+            "type": "object",
+            "required": [
+                "port", "process", "pids", "command", "start_time", "monitored",
+                "section", "address", "interface", "family", "protocol", "state",
+            ],
+            "additionalProperties": false,
+            "properties": {
+                "port": port(),
+                "process": { "type": "string" },
+                "pids": { "type": "array", "items": { "type": "string" } },
+                "command": { "type": "string", "description": "Command line of the first process" },
+                "start_time": { "type": "string", "description": "Start time as printed by ps" },
+                "monitored": {
+                    "type": "boolean",
+                    "description": "In a [[ports]] range of the config",
+                },
+                "section": { "enum": ["dev", "others", "multis", "process_groups"] },
+                "address": {
+                    "type": "string",
+                    "description": "Local address, \"*\" for all addresses",
+                },
+                "interface": { "type": "string", "description": "e.g. lo, en0, or all" },
+                "family": { "enum": ["IPv4", "IPv6", "IPv4+IPv6"] },
+                "protocol": { "enum": ["TCP", "UDP"] },
+                "state": {
+                    "type": "string",
+                    "description": "LISTEN, UNCONN (UDP) or the state of a connection (ESTABLISHED, ...)",
+                },
+                "peer": { "type": "string", "description": "Remote address:port of a connection" },
+                "label": { "type": "string", "description": "Set by the row hook" },
+                "port_name": {
+                    "type": "string",
+                    "description": "Name of the [[ports]] range the port is in",
+                },
+                "forwarding": {
+                    "enum": [
+                        "public", "org", "private", "auto-forwarded", "not forwarded", "ssh -L only",
+                    ],
+                    "description": "Visibility in GitHub Codespaces, or loopback over SSH",
+                },
+            },
+        },
+    })
+}
+
+pub fn run(kind: SchemaKind) -> Result<()> {
+    let schema = match kind {
+        SchemaKind::Config => config_schema()?,
+        SchemaKind::Output => output_schema(),
+    };
+    println!("{}", serde_json::to_string_pretty(&schema)?);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Paths in `value` that `schema` has no property for
+    fn uncovered(value: &Value, schema: &Value, path: &str) -> Vec<String> {
+        if let Some(branches) = schema["oneOf"].as_array() {
+            let covered = branches
+                .iter()
+                .any(|branch| uncovered(value, branch, path).is_empty());
+            return if covered {
+                Vec::new()
+            } else {
+                vec![path.to_string()]
+            };
+        }
+        match value {
+            Value::Object(fields) => fields
+                .iter()
+                .flat_map(|(key, field)| {
+                    let path = format!("{}.{}", path, key);
+                    let map_values = schema.get("additionalProperties").filter(|s| s.is_object());
+                    match schema["properties"].get(key).or(map_values) {
+                        Some(schema) => uncovered(field, schema, &path),
+                        None => vec![path],
+                    }
+                })
+                .collect(),
+            Value::Array(items) => items
+                .iter()
+                .flat_map(|item| uncovered(item, &schema["items"], path))
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// The example config with every optional setting filled in
+    fn full_config() -> crate::Config {
+        let mut config: crate::Config =
+            toml::from_str(include_str!("../example-config.toml")).unwrap();
+        config.theme = crate::theme::ThemeName::value_variants().first().copied();
+        config.glyphs = crate::output::GlyphSet::value_variants().first().copied();
+        config.insights = true;
+        config.display.pinned = vec![3000];
+        config.display.expand_multis = false;
+        #[cfg(feature = "hooks")]
+        {
+            config.hook = Some("./hook.sh".to_string());
+        }
+        config
+    }
+
+    #[test]
+    fn config_schema_describes_every_serialized_field() {
+        let schema = config_schema().unwrap();
+        for config in [crate::Config::default(), full_config()] {
+            let value = serde_json::to_value(&config).unwrap();
+            assert_eq!(uncovered(&value, &schema, "config"), Vec::<String>::new());
+        }
+    }
+
+    #[test]
+    fn output_schema_describes_every_field() {
+        let schema = output_schema();
+        let record = crate::JsonRecord {
+            port: 3000,
+            process: "node",
+            pids: vec!["4242"],
+            command: "node server.js",
+            start_time: "Mon Jan  1 00:00:00 2024",
+            monitored: true,
+            section: "dev",
+            address: "*",
+            interface: "all",
+            family: "IPv4",
+            protocol: "TCP",
+            state: "ESTABLISHED",
+            peer: Some("127.0.0.1:51234"),
+            label: Some("web"),
+            port_name: Some("frontend"),
+            forwarding: Some("private"),
+        };
+        let value = serde_json::to_value(vec![record]).unwrap();
+        assert_eq!(uncovered(&value, &schema, "output"), Vec::<String>::new());
+        for required in schema["items"]["required"].as_array().unwrap() {
+            let key = required.as_str().unwrap();
+            assert!(
+                value[0].get(key).is_some(),
+                "required `{}` is not serialized",
+                key
+            );
+        }
+    }
+}
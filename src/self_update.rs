@@ -0,0 +1,256 @@
+//! Self-update from GitHub releases
+//!
+//! Only compiled with the `self-update` feature. Package-manager builds should
+//! disable it so the binary is updated through the package manager instead.
+//!
+//! The download is checked against the release's `SHA256SUMS` only. That catches
+//! corrupted or truncated downloads, but there is no signature: checksums and binary
+//! come from the same release, so whoever can publish a release can replace both.
+
+use anyhow::{Context, Result};
+use clap::ValueEnum;
+use serde::Deserialize;
+use std::path::Path;
+use std::process::Command;
+
+const REPO: &str = "elzup/lsof-work-ports";
+
+/// Tag used for the rolling nightly release
+const NIGHTLY_TAG: &str = "nightly";
+
+/// Release asset listing `<sha256>  <asset name>` for every binary
+const CHECKSUMS_ASSET: &str = "SHA256SUMS";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Channel {
+    /// Latest tagged release
+    Stable,
+    /// Rolling build of the main branch
+    Nightly,
+}
+
+/// `1.2.3` or `1.2.3-rc.1`, ordered by semver precedence (build metadata is ignored)
+#[derive(Debug, PartialEq, Eq)]
+struct Version {
+    core: [u64; 3],
+    pre: Vec<String>,
+}
+
+impl Version {
+    fn parse(text: &str) -> Option<Self> {
+        let text = text.split('+').next()?;
+        let (core, pre) = match text.split_once('-') {
+            Some((core, pre)) => (core, pre.split('.').map(str::to_string).collect()),
+            None => (text, Vec::new()),
+        };
+        let numbers = core
+            .split('.')
+            .map(|n| n.parse().ok())
+            .collect::<Option<Vec<u64>>>()?;
+        Some(Self {
+            core: numbers.try_into().ok()?,
+            pre,
+        })
+    }
+}
+
+impl Ord for Version {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        use std::cmp::Ordering;
+        self.core.cmp(&other.core).then_with(|| {
+            match (self.pre.is_empty(), other.pre.is_empty()) {
+                (true, true) => Ordering::Equal,
+                // A pre-release comes before its release
+                (true, false) => Ordering::Greater,
+                (false, true) => Ordering::Less,
+                (false, false) => {
+                    // Numeric identifiers compare as numbers and before alphanumeric ones
+                    let key = |id: &String| match id.parse::<u64>() {
+                        Ok(n) => (0, n, String::new()),
+                        Err(_) => (1, 0, id.clone()),
+                    };
+                    self.pre.iter().map(key).cmp(other.pre.iter().map(key))
+                }
+            }
+        })
+    }
+}
+
+impl PartialOrd for Version {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct Release {
+    tag_name: String,
+    assets: Vec<Asset>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Asset {
+    name: String,
+    browser_download_url: String,
+}
+
+/// Check for a newer release and replace the running binary with it
+pub fn run(channel: Channel, check_only: bool, force: bool) -> Result<()> {
+    let release = fetch_release(channel)?;
+    let current = env!("CARGO_PKG_VERSION");
+    let latest = release.tag_name.trim_start_matches('v');
+
+    // Nightly builds share one tag, so they are always considered newer
+    if channel == Channel::Stable && !force {
+        let parse = |version| {
+            Version::parse(version).with_context(|| format!("Unexpected version \"{}\"", version))
+        };
+        if parse(latest)? <= parse(current)? {
+            println!(
+                "Already up to date ({}, latest release {})",
+                current, latest
+            );
+            return Ok(());
+        }
+    }
+
+    println!("Current: {}  Available: {}", current, release.tag_name);
+    if check_only {
+        return Ok(());
+    }
+
+    let asset_name = asset_name();
+    let asset = find_asset(&release, &asset_name)?;
+    let checksums = find_asset(&release, CHECKSUMS_ASSET)?;
+
+    let exe = std::env::current_exe().context("Failed to locate current executable")?;
+    let tmp = exe.with_extension("download");
+
+    let install = || -> Result<()> {
+        download(&asset.browser_download_url, &tmp)?;
+        let checksums = download_text(&checksums.browser_download_url)?;
+        verify_checksum(&tmp, &asset_name, &checksums)?;
+        replace_binary(&tmp, &exe)
+    };
+    // Don't leave a partial or unverified download next to the binary
+    if let Err(e) = install() {
+        let _ = std::fs::remove_file(&tmp);
+        return Err(e);
+    }
+    println!(
+        "{} Updated to {}",
+        crate::output::glyphs().check,
+        release.tag_name
+    );
+    Ok(())
+}
+
+fn fetch_release(channel: Channel) -> Result<Release> {
+    let url = match channel {
+        Channel::Stable => format!("https://api.github.com/repos/{REPO}/releases/latest"),
+        Channel::Nightly => {
+            format!("https://api.github.com/repos/{REPO}/releases/tags/{NIGHTLY_TAG}")
+        }
+    };
+    let body = download_text(&url)?;
+    serde_json::from_str(&body).context("Failed to parse release information")
+}
+
+/// Release asset name for this platform, e.g. `lsof-work-ports-aarch64-macos`
+fn asset_name() -> String {
+    let ext = if cfg!(windows) { ".exe" } else { "" };
+    format!(
+        "lsof-work-ports-{}-{}{}",
+        std::env::consts::ARCH,
+        std::env::consts::OS,
+        ext
+    )
+}
+
+fn find_asset<'a>(release: &'a Release, name: &str) -> Result<&'a Asset> {
+    release
+        .assets
+        .iter()
+        .find(|a| a.name == name)
+        .with_context(|| format!("Release {} has no asset named {}", release.tag_name, name))
+}
+
+fn download(url: &str, dest: &Path) -> Result<()> {
+    let status = Command::new("curl")
+        .args(["-fsSL", "-o"])
+        .arg(dest)
+        .arg(url)
+        .status()
+        .context("Failed to execute curl command")?;
+    anyhow::ensure!(status.success(), "Download failed: {}", url);
+    Ok(())
+}
+
+fn download_text(url: &str) -> Result<String> {
+    let output = Command::new("curl")
+        .args(["-fsSL", "-H", "Accept: application/vnd.github+json", url])
+        .output()
+        .context("Failed to execute curl command")?;
+    anyhow::ensure!(output.status.success(), "Download failed: {}", url);
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Compare the file's SHA-256 against its entry in a `SHA256SUMS` listing
+fn verify_checksum(file: &Path, asset_name: &str, checksums: &str) -> Result<()> {
+    let expected = checksums
+        .lines()
+        .filter_map(|line| line.split_once(char::is_whitespace))
+        .find(|(_, name)| name.trim().trim_start_matches('*') == asset_name)
+        .map(|(hash, _)| hash.to_lowercase())
+        .with_context(|| format!("No checksum listed for {}", asset_name))?;
+
+    let actual = sha256(file)?;
+    anyhow::ensure!(
+        actual == expected,
+        "Checksum mismatch for {} (expected {}, got {})",
+        asset_name,
+        expected,
+        actual
+    );
+    Ok(())
+}
+
+fn sha256(file: &Path) -> Result<String> {
+    // `sha256sum` on Linux, `shasum` on macOS
+    let output = Command::new("sha256sum")
+        .arg(file)
+        .output()
+        .or_else(|_| {
+            Command::new("shasum")
+                .args(["-a", "256"])
+                .arg(file)
+                .output()
+        })
+        .context("Failed to execute sha256sum or shasum")?;
+    anyhow::ensure!(output.status.success(), "Failed to hash {}", file.display());
+
+    String::from_utf8_lossy(&output.stdout)
+        .split_whitespace()
+        .next()
+        .map(|s| s.to_lowercase())
+        .context("Unexpected checksum output")
+}
+
+/// Atomically swap the downloaded binary into place
+fn replace_binary(new: &Path, exe: &Path) -> Result<()> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(new, std::fs::Permissions::from_mode(0o755))?;
+    }
+
+    // Windows cannot overwrite a running executable, but it can rename it
+    #[cfg(windows)]
+    {
+        let old = exe.with_extension("old");
+        let _ = std::fs::remove_file(&old);
+        std::fs::rename(exe, &old).context("Failed to move current executable aside")?;
+    }
+
+    std::fs::rename(new, exe).with_context(|| format!("Failed to replace {}", exe.display()))
+}
@@ -0,0 +1,67 @@
+//! iOS Simulator attribution: processes of simulated apps run from
+//! `~/Library/Developer/CoreSimulator/Devices/<UDID>/.../<App>.app/<App>`
+
+use std::collections::HashMap;
+use std::process::Command;
+use std::sync::OnceLock;
+
+const DEVICES_DIR: &str = "/CoreSimulator/Devices/";
+
+/// Simulator names by UDID, from `xcrun simctl list devices -j` (looked up once)
+fn device_names() -> &'static HashMap<String, String> {
+    static NAMES: OnceLock<HashMap<String, String>> = OnceLock::new();
+    NAMES.get_or_init(|| {
+        let Ok(output) = Command::new("xcrun")
+            .args(["simctl", "list", "devices", "-j"])
+            .output()
+        else {
+            return HashMap::new();
+        };
+        let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap_or_default();
+        json["devices"]
+            .as_object()
+            .into_iter()
+            .flat_map(|runtimes| runtimes.values())
+            .filter_map(|devices| devices.as_array())
+            .flatten()
+            .filter_map(|device| {
+                Some((
+                    device["udid"].as_str()?.to_string(),
+                    device["name"].as_str()?.to_string(),
+                ))
+            })
+            .collect()
+    })
+}
+
+/// `CFBundleIdentifier` of an app bundle
+fn bundle_id(app_path: &str) -> Option<String> {
+    let output = Command::new("plutil")
+        .args(["-extract", "CFBundleIdentifier", "raw", "-o", "-"])
+        .arg(format!("{}/Info.plist", app_path))
+        .output()
+        .ok()?;
+    let id = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    (output.status.success() && !id.is_empty()).then_some(id)
+}
+
+/// Label for a process running inside a simulator, e.g. `iPhone 15 (sim) com.example.app`
+pub fn describe(command: &str) -> Option<String> {
+    if !cfg!(target_os = "macos") {
+        return None;
+    }
+    let (_, rest) = command.split_once(DEVICES_DIR)?;
+    let udid = rest.split('/').next()?;
+    let device = device_names()
+        .get(udid)
+        .cloned()
+        .unwrap_or_else(|| format!("simulator {}", &udid[..udid.len().min(8)]));
+
+    // Path of the .app bundle the executable lives in (the command starts with it;
+    // app names may contain spaces)
+    let app = command.find(".app/").map(|i| &command[..i + ".app".len()]);
+    match app.and_then(bundle_id) {
+        Some(id) => Some(format!("{} (sim) {}", device, id)),
+        None => Some(format!("{} (sim)", device)),
+    }
+}
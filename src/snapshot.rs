@@ -0,0 +1,55 @@
+//! Incremental snapshots for polling loops (`daemon`, `--watch`)
+//!
+//! Consecutive snapshots mostly contain the same sockets. Listeners are keyed by pid,
+//! port, address and socket identity; only new ones are enriched (one `ps` call for
+//! their pids), the others are carried over from the previous snapshot.
+
+use crate::PortInfo;
+use anyhow::Result;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// (pid, port, address, socket)
+type Key = (Arc<str>, u16, String, String);
+
+#[derive(Default)]
+pub struct Snapshots {
+    previous: HashMap<Key, PortInfo>,
+}
+
+impl Snapshots {
+    pub fn next(&mut self) -> Result<Vec<PortInfo>> {
+        let listeners = crate::collector::Backend::detect().listeners()?;
+
+        // Cached entries in listener order; the gaps are filled from the new ones
+        let mut slots: Vec<(Key, Option<PortInfo>)> = Vec::new();
+        let mut new = Vec::new();
+        for listener in listeners {
+            let key = (
+                listener.pid.clone(),
+                listener.port,
+                listener.address.clone(),
+                listener.socket.clone(),
+            );
+            let cached = self.previous.remove(&key);
+            if cached.is_none() {
+                new.push(listener);
+            }
+            slots.push((key, cached));
+        }
+
+        // What is left closed since the last snapshot
+        self.previous.clear();
+
+        let mut enriched = crate::enrich(new).into_iter();
+        let infos: Vec<PortInfo> = slots
+            .into_iter()
+            .filter_map(|(key, cached)| {
+                let info = cached.or_else(|| enriched.next())?;
+                self.previous.insert(key, info.clone());
+                Some(info)
+            })
+            .collect();
+        Ok(infos)
+    }
+}
@@ -0,0 +1,171 @@
+//! `stats`: aggregates over the recorded history
+
+use crate::history::{self, SessionRecord, StartupRecord};
+use crate::theme::Themed;
+use anyhow::Result;
+use colored::Colorize;
+
+/// A startup slower than this multiple of the median is flagged as a regression
+const REGRESSION_FACTOR: f64 = 1.5;
+
+fn format_ms(ms: u64) -> String {
+    if ms >= 1000 {
+        format!("{:.1}s", ms as f64 / 1000.0)
+    } else {
+        format!("{}ms", ms)
+    }
+}
+
+/// Per service: how long it takes from spawn to bound port
+pub fn run_startup() -> Result<()> {
+    let records: Vec<StartupRecord> = history::read(history::STARTUP)?;
+    if records.is_empty() {
+        println!(
+            "{}",
+            "No startup times recorded yet; they are measured for commands run with `hold` or `up`"
+                .warning()
+        );
+        return Ok(());
+    }
+
+    let mut names: Vec<&str> = records.iter().map(|r| r.name.as_str()).collect();
+    names.sort_unstable();
+    names.dedup();
+
+    for name in names {
+        let runs: Vec<&StartupRecord> = records.iter().filter(|r| r.name == name).collect();
+        let mut times: Vec<u64> = runs.iter().map(|r| r.startup_ms).collect();
+        times.sort_unstable();
+        let median = times[times.len() / 2];
+        let last = runs.last().map_or(0, |r| r.startup_ms);
+
+        let regression = if runs.len() > 2 && last as f64 > median as f64 * REGRESSION_FACTOR {
+            format!(
+                "  {} last startup {:.1}x the median",
+                crate::output::glyphs().warn,
+                last as f64 / median.max(1) as f64
+            )
+            .warning()
+            .to_string()
+        } else {
+            String::new()
+        };
+        println!(
+            "{} {}  last {}  median {}  min {}  max {}  {}{}",
+            name.success(),
+            format!(":{}", runs.last().map_or(0, |r| r.port))
+                .accent()
+                .bold(),
+            format_ms(last).bold(),
+            format_ms(median),
+            format_ms(times[0]),
+            format_ms(times[times.len() - 1]),
+            format!("({} runs)", runs.len()).muted(),
+            regression
+        );
+    }
+    Ok(())
+}
+
+const HOUR: u64 = 3600;
+const DAY: u64 = 24 * HOUR;
+const WEEKDAYS: [&str; 7] = ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"];
+
+/// Offset of local time from UTC in seconds, from `date +%z` (e.g. `+0900`)
+fn utc_offset() -> i64 {
+    let output = std::process::Command::new("date").arg("+%z").output();
+    let Ok(output) = output else {
+        return 0;
+    };
+    let zone = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    let (sign, digits) = match zone.strip_prefix('-') {
+        Some(digits) => (-1, digits),
+        None => (1, zone.trim_start_matches('+')),
+    };
+    if digits.len() != 4 {
+        return 0;
+    }
+    let hours: i64 = digits[..2].parse().unwrap_or(0);
+    let minutes: i64 = digits[2..].parse().unwrap_or(0);
+    sign * (hours * 3600 + minutes * 60)
+}
+
+/// Hour-by-weekday map of how long the monitored ports were occupied
+pub fn run_heatmap(weeks: u64) -> Result<()> {
+    anyhow::ensure!(weeks > 0, "--weeks must be at least 1");
+    let config = crate::Config::load()?;
+    let monitored = config.monitored_ports()?;
+    let sessions: Vec<SessionRecord> = history::read(history::SESSIONS)?;
+    let sessions: Vec<&SessionRecord> = sessions
+        .iter()
+        .filter(|s| monitored.is_empty() || monitored.contains(&s.port))
+        .collect();
+    let until = history::now();
+    let since = until.saturating_sub(weeks * 7 * DAY);
+    let sessions: Vec<&SessionRecord> = sessions.into_iter().filter(|s| s.end > since).collect();
+    if sessions.is_empty() {
+        println!(
+            "{}",
+            "No port sessions recorded yet; they are recorded while `daemon` runs".warning()
+        );
+        return Ok(());
+    }
+
+    let offset = utc_offset();
+    // Occupied port-seconds per weekday (Monday first) and local hour
+    let mut grid = [[0u64; 24]; 7];
+    for session in &sessions {
+        let mut t = session.start.max(since);
+        let end = session.end.min(until);
+        while t < end {
+            let local = t.saturating_add_signed(offset);
+            let next = (t / HOUR + 1) * HOUR;
+            // 1970-01-01 was a Thursday
+            let weekday = ((local / DAY + 3) % 7) as usize;
+            let hour = ((local % DAY) / HOUR) as usize;
+            grid[weekday][hour] += next.min(end) - t;
+            t = next;
+        }
+    }
+
+    let max = grid.iter().flatten().copied().max().unwrap_or(0).max(1);
+    let shades = crate::output::glyphs().shades;
+    println!(
+        "{}",
+        format!(
+            "Monitored port occupancy, last {} week(s) ({} sessions)",
+            weeks,
+            sessions.len()
+        )
+        .muted()
+    );
+    let hours: String = (0..24).step_by(3).map(|h| format!("{:<6}", h)).collect();
+    println!("    {}", hours.trim_end().muted());
+    for (weekday, row) in grid.iter().enumerate() {
+        let cells: String = row
+            .iter()
+            .map(|&seconds| {
+                // Any occupancy gets at least the lightest shade
+                let level = if seconds == 0 {
+                    0
+                } else {
+                    1 + (seconds * 3 / max) as usize
+                };
+                shades[level.min(4)].repeat(2)
+            })
+            .collect();
+        println!("{} {}", WEEKDAYS[weekday].muted(), cells.accent());
+    }
+    println!(
+        "    {}",
+        format!(
+            "{} none  {} some  {} most  (darkest cell: {:.1}h of port time)",
+            shades[0].repeat(2),
+            shades[1].repeat(2),
+            shades[4].repeat(2),
+            max as f64 / HOUR as f64
+        )
+        .muted()
+    );
+    Ok(())
+}
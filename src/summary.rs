@@ -0,0 +1,260 @@
+//! `--stats`: a header with socket counts beyond the listening ports shown
+
+use crate::collector::Backend;
+use crate::theme::Themed;
+use anyhow::Result;
+use colored::Colorize;
+use std::collections::BTreeMap;
+use std::process::Command;
+
+#[derive(Debug, Default)]
+pub struct SocketStats {
+    /// TCP sockets in LISTEN state
+    pub tcp_listen: usize,
+    /// Bound UDP sockets (UDP has no listen state)
+    pub udp: usize,
+    /// TCP connections in ESTABLISHED state
+    pub established: usize,
+    /// Listening sockets per user
+    pub per_user: BTreeMap<String, usize>,
+}
+
+/// What a socket counts as in the stats
+enum SocketKind {
+    Listen,
+    Established,
+    Udp,
+}
+
+impl SocketStats {
+    /// Count every internet socket the active backend reports
+    pub fn collect() -> Result<Self> {
+        let backend = Backend::detect();
+        let sockets = match backend {
+            Backend::Lsof => {
+                let mut command = crate::collector::lsof();
+                command.args(["-i", "-P", "-n"]);
+                classify(&backend.run(command)?, lsof_socket)
+            }
+            Backend::Sockstat => {
+                // Without -l, so connected sockets are listed too
+                let mut command = Command::new("sockstat");
+                command.args(["-4", "-6", "-P", "tcp,udp"]);
+                classify(&backend.run(command)?, sockstat_socket)
+            }
+            Backend::Fstat => classify(&backend.run(Command::new("fstat"))?, fstat_socket),
+            Backend::Proc => proc_sockets()?,
+        };
+
+        let mut stats = Self::default();
+        for (user, kind) in sockets {
+            match kind {
+                SocketKind::Listen => stats.tcp_listen += 1,
+                SocketKind::Udp => stats.udp += 1,
+                SocketKind::Established => {
+                    stats.established += 1;
+                    continue;
+                }
+            }
+            *stats.per_user.entry(user).or_default() += 1;
+        }
+        Ok(stats)
+    }
+}
+
+fn classify(
+    stdout: &str,
+    socket: impl Fn(&[&str], &str) -> Option<(String, SocketKind)>,
+) -> Vec<(String, SocketKind)> {
+    stdout
+        .lines()
+        .skip(1)
+        .filter_map(|line| socket(&line.split_whitespace().collect::<Vec<_>>(), line))
+        .collect()
+}
+
+/// `node 1234 me 23u IPv4 0x... 0t0 TCP 127.0.0.1:3000 (LISTEN)`
+fn lsof_socket(parts: &[&str], line: &str) -> Option<(String, SocketKind)> {
+    if parts.len() < 9 {
+        return None;
+    }
+    let kind = match parts[7] {
+        "TCP" if line.ends_with("(LISTEN)") => SocketKind::Listen,
+        "TCP" if line.ends_with("(ESTABLISHED)") => SocketKind::Established,
+        "UDP" => SocketKind::Udp,
+        _ => return None,
+    };
+    Some((parts[2].to_string(), kind))
+}
+
+/// `www nginx 900 6 tcp4 *:80 *:*`; listening sockets have no foreign address
+fn sockstat_socket(parts: &[&str], _line: &str) -> Option<(String, SocketKind)> {
+    let (user, proto, foreign) = (parts.first()?, parts.get(4)?, parts.get(6)?);
+    let kind = if proto.starts_with("udp") {
+        SocketKind::Udp
+    } else if !proto.starts_with("tcp") {
+        return None;
+    } else if *foreign == "*:*" {
+        SocketKind::Listen
+    } else {
+        SocketKind::Established
+    };
+    Some((user.to_string(), kind))
+}
+
+/// `root sshd 6570 3* internet stream tcp 0x... *:22`; connected sockets continue
+/// with `<--`/`-->` and the peer address
+fn fstat_socket(parts: &[&str], line: &str) -> Option<(String, SocketKind)> {
+    if !parts.get(4).is_some_and(|t| t.starts_with("internet")) {
+        return None;
+    }
+    let connected = line.contains("<--") || line.contains("-->");
+    let kind = match (parts.get(6), connected) {
+        (Some(&"udp"), _) => SocketKind::Udp,
+        (Some(&"tcp"), false) => SocketKind::Listen,
+        (Some(&"tcp"), true) => SocketKind::Established,
+        _ => return None,
+    };
+    Some((parts[0].to_string(), kind))
+}
+
+/// `/proc/net/{tcp,udp}[6]`: the state is the 4th column and the owner's uid the 8th
+fn proc_sockets() -> Result<Vec<(String, SocketKind)>> {
+    /// `st` values of TCP sockets
+    const LISTEN: &str = "0A";
+    const ESTABLISHED: &str = "01";
+    let users = crate::procfs::user_names();
+    let rows = |tables: &str| -> Vec<(String, String)> {
+        tables
+            .lines()
+            .filter_map(|line| {
+                let parts: Vec<&str> = line.split_whitespace().collect();
+                let (state, uid) = (parts.get(3)?, parts.get(7)?);
+                // Header lines start with `sl`
+                (parts[0] != "sl").then(|| {
+                    let user = users.get(*uid).cloned().unwrap_or_else(|| uid.to_string());
+                    (state.to_string(), user)
+                })
+            })
+            .collect()
+    };
+    let tcp = rows(&crate::procfs::read_tables(&["net/tcp", "net/tcp6"])?);
+    let udp = rows(&crate::procfs::read_tables(&["net/udp", "net/udp6"])?);
+    Ok(tcp
+        .into_iter()
+        .filter_map(|(state, user)| match state.as_str() {
+            LISTEN => Some((user, SocketKind::Listen)),
+            ESTABLISHED => Some((user, SocketKind::Established)),
+            _ => None,
+        })
+        .chain(udp.into_iter().map(|(_, user)| (user, SocketKind::Udp)))
+        .collect())
+}
+
+/// Listening ports left out of the listing, by reason
+#[derive(Debug, Default)]
+pub struct HiddenCounts {
+    /// Didn't match `--port`/`--process`/`--interface`
+    pub filtered: usize,
+    /// Not a dev process (shown with `--all`)
+    pub not_dev: usize,
+    /// Cut off by `--limit`
+    pub over_limit: usize,
+    /// Sockets whose owner isn't visible (shown with `--all` or `--port`)
+    pub permission_denied: usize,
+}
+
+impl HiddenCounts {
+    pub fn total(&self) -> usize {
+        self.filtered + self.not_dev + self.over_limit + self.permission_denied
+    }
+
+    /// Trailing note, e.g. `42 port(s) hidden: 40 not dev processes (use --all), 2 filtered out`
+    pub fn print_note(&self) {
+        if self.total() == 0 {
+            return;
+        }
+        let reasons: Vec<String> = [
+            (self.not_dev, "not dev processes (use --all)"),
+            (self.filtered, "filtered out by -p/-n/-i"),
+            (self.over_limit, "over --limit"),
+            (self.permission_denied, "without visible owner (use --all)"),
+        ]
+        .iter()
+        .filter(|(count, _)| *count > 0)
+        .map(|(count, reason)| format!("{} {}", count, reason))
+        .collect();
+        println!();
+        println!(
+            "{}",
+            format!("{} port(s) hidden: {}", self.total(), reasons.join(", ")).muted()
+        );
+    }
+}
+
+/// Print the stats header; `hidden` is the number of listening ports not shown
+pub fn print(stats: &SocketStats, shown: usize, hidden: usize) {
+    let users = stats
+        .per_user
+        .iter()
+        .map(|(user, count)| format!("{} {}", user, count))
+        .collect::<Vec<_>>()
+        .join(", ");
+    println!(
+        "\n{} port(s) shown {} {} listening (TCP {}, UDP {}) {} {} established",
+        shown.to_string().bold(),
+        crate::output::glyphs().sep,
+        stats.tcp_listen + stats.udp,
+        stats.tcp_listen,
+        stats.udp,
+        crate::output::glyphs().sep,
+        stats.established
+    );
+    println!(
+        "{}",
+        format!(
+            "by user: {} {} {} hidden by filters",
+            users,
+            crate::output::glyphs().sep,
+            hidden
+        )
+        .muted()
+    );
+    println!();
+}
+
+/// One line per `[[ports]]` range: `frontend 3000-3010: 2/11 in use: 3000, 3002`
+pub fn print_range_utilization(
+    config: &crate::Config,
+    port_infos: &[crate::PortInfo],
+) -> Result<()> {
+    anyhow::ensure!(
+        !config.port_entries().is_empty(),
+        "No port ranges configured; add [[ports]] entries to the config"
+    );
+    for entry in config.port_entries() {
+        let ports = entry.expand()?;
+        let in_use: Vec<String> = ports
+            .iter()
+            .filter(|p| port_infos.iter().any(|i| i.port == **p))
+            .map(|p| p.to_string())
+            .collect();
+        let usage = format!("{}/{} in use", in_use.len(), ports.len());
+        let usage = if in_use.is_empty() {
+            usage.muted()
+        } else {
+            usage.normal()
+        };
+        let mut line = format!(
+            "{} {}: {}",
+            entry.name.as_deref().unwrap_or("(unnamed)").bold(),
+            entry.ports.accent(),
+            usage
+        );
+        if !in_use.is_empty() {
+            line.push_str(&format!(": {}", in_use.join(", ").accent().bold()));
+        }
+        println!("{}", line);
+    }
+    Ok(())
+}
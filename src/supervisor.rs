@@ -0,0 +1,300 @@
+//! Background supervision of long-running commands (SSH tunnels, held servers)
+//!
+//! Each supervised command is recorded in `supervised.json` in the state dir and
+//! run by a detached copy of this binary (`__supervise <name>`), which restarts
+//! the command when it exits. The supervisor leads its own process group, so
+//! stopping an entry signals the group and takes the command down with it.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+
+/// Longest delay between restarts of a crashing command
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+
+/// A command that ran this long is considered healthy; its backoff is reset
+const HEALTHY_RUNTIME: Duration = Duration::from_secs(60);
+
+/// Give up measuring the startup time of a command that hasn't bound a port by then
+const STARTUP_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// How often to look for the command's listening port while it starts
+const STARTUP_POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Entry {
+    /// Unique name, e.g. `tunnel-5433`
+    pub name: String,
+    /// What kind of entry this is (`tunnel`, `hold`, `service`)
+    pub kind: String,
+    /// Port the command is expected to bind
+    pub port: Option<u16>,
+    /// Program and arguments
+    pub command: Vec<String>,
+    /// Extra environment variables (e.g. `PORT`)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub env: Vec<(String, String)>,
+    /// Restart the command when it exits
+    pub restart: bool,
+    /// PID of the supervisor process (also its process group)
+    #[serde(default)]
+    pub pid: Option<u32>,
+    /// PID of the currently running command
+    #[serde(default)]
+    pub child_pid: Option<u32>,
+    /// Number of restarts so far
+    #[serde(default)]
+    pub restarts: u32,
+}
+
+impl Entry {
+    pub fn new(name: String, kind: &str, port: Option<u16>, command: Vec<String>) -> Self {
+        Self {
+            name,
+            kind: kind.to_string(),
+            port,
+            command,
+            env: Vec::new(),
+            restart: true,
+            pid: None,
+            child_pid: None,
+            restarts: 0,
+        }
+    }
+
+    /// Whether the supervisor process is still alive
+    pub fn is_running(&self) -> bool {
+        self.pid.is_some_and(|pid| {
+            Command::new("kill")
+                .args(["-0", &pid.to_string()])
+                .stderr(Stdio::null())
+                .status()
+                .is_ok_and(|s| s.success())
+        })
+    }
+}
+
+fn registry_path() -> Result<std::path::PathBuf> {
+    Ok(crate::paths::state_dir()?.join("supervised.json"))
+}
+
+/// Path of the log file collecting an entry's output
+pub fn log_path(name: &str) -> Result<std::path::PathBuf> {
+    Ok(crate::paths::state_dir()?
+        .join("logs")
+        .join(format!("{}.log", name)))
+}
+
+/// All registered entries
+pub fn load() -> Result<Vec<Entry>> {
+    let path = registry_path()?;
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let content = std::fs::read_to_string(&path).context("Failed to read supervisor registry")?;
+    serde_json::from_str(&content).context("Failed to parse supervisor registry")
+}
+
+/// Load, modify and save the registry while holding its lock
+fn update<T>(f: impl FnOnce(&mut Vec<Entry>) -> Result<T>) -> Result<T> {
+    let path = registry_path()?;
+    std::fs::create_dir_all(path.parent().context("Invalid state dir")?)?;
+    let _lock = crate::lock_file(&path.with_extension("json.lock"))?;
+
+    let mut entries = load()?;
+    let result = f(&mut entries)?;
+    let content = serde_json::to_string_pretty(&entries)?;
+    crate::write_atomic(&path, content.as_bytes())?;
+    Ok(result)
+}
+
+/// Register an entry and start its detached supervisor
+pub fn start(entry: Entry) -> Result<Entry> {
+    let name = entry.name.clone();
+    update(|entries| {
+        if let Some(existing) = entries.iter().find(|e| e.name == name) {
+            anyhow::ensure!(
+                !existing.is_running(),
+                "{} is already running (supervisor PID {})",
+                name,
+                existing.pid.unwrap_or_default()
+            );
+        }
+        entries.retain(|e| e.name != name);
+        entries.push(entry);
+        Ok(())
+    })?;
+
+    let exe = std::env::current_exe().context("Failed to locate current executable")?;
+    let mut command = Command::new(exe);
+    command
+        .args(["__supervise", &name])
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null());
+    // The supervisor must read the registry this process wrote to
+    if let Some(root) = crate::paths::root() {
+        command.env("LWP_ROOT", root);
+    }
+
+    // Own process group: survives the terminal's Ctrl-C and can be stopped as a unit
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::CommandExt;
+        command.process_group(0);
+    }
+
+    let child = command.spawn().context("Failed to start supervisor")?;
+    update(|entries| {
+        let entry = entries
+            .iter_mut()
+            .find(|e| e.name == name)
+            .context("Supervisor entry disappeared")?;
+        entry.pid = Some(child.id());
+        Ok(entry.clone())
+    })
+}
+
+/// Unregister an entry and terminate its supervisor and command
+pub fn stop(name: &str) -> Result<Entry> {
+    let entry = update(|entries| {
+        let index = entries
+            .iter()
+            .position(|e| e.name == name)
+            .with_context(|| format!("{} is not running", name))?;
+        Ok(entries.remove(index))
+    })?;
+
+    if let Some(pid) = entry.pid.filter(|_| entry.is_running()) {
+        let _ = Command::new("kill")
+            .args(["-TERM", "--", &format!("-{}", pid)])
+            .status();
+    }
+    Ok(entry)
+}
+
+/// Supervisor main loop (runs in the detached `__supervise` process)
+pub fn supervise(name: &str) -> Result<()> {
+    use std::io::Write;
+
+    let log_path = log_path(name)?;
+    std::fs::create_dir_all(log_path.parent().context("Invalid log path")?)?;
+    let mut log = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&log_path)
+        .context("Failed to open supervisor log")?;
+
+    let mut backoff = Duration::from_secs(1);
+    loop {
+        // Stop once the entry has been removed (`tunnel rm`, `hold --stop`)
+        let Some(entry) = load()?.into_iter().find(|e| e.name == name) else {
+            return Ok(());
+        };
+        let (program, args) = entry
+            .command
+            .split_first()
+            .context("Supervised command is empty")?;
+
+        writeln!(log, "[supervisor] starting: {}", entry.command.join(" "))?;
+        let started = Instant::now();
+        let mut child = Command::new(program)
+            .args(args)
+            .envs(entry.env.iter().map(|(k, v)| (k, v)))
+            .stdin(Stdio::null())
+            .stdout(log.try_clone()?)
+            .stderr(log.try_clone()?)
+            .spawn()
+            .with_context(|| format!("Failed to start {}", program))?;
+
+        let child_pid = child.id();
+        let current = update(|entries| {
+            let e = entries
+                .iter_mut()
+                .find(|e| e.name == name)
+                .context("Supervisor entry disappeared")?;
+            e.child_pid = Some(child_pid);
+            Ok(e.clone())
+        })?;
+        std::thread::spawn(move || measure_startup(&current, started));
+
+        let status = child.wait()?;
+        if !entry.restart {
+            writeln!(log, "[supervisor] exited with {}", status)?;
+            update(|entries| {
+                entries.retain(|e| e.name != name);
+                Ok(())
+            })?;
+            return Ok(());
+        }
+
+        if started.elapsed() >= HEALTHY_RUNTIME {
+            backoff = Duration::from_secs(1);
+        }
+        writeln!(
+            log,
+            "[supervisor] exited with {}, restarting in {}s",
+            status,
+            backoff.as_secs()
+        )?;
+        std::thread::sleep(backoff);
+        backoff = (backoff * 2).min(MAX_BACKOFF);
+
+        update(|entries| {
+            if let Some(e) = entries.iter_mut().find(|e| e.name == name) {
+                e.restarts += 1;
+                e.child_pid = None;
+            }
+            Ok(())
+        })?;
+    }
+}
+
+/// Poll until the command's process group binds a port and record how long it took
+fn measure_startup(entry: &Entry, started: Instant) {
+    let started_at = crate::history::now();
+    while started.elapsed() < STARTUP_TIMEOUT {
+        std::thread::sleep(STARTUP_POLL_INTERVAL);
+        let pids = group_pids(entry);
+        // Only the supervisor itself is left: the command exited before binding
+        if pids.len() <= 1 {
+            return;
+        }
+        let Ok(port_infos) = crate::get_port_info() else {
+            return;
+        };
+        let bound = port_infos
+            .iter()
+            .filter(|i| pids.iter().any(|p| *p == *i.pid))
+            .find(|i| entry.port.is_none_or(|p| p == i.port));
+        if let Some(info) = bound {
+            let record = crate::history::StartupRecord {
+                name: entry.name.clone(),
+                port: info.port,
+                started_at,
+                startup_ms: started.elapsed().as_millis() as u64,
+            };
+            let _ = crate::history::append(crate::history::STARTUP, &record);
+            return;
+        }
+    }
+}
+
+/// PIDs of every process in the supervisor's process group (the command and its children)
+pub fn group_pids(entry: &Entry) -> Vec<String> {
+    let Some(pgid) = entry.pid else {
+        return Vec::new();
+    };
+    let Ok(output) = Command::new("ps").args(["-A", "-o", "pid=,pgid="]).output() else {
+        return Vec::new();
+    };
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| {
+            let mut parts = line.split_whitespace();
+            let pid = parts.next()?;
+            (parts.next()? == pgid.to_string()).then(|| pid.to_string())
+        })
+        .collect()
+}
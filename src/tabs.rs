@@ -0,0 +1,101 @@
+//! `tabs`: localhost tabs open in Chrome/Edge, matched with the listening ports
+//!
+//! Reads the DevTools HTTP endpoint (`/json/list`), which is only available when the
+//! browser was started with `--remote-debugging-port`.
+
+use crate::theme::Themed;
+use anyhow::{Context, Result};
+use colored::Colorize;
+use serde::Deserialize;
+use std::process::Command;
+
+#[derive(Debug, Deserialize)]
+struct Target {
+    #[serde(rename = "type")]
+    kind: String,
+    #[serde(default)]
+    title: String,
+    url: String,
+}
+
+/// Port of a URL on this machine (`http://localhost:3000/...`), if it is one
+fn local_port(url: &str) -> Option<u16> {
+    let (scheme, rest) = url.split_once("://")?;
+    let authority = rest.split(['/', '?', '#']).next()?;
+    let (host, port) = match authority.rsplit_once(':') {
+        Some((host, port)) if !port.contains(']') => (host, port.parse().ok()?),
+        _ => (
+            authority,
+            match scheme {
+                "http" | "ws" => 80,
+                "https" | "wss" => 443,
+                _ => return None,
+            },
+        ),
+    };
+    let local = matches!(host, "localhost" | "127.0.0.1" | "[::1]" | "0.0.0.0")
+        || host.ends_with(".localhost");
+    local.then_some(port)
+}
+
+fn targets(devtools_port: u16) -> Result<Vec<Target>> {
+    let url = format!("http://127.0.0.1:{}/json/list", devtools_port);
+    let output = Command::new("curl")
+        .args(["-s", "--max-time", "2", &url])
+        .output()
+        .context("Failed to run curl")?;
+    anyhow::ensure!(
+        output.status.success() && !output.stdout.is_empty(),
+        "No DevTools endpoint on port {} (start Chrome/Edge with --remote-debugging-port={})",
+        devtools_port,
+        devtools_port
+    );
+    serde_json::from_slice(&output.stdout).context("Unexpected DevTools /json/list response")
+}
+
+pub fn run(devtools_port: u16) -> Result<()> {
+    let tabs: Vec<(u16, Target)> = targets(devtools_port)?
+        .into_iter()
+        .filter(|t| t.kind == "page")
+        .filter_map(|t| Some((local_port(&t.url)?, t)))
+        .collect();
+    if tabs.is_empty() {
+        println!("No open tabs point at localhost");
+        return Ok(());
+    }
+
+    let port_infos = crate::get_port_info()?;
+    let glyphs = crate::output::glyphs();
+    let mut dead = 0;
+    for (port, tab) in &tabs {
+        let server = match port_infos.iter().find(|i| i.port == *port) {
+            Some(info) => format!(
+                "{} {} {}",
+                glyphs.arrow,
+                info.process.success(),
+                crate::format_pid(&info.pid).muted()
+            ),
+            None => {
+                dead += 1;
+                format!("{} nothing is listening", glyphs.warn)
+                    .warning()
+                    .to_string()
+            }
+        };
+        println!(
+            "{} {:<40}  {}",
+            format!(":{:<5}", port).accent().bold(),
+            crate::truncate_chars(&tab.title, 40),
+            server
+        );
+        println!("       {}", tab.url.muted());
+    }
+    if dead > 0 {
+        println!();
+        println!(
+            "{}",
+            format!("{} tab(s) point at ports nothing is listening on", dead).warning()
+        );
+    }
+    Ok(())
+}
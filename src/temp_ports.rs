@@ -0,0 +1,112 @@
+//! `--monitor-temp`: ports monitored for the current shell session only, kept in
+//! `$XDG_STATE_HOME/lsof-work-ports/temp-ports.json` instead of the config
+//!
+//! The session is `LWP_SESSION` when set (e.g. one token per project), otherwise the
+//! shell that runs us. Entries expire after a day, and entries of shells that exited
+//! are dropped whenever the file is updated.
+
+use crate::theme::Themed;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+/// How long temporary ports stay monitored
+const TTL_SECS: u64 = 24 * 3600;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct TempPorts {
+    /// Port spec as in `[[ports]]`, e.g. `4000-4010`
+    ports: String,
+    session: String,
+    /// Shell the ports belong to, unless the session came from `LWP_SESSION`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    shell_pid: Option<u32>,
+    /// Unix seconds
+    expires: u64,
+}
+
+fn path() -> Result<std::path::PathBuf> {
+    Ok(crate::paths::state_dir()?.join("temp-ports.json"))
+}
+
+fn load() -> Result<Vec<TempPorts>> {
+    let path = path()?;
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let content = std::fs::read_to_string(&path).context("Failed to read temporary ports")?;
+    serde_json::from_str(&content).context("Failed to parse temporary ports")
+}
+
+/// (session, shell pid)
+fn session() -> (String, Option<u32>) {
+    match std::env::var("LWP_SESSION") {
+        Ok(token) if !token.is_empty() => (token, None),
+        _ => {
+            let shell = crate::alloc::caller_pid();
+            (format!("shell-{}", shell), Some(shell))
+        }
+    }
+}
+
+/// Load, modify and save the entries while holding the lock, dropping stale ones
+fn update(f: impl FnOnce(&mut Vec<TempPorts>)) -> Result<()> {
+    let path = path()?;
+    std::fs::create_dir_all(path.parent().context("Invalid state dir")?)?;
+    let _lock = crate::lock_file(&path.with_extension("json.lock"))?;
+
+    let now = crate::history::now();
+    let mut entries: Vec<TempPorts> = load()?.into_iter().filter(|e| e.expires > now).collect();
+    let shells: Vec<u32> = entries.iter().filter_map(|e| e.shell_pid).collect();
+    let alive = crate::leases::alive(&shells);
+    entries.retain(|e| e.shell_pid.is_none_or(|pid| alive.contains(&pid)));
+
+    f(&mut entries);
+    crate::write_atomic(&path, serde_json::to_string_pretty(&entries)?.as_bytes())
+}
+
+/// Monitor `spec` for the rest of this session
+pub fn add(spec: &str) -> Result<()> {
+    anyhow::ensure!(
+        !crate::expand_ports(spec)?.is_empty(),
+        "No ports in \"{}\"",
+        spec
+    );
+    let (session, shell_pid) = session();
+    let expires = crate::history::now() + TTL_SECS;
+    update(|entries| {
+        entries.push(TempPorts {
+            ports: spec.to_string(),
+            session,
+            shell_pid,
+            expires,
+        })
+    })?;
+    // stderr, so it doesn't end up in piped listings
+    eprintln!(
+        "{}",
+        format!("Monitoring {} in this session for 24h", spec).muted()
+    );
+    Ok(())
+}
+
+/// Stop monitoring the temporary ports of this session
+pub fn clear() -> Result<()> {
+    let (session, _) = session();
+    update(|entries| entries.retain(|e| e.session != session))
+}
+
+/// This session's temporary ports as `[[ports]]` entries
+pub fn entries() -> Result<Vec<crate::PortEntry>> {
+    let (session, _) = session();
+    let now = crate::history::now();
+    Ok(load()?
+        .into_iter()
+        .filter(|e| e.session == session && e.expires > now)
+        .map(|e| crate::PortEntry {
+            ports: e.ports,
+            name: Some("temporary".to_string()),
+            http: None,
+            status: None,
+        })
+        .collect())
+}
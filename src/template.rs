@@ -0,0 +1,184 @@
+//! `--format` line templates, e.g. `'{port}\t{process:<20}\t{command:.40}'`
+//!
+//! Fields are replaced per port; `{field:<N}`/`{field:>N}` pads to N columns and
+//! `{field:.N}` truncates to N characters (both combine as `{field:<20.20}`).
+//! `\t`, `\n` and `\\` are unescaped so templates can be passed from a shell as-is,
+//! and `{{`/`}}` print literal braces.
+
+use anyhow::{Context, Result};
+
+/// Fields available in templates
+pub const FIELDS: &[&str] = &[
+    "port",
+    "process",
+    "pid",
+    "pids",
+    "section",
+    "address",
+    "interface",
+    "family",
+    "command",
+    "label",
+    "port_name",
+    "protocol",
+    "state",
+    "peer",
+    "start_time",
+    "cwd",
+    #[cfg(feature = "probe")]
+    "latency",
+    "forwarding",
+];
+
+/// Alignment ('<' or '>') and width
+type Pad = (char, usize);
+
+#[derive(Debug, Clone)]
+enum Part {
+    Literal(String),
+    Field {
+        name: String,
+        pad: Option<Pad>,
+        max: Option<usize>,
+    },
+}
+
+#[derive(Debug, Clone)]
+pub struct Template {
+    parts: Vec<Part>,
+}
+
+fn parse_spec(spec: &str) -> Result<(Option<Pad>, Option<usize>)> {
+    let (pad, max) = match spec.split_once('.') {
+        Some((pad, max)) => (pad, Some(max)),
+        None => (spec, None),
+    };
+    let pad = match pad.chars().next() {
+        None => None,
+        Some(align @ ('<' | '>')) => Some((
+            align,
+            pad[1..]
+                .parse()
+                .with_context(|| format!("Invalid width in '{{:{}}}'", spec))?,
+        )),
+        // A bare width pads on the right, like format!
+        Some(_) => Some((
+            '<',
+            pad.parse()
+                .with_context(|| format!("Invalid width in '{{:{}}}'", spec))?,
+        )),
+    };
+    let max = max
+        .map(|m| m.parse())
+        .transpose()
+        .with_context(|| format!("Invalid truncation in '{{:{}}}'", spec))?;
+    Ok((pad, max))
+}
+
+impl Template {
+    pub fn parse(source: &str) -> Result<Self> {
+        let mut parts = Vec::new();
+        let mut literal = String::new();
+        let mut chars = source.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '\\' => match chars.next() {
+                    Some('t') => literal.push('\t'),
+                    Some('n') => literal.push('\n'),
+                    Some(other) => literal.push(other),
+                    None => literal.push('\\'),
+                },
+                '{' if chars.peek() == Some(&'{') => {
+                    chars.next();
+                    literal.push('{');
+                }
+                '}' if chars.peek() == Some(&'}') => {
+                    chars.next();
+                    literal.push('}');
+                }
+                '{' => {
+                    let field: String = chars.by_ref().take_while(|&c| c != '}').collect();
+                    let (name, spec) = field.split_once(':').unwrap_or((&field, ""));
+                    anyhow::ensure!(
+                        FIELDS.contains(&name),
+                        "Unknown field '{{{}}}' in --format (fields: {})",
+                        name,
+                        FIELDS.join(", ")
+                    );
+                    let (pad, max) = parse_spec(spec)?;
+                    if !literal.is_empty() {
+                        parts.push(Part::Literal(std::mem::take(&mut literal)));
+                    }
+                    parts.push(Part::Field {
+                        name: name.to_string(),
+                        pad,
+                        max,
+                    });
+                }
+                _ => literal.push(c),
+            }
+        }
+        if !literal.is_empty() {
+            parts.push(Part::Literal(literal));
+        }
+        Ok(Self { parts })
+    }
+
+    pub fn uses(&self, field: &str) -> bool {
+        self.parts
+            .iter()
+            .any(|p| matches!(p, Part::Field { name, .. } if name == field))
+    }
+
+    /// One line for a port of the listing; `cwd` is only looked up when the template
+    /// uses `{cwd}` (see [`Template::uses`])
+    pub fn render(&self, info: &crate::GroupedPortInfo, section: &str, cwd: &str) -> String {
+        let pid = info.pids.first().map(|p| &**p).unwrap_or_default();
+
+        let mut line = String::new();
+        for part in &self.parts {
+            let (name, pad, max) = match part {
+                Part::Literal(text) => {
+                    line.push_str(text);
+                    continue;
+                }
+                Part::Field { name, pad, max } => (name.as_str(), pad, max),
+            };
+            let value = match name {
+                "port" => info.port.to_string(),
+                "process" => info.processes.join(","),
+                "pid" => pid.to_string(),
+                "pids" => info.pids.join(","),
+                "section" => section.to_string(),
+                "address" => info.address.clone(),
+                "interface" => info.interface.clone(),
+                "family" => info.family.clone(),
+                "command" => info.command.clone(),
+                "label" => info.label.clone().unwrap_or_default(),
+                "port_name" => info.port_name.clone().unwrap_or_default(),
+                "protocol" => info.protocol.name().to_string(),
+                "state" => info.state.clone(),
+                "peer" => info.peer.clone().unwrap_or_default(),
+                "start_time" => info.start_time.clone(),
+                "cwd" => crate::anonymize::text(cwd),
+                #[cfg(feature = "probe")]
+                "latency" => info.latency.map(|l| l.text()).unwrap_or_default(),
+                "forwarding" => info
+                    .forwarding
+                    .map(|f| f.text().to_string())
+                    .unwrap_or_default(),
+                _ => String::new(),
+            };
+            let mut value = value.replace(['\t', '\n'], " ");
+            if let Some(max) = max {
+                value = value.chars().take(*max).collect();
+            }
+            match pad {
+                Some(('>', width)) => line.push_str(&format!("{:>width$}", value, width = width)),
+                Some((_, width)) => line.push_str(&format!("{:<width$}", value, width = width)),
+                None => line.push_str(&value),
+            }
+        }
+        line
+    }
+}
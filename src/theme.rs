@@ -0,0 +1,232 @@
+//! Color themes (`--theme`, `theme` in the config)
+//!
+//! Output is colored by role rather than by color name: `"3000".accent()` instead of
+//! `"3000".cyan()`, so a theme can swap the green/red pairs that are hard to tell apart
+//! with color vision deficiencies, raise the contrast, or use bold and dim only.
+
+use clap::ValueEnum;
+use colored::{Color, ColoredString, Colorize};
+use serde::{Deserialize, Serialize};
+use std::sync::OnceLock;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ThemeName {
+    /// Cyan ports, green processes, gray details
+    Default,
+    /// Blue/magenta instead of green/red, for red-green color blindness
+    DeuteranopiaSafe,
+    /// Bright colors and no gray text
+    HighContrast,
+    /// No colors; emphasis with bold, dim and underline
+    MonochromeBold,
+}
+
+#[derive(Clone, Copy)]
+struct Style {
+    color: Option<Color>,
+    bold: bool,
+    dimmed: bool,
+    underline: bool,
+    reversed: bool,
+}
+
+impl Style {
+    const fn color(color: Color) -> Self {
+        Self {
+            color: Some(color),
+            bold: false,
+            dimmed: false,
+            underline: false,
+            reversed: false,
+        }
+    }
+
+    const PLAIN: Self = Self {
+        color: None,
+        bold: false,
+        dimmed: false,
+        underline: false,
+        reversed: false,
+    };
+
+    const fn bold(self) -> Self {
+        Self { bold: true, ..self }
+    }
+
+    const fn dimmed(self) -> Self {
+        Self {
+            dimmed: true,
+            ..self
+        }
+    }
+
+    const fn underline(self) -> Self {
+        Self {
+            underline: true,
+            ..self
+        }
+    }
+
+    const fn reversed(self) -> Self {
+        Self {
+            reversed: true,
+            ..self
+        }
+    }
+
+    fn apply(self, text: impl Colorize) -> ColoredString {
+        let mut text = match self.color {
+            Some(color) => text.color(color),
+            None => text.normal(),
+        };
+        if self.bold {
+            text = text.bold();
+        }
+        if self.dimmed {
+            text = text.dimmed();
+        }
+        if self.underline {
+            text = text.underline();
+        }
+        if self.reversed {
+            text = text.reversed();
+        }
+        text
+    }
+}
+
+struct Theme {
+    /// Ports, ranges and URLs
+    accent: Style,
+    /// Process names and healthy states (`up`, checks)
+    success: Style,
+    /// Secondary details: commands, hints, timestamps
+    muted: Style,
+    warning: Style,
+    /// Failed and stopped states
+    error: Style,
+    /// Labels and tags next to a port
+    label: Style,
+    /// Section titles
+    heading: Style,
+    /// Rows matching `--highlight`
+    highlight: Style,
+}
+
+const DEFAULT: Theme = Theme {
+    accent: Style::color(Color::Cyan),
+    success: Style::color(Color::Green),
+    muted: Style::color(Color::BrightBlack),
+    warning: Style::color(Color::Yellow),
+    error: Style::color(Color::Red),
+    label: Style::color(Color::Magenta),
+    heading: Style::color(Color::BrightBlue),
+    highlight: Style::color(Color::Yellow).bold().reversed(),
+};
+
+const DEUTERANOPIA_SAFE: Theme = Theme {
+    accent: Style::color(Color::Cyan),
+    success: Style::color(Color::BrightBlue),
+    muted: Style::color(Color::BrightBlack),
+    warning: Style::color(Color::Yellow),
+    error: Style::color(Color::BrightMagenta).bold(),
+    label: Style::color(Color::White).underline(),
+    heading: Style::color(Color::BrightWhite),
+    highlight: Style::color(Color::Yellow).bold().reversed(),
+};
+
+const HIGH_CONTRAST: Theme = Theme {
+    accent: Style::color(Color::BrightCyan).bold(),
+    success: Style::color(Color::BrightGreen),
+    muted: Style::color(Color::White),
+    warning: Style::color(Color::BrightYellow).bold(),
+    error: Style::color(Color::BrightRed).bold(),
+    label: Style::color(Color::BrightMagenta),
+    heading: Style::color(Color::BrightWhite).bold(),
+    highlight: Style::color(Color::BrightYellow).bold().reversed(),
+};
+
+const MONOCHROME_BOLD: Theme = Theme {
+    accent: Style::PLAIN.bold(),
+    success: Style::PLAIN,
+    muted: Style::PLAIN.dimmed(),
+    warning: Style::PLAIN.bold(),
+    error: Style::PLAIN.bold().underline(),
+    label: Style::PLAIN.underline(),
+    heading: Style::PLAIN.bold().underline(),
+    highlight: Style::PLAIN.bold().reversed(),
+};
+
+impl ThemeName {
+    fn theme(self) -> &'static Theme {
+        match self {
+            ThemeName::Default => &DEFAULT,
+            ThemeName::DeuteranopiaSafe => &DEUTERANOPIA_SAFE,
+            ThemeName::HighContrast => &HIGH_CONTRAST,
+            ThemeName::MonochromeBold => &MONOCHROME_BOLD,
+        }
+    }
+}
+
+static THEME: OnceLock<&'static Theme> = OnceLock::new();
+
+/// Use the theme given on the command line; without one it comes from the config
+pub fn init(name: Option<ThemeName>) {
+    if let Some(name) = name {
+        let _ = THEME.set(name.theme());
+    }
+}
+
+/// Take `theme` from an already loaded config instead of reading it again
+pub fn use_config(config: &crate::Config) {
+    let _ = THEME.set(config.theme.unwrap_or(ThemeName::Default).theme());
+}
+
+fn theme() -> &'static Theme {
+    THEME.get_or_init(|| {
+        // A broken config is reported by the commands that need it
+        crate::Config::load()
+            .ok()
+            .and_then(|c| c.theme)
+            .unwrap_or(ThemeName::Default)
+            .theme()
+    })
+}
+
+/// Role-based coloring for anything `colored` can color
+pub trait Themed: Colorize + Sized {
+    fn accent(self) -> ColoredString {
+        theme().accent.apply(self)
+    }
+
+    fn success(self) -> ColoredString {
+        theme().success.apply(self)
+    }
+
+    fn muted(self) -> ColoredString {
+        theme().muted.apply(self)
+    }
+
+    fn warning(self) -> ColoredString {
+        theme().warning.apply(self)
+    }
+
+    fn error(self) -> ColoredString {
+        theme().error.apply(self)
+    }
+
+    fn label(self) -> ColoredString {
+        theme().label.apply(self)
+    }
+
+    fn heading(self) -> ColoredString {
+        theme().heading.apply(self)
+    }
+
+    fn highlight(self) -> ColoredString {
+        theme().highlight.apply(self)
+    }
+}
+
+impl<T: Colorize> Themed for T {}
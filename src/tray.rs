@@ -0,0 +1,79 @@
+//! `tray`: menu bar output in the xbar/SwiftBar plugin format (also read by Argos on GNOME)
+//!
+//! The plugin runs this command on a timer; the first line is the menu bar title and the
+//! lines after `---` form the dropdown, with `bash=`/`href=` parameters for the actions.
+
+use anyhow::{Context, Result};
+
+pub fn run() -> Result<()> {
+    let config = crate::Config::load()?;
+    let port_infos = crate::get_port_info()?;
+    let exe = std::env::current_exe().context("Failed to locate current executable")?;
+    let exe = exe.display();
+    // Menu bars render emoji well, so they are the default here
+    let glyphs = crate::output::GlyphSet::chosen(&config)
+        .unwrap_or(crate::output::GlyphSet::Emoji)
+        .glyphs();
+
+    // Title: one dot per monitored range, or the listener count without a config
+    let dots: String = config
+        .port_entries()
+        .iter()
+        .map(|entry| {
+            let up = entry
+                .expand()
+                .unwrap_or_default()
+                .iter()
+                .any(|p| port_infos.iter().any(|i| i.port == *p));
+            if up {
+                glyphs.up
+            } else {
+                glyphs.down
+            }
+        })
+        .collect();
+    if dots.is_empty() {
+        println!("⚡{}", port_infos.len());
+    } else {
+        println!("{}", dots);
+    }
+    println!("---");
+
+    for entry in config.port_entries() {
+        let bound: Vec<u16> = entry
+            .expand()
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|p| port_infos.iter().any(|i| i.port == *p))
+            .collect();
+        let dot = if bound.is_empty() {
+            glyphs.down
+        } else {
+            glyphs.up
+        };
+        println!(
+            "{} {} ({})",
+            dot,
+            entry.name.as_deref().unwrap_or(&entry.ports),
+            entry.ports
+        );
+    }
+    if !config.port_entries().is_empty() {
+        println!("---");
+    }
+
+    let mut ports: Vec<(u16, &str)> = port_infos.iter().map(|i| (i.port, &*i.process)).collect();
+    ports.sort_unstable();
+    ports.dedup_by_key(|(port, _)| *port);
+    for (port, process) in ports {
+        println!(":{} {} | font=Menlo", port, process);
+        println!("--Open in browser | href=http://localhost:{}", port);
+        println!(
+            "--Kill | bash={} param1=kill param2={} terminal=false refresh=true",
+            exe, port
+        );
+    }
+    println!("---");
+    println!("Refresh | refresh=true");
+    Ok(())
+}
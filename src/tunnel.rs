@@ -0,0 +1,301 @@
+//! Detection of SSH port forwards (`ssh -L`, `-R`, `-D`)
+
+use crate::theme::Themed;
+use anyhow::{Context, Result};
+use colored::Colorize;
+use std::process::Command;
+
+/// ssh options that take an argument (from `man ssh`)
+const SSH_OPTIONS_WITH_ARG: &str = "BbcDEeFIiJLlmOopQRSWw";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ForwardKind {
+    /// `-L`: local port forwarded to a remote endpoint
+    Local,
+    /// `-R`: remote port forwarded back to a local endpoint
+    Remote,
+    /// `-D`: local SOCKS proxy
+    Dynamic,
+}
+
+#[derive(Debug, Clone)]
+pub struct Forward {
+    pub kind: ForwardKind,
+    /// Port bound by the forward (local for -L/-D, remote for -R)
+    pub port: u16,
+    /// Destination `host:port` (none for dynamic forwards)
+    pub target: Option<String>,
+    /// SSH destination the forward goes through
+    pub via: String,
+}
+
+impl Forward {
+    /// Human-readable description, e.g. `→ db.internal:5432 via ssh bastion`
+    pub fn describe(&self) -> String {
+        match (&self.kind, &self.target) {
+            (ForwardKind::Dynamic, _) => format!("SOCKS proxy via ssh {}", self.via),
+            (ForwardKind::Remote, Some(target)) => {
+                format!(
+                    "{}:{} {} {} (remote forward)",
+                    self.via,
+                    self.port,
+                    crate::output::glyphs().back_arrow,
+                    target
+                )
+            }
+            (_, target) => format!(
+                "{} {} via ssh {}",
+                crate::output::glyphs().arrow,
+                target.as_deref().unwrap_or("?"),
+                self.via
+            ),
+        }
+    }
+}
+
+/// Whether a process is an ssh client
+pub fn is_ssh(process: &str) -> bool {
+    process == "ssh" || process.ends_with("/ssh")
+}
+
+/// Parse the forwards from an ssh command line
+pub fn parse_forwards(command: &str) -> Vec<Forward> {
+    let mut tokens = command.split_whitespace();
+    if !tokens.next().is_some_and(is_ssh) {
+        return Vec::new();
+    }
+
+    let mut specs = Vec::new();
+    let mut destination = None;
+    while let Some(token) = tokens.next() {
+        let Some(flags) = token.strip_prefix('-') else {
+            // First positional argument is the destination; the rest is a remote command
+            destination = Some(token.to_string());
+            break;
+        };
+        for (i, flag) in flags.char_indices() {
+            if !SSH_OPTIONS_WITH_ARG.contains(flag) {
+                continue;
+            }
+            // The argument is either attached (`-L5433:...`) or the next token
+            let attached = &flags[i + flag.len_utf8()..];
+            let arg = if attached.is_empty() {
+                tokens.next().unwrap_or_default().to_string()
+            } else {
+                attached.to_string()
+            };
+            if matches!(flag, 'L' | 'R' | 'D') {
+                specs.push((flag, arg));
+            }
+            break;
+        }
+    }
+
+    let via = destination.unwrap_or_else(|| "?".to_string());
+    specs
+        .into_iter()
+        .filter_map(|(flag, spec)| parse_spec(flag, &spec, &via))
+        .collect()
+}
+
+/// Split a forward spec into its fields: `:`-separated with IPv6 addresses in
+/// brackets (`[::1]:5433:[fe80::1]:5432`, brackets kept), or `/`-separated
+/// (`5433/fe80::1/5432`)
+fn spec_fields(spec: &str) -> Option<Vec<String>> {
+    if !spec.contains('[') && spec.contains('/') {
+        return Some(spec.split('/').map(str::to_string).collect());
+    }
+    let mut fields = Vec::new();
+    let mut rest = spec;
+    loop {
+        let end = if rest.starts_with('[') {
+            rest.find(']')? + 1
+        } else {
+            rest.find(':').unwrap_or(rest.len())
+        };
+        fields.push(rest[..end].to_string());
+        rest = &rest[end..];
+        match rest.strip_prefix(':') {
+            Some(next) => rest = next,
+            None if rest.is_empty() => return Some(fields),
+            None => return None,
+        }
+    }
+}
+
+/// `host:port`, with an IPv6 host in brackets
+fn endpoint(host: &str, port: &str) -> String {
+    if host.contains(':') && !host.starts_with('[') {
+        format!("[{}]:{}", host, port)
+    } else {
+        format!("{}:{}", host, port)
+    }
+}
+
+/// Parse `[bind_address:]port:host:hostport` (or `[bind_address:]port` for -D)
+fn parse_spec(flag: char, spec: &str, via: &str) -> Option<Forward> {
+    let (port, target) = if flag == 'D' {
+        // The port is last, also after an unbracketed IPv6 address (`::1:1080`)
+        (spec.rsplit(['/', ':']).next()?.to_string(), None)
+    } else {
+        match spec_fields(spec)?.as_slice() {
+            [port, host, host_port] | [_, port, host, host_port] => {
+                (port.clone(), Some(endpoint(host, host_port)))
+            }
+            _ => return None,
+        }
+    };
+    Some(Forward {
+        kind: match flag {
+            'L' => ForwardKind::Local,
+            'R' => ForwardKind::Remote,
+            _ => ForwardKind::Dynamic,
+        },
+        port: port.parse().ok()?,
+        target,
+        via: via.to_string(),
+    })
+}
+
+/// Describe the forward bound to `port` if the command is an ssh tunnel
+pub fn describe_port(port: u16, command: &str) -> Option<String> {
+    parse_forwards(command)
+        .into_iter()
+        .find(|f| f.port == port && f.kind != ForwardKind::Remote)
+        .map(|f| f.describe())
+}
+
+/// List every forward of every running ssh client
+pub fn run_list() -> Result<()> {
+    let output = Command::new("ps")
+        .args(["-A", "-o", "pid=,command="])
+        .output()
+        .context("Failed to execute ps command")?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let bound: Vec<u16> = crate::get_port_info()?.iter().map(|i| i.port).collect();
+
+    let mut count = 0;
+    for line in stdout.lines() {
+        let Some((pid, command)) = line.trim().split_once(char::is_whitespace) else {
+            continue;
+        };
+        for forward in parse_forwards(command.trim()) {
+            let status = match forward.kind {
+                ForwardKind::Remote => "remote".muted(),
+                _ if bound.contains(&forward.port) => "up".success(),
+                _ => "not bound".warning(),
+            };
+            println!(
+                "{} {}  {} {}",
+                format!(":{:<5}", forward.port).accent().bold(),
+                forward.describe(),
+                crate::format_pid(pid).muted(),
+                status
+            );
+            count += 1;
+        }
+    }
+
+    if count == 0 {
+        println!("{}", "No SSH tunnels found".warning());
+    }
+    Ok(())
+}
+
+fn entry_name(port: u16) -> String {
+    format!("tunnel-{}", port)
+}
+
+/// Start a supervised `ssh -L` forward from local `port` to `target` through `via`
+pub fn add(port: u16, target: &str, via: &str) -> Result<()> {
+    anyhow::ensure!(
+        target
+            .rsplit_once(':')
+            .is_some_and(|(_, p)| p.parse::<u16>().is_ok()),
+        "Target must be host:port (got {})",
+        target
+    );
+
+    let command = vec![
+        "ssh".to_string(),
+        "-N".to_string(),
+        "-o".to_string(),
+        "ExitOnForwardFailure=yes".to_string(),
+        "-o".to_string(),
+        "ServerAliveInterval=15".to_string(),
+        "-L".to_string(),
+        format!("{}:{}", port, target),
+        via.to_string(),
+    ];
+    let entry = crate::supervisor::Entry::new(entry_name(port), "tunnel", Some(port), command);
+    let entry = crate::supervisor::start(entry)?;
+
+    println!(
+        "{} Tunnel {} {} {} via ssh {} started {}",
+        crate::output::glyphs().check,
+        format!(":{}", port).accent().bold(),
+        crate::output::glyphs().arrow,
+        target,
+        via,
+        crate::format_pid(&entry.pid.unwrap_or_default().to_string()).muted()
+    );
+    println!(
+        "Output is logged to {}",
+        crate::supervisor::log_path(&entry.name)?.display()
+    );
+    Ok(())
+}
+
+/// Stop a supervised tunnel
+pub fn rm(port: u16) -> Result<()> {
+    crate::supervisor::stop(&entry_name(port))?;
+    println!(
+        "{} Tunnel on port {} stopped",
+        crate::output::glyphs().check,
+        port
+    );
+    Ok(())
+}
+
+/// Print the `tunnels` section of the listing for managed tunnels
+pub fn print_managed_section(port_infos: &[crate::PortInfo], header: bool) -> Result<()> {
+    let tunnels: Vec<_> = crate::supervisor::load()?
+        .into_iter()
+        .filter(|e| e.kind == "tunnel")
+        .collect();
+    if tunnels.is_empty() {
+        return Ok(());
+    }
+
+    if header {
+        println!();
+        println!("{}", "tunnels".heading().bold());
+    }
+    for entry in &tunnels {
+        let port = entry.port.unwrap_or_default();
+        let status = if !entry.is_running() {
+            "stopped".error()
+        } else if port_infos.iter().any(|i| i.port == port) {
+            "up".success()
+        } else {
+            "connecting".warning()
+        };
+        let description = parse_forwards(&entry.command.join(" "))
+            .first()
+            .map(|f| f.describe())
+            .unwrap_or_default();
+        let restarts = if entry.restarts > 0 {
+            format!(" (restarts: {})", entry.restarts)
+        } else {
+            String::new()
+        };
+        println!(
+            "  {} {}  {}{}",
+            format!(":{:<5}", port).accent().bold(),
+            description.label(),
+            status,
+            restarts.muted()
+        );
+    }
+    Ok(())
+}
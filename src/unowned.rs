@@ -0,0 +1,91 @@
+//! Listening sockets whose owner lsof can't show
+//!
+//! Without root, lsof only reports the user's own processes, while `ss`/`netstat`
+//! still list every listening socket. These are shown as rows marked
+//! `permission denied` instead of being dropped silently.
+
+use crate::theme::Themed;
+use colored::Colorize;
+use std::process::Command;
+
+#[derive(Debug, Clone)]
+pub struct Unowned {
+    pub port: u16,
+    /// Local address as printed by ss/netstat
+    pub local: String,
+}
+
+pub fn is_root() -> bool {
+    Command::new("id")
+        .arg("-u")
+        .output()
+        .map(|o| String::from_utf8_lossy(&o.stdout).trim() == "0")
+        .unwrap_or(false)
+}
+
+/// Listening sockets on ports that no listener in `port_infos` accounts for
+pub fn collect(port_infos: &[crate::PortInfo]) -> Vec<Unowned> {
+    let mut unowned: Vec<Unowned> = crate::explain::listen_queues()
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|q| {
+            !port_infos
+                .iter()
+                .any(|i| i.port == q.port && i.protocol == crate::collector::Protocol::Tcp)
+        })
+        .map(|q| Unowned {
+            port: q.port,
+            local: q.local,
+        })
+        .collect();
+    unowned.sort_by(|a, b| a.port.cmp(&b.port).then(a.local.cmp(&b.local)));
+    unowned.dedup_by(|a, b| a.port == b.port && a.local == b.local);
+    unowned
+}
+
+/// Rows for sockets without a visible owner; as root they are held by the kernel or a zombie
+pub fn print_section(unowned: &[Unowned], compact: bool, header: bool) {
+    if unowned.is_empty() {
+        return;
+    }
+    let root = is_root();
+    if header {
+        let title = if root {
+            "no_owner"
+        } else {
+            "permission_denied"
+        };
+        println!("{}", title.heading().bold());
+    }
+    let marker = if root {
+        "no owner (see explain)"
+    } else {
+        "permission denied"
+    };
+    for socket in unowned {
+        let port = format!(":{:<5}", socket.port);
+        if compact {
+            println!("  {} {}", port.accent().bold(), marker.warning());
+        } else {
+            println!(
+                "  {} {:<22} {}",
+                port.accent().bold(),
+                socket.local,
+                marker.warning()
+            );
+        }
+    }
+    if !root {
+        println!(
+            "{}",
+            format!(
+                "{} listener(s) belong to other users; run with sudo to see their processes",
+                unowned.len()
+            )
+            .muted()
+        );
+    }
+    if header {
+        println!();
+    }
+}
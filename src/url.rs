@@ -0,0 +1,100 @@
+//! URLs other devices can use to reach a listening port
+
+use crate::interfaces::{self, Exposure, Interface};
+use crate::theme::Themed;
+use anyhow::{Context, Result};
+use colored::Colorize;
+use std::net::IpAddr;
+use std::process::Command;
+
+/// Print the URLs for `port`, optionally with a terminal QR code for the first LAN URL
+pub fn run(port: u16, qr: bool) -> Result<()> {
+    let listeners = crate::tcp_listeners(port)?;
+    anyhow::ensure!(
+        !listeners.is_empty(),
+        "No process is listening on port {}",
+        port
+    );
+
+    let interfaces = interfaces::list();
+    let mut local = Vec::new();
+    let mut vpn = Vec::new();
+    let mut lan = Vec::new();
+    for info in &listeners {
+        for ip in reachable_addresses(&info.address, &info.family, &interfaces) {
+            let url = format_url(ip, port);
+            let target = match interfaces::exposure(&ip.to_string(), &interfaces) {
+                Exposure::Local => &mut local,
+                Exposure::Vpn => &mut vpn,
+                Exposure::Lan => &mut lan,
+            };
+            if !target.contains(&url) {
+                target.push(url);
+            }
+        }
+    }
+
+    for url in &local {
+        println!("{}  {}", "local".muted(), url);
+    }
+    for url in &lan {
+        println!("{}    {}", "lan".warning(), url.bold());
+    }
+    for url in &vpn {
+        println!("{}    {}", "vpn".label(), url.bold());
+    }
+    if lan.is_empty() && vpn.is_empty() {
+        println!(
+            "{}",
+            "Only reachable from this machine; bind to 0.0.0.0 to allow LAN access".warning()
+        );
+    }
+
+    if qr {
+        let url = lan.first().context("No LAN URL to encode as a QR code")?;
+        print_qr(url)?;
+    }
+    Ok(())
+}
+
+/// Addresses a listener accepts connections on
+fn reachable_addresses(address: &str, family: &str, interfaces: &[Interface]) -> Vec<IpAddr> {
+    if !interfaces::is_wildcard(address) {
+        return interfaces::parse_ip(address).into_iter().collect();
+    }
+
+    // Wildcard: every interface address of the listener's family. IPv6 wildcard
+    // sockets are dual-stack by default, so they accept IPv4 as well.
+    let dual_stack = family == "IPv6";
+    interfaces
+        .iter()
+        .map(|i| i.addr)
+        .filter(|ip| (dual_stack || ip.is_ipv4()) && !is_link_local(ip))
+        .collect()
+}
+
+/// Link-local addresses need a zone id and rarely work in browsers
+fn is_link_local(ip: &IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(v4) => v4.is_link_local(),
+        IpAddr::V6(v6) => (v6.segments()[0] & 0xffc0) == 0xfe80,
+    }
+}
+
+fn format_url(ip: IpAddr, port: u16) -> String {
+    match ip {
+        IpAddr::V4(v4) if v4.is_loopback() => format!("http://localhost:{}", port),
+        IpAddr::V4(v4) => format!("http://{}:{}", v4, port),
+        IpAddr::V6(v6) => format!("http://[{}]:{}", v6, port),
+    }
+}
+
+/// Render a QR code with `qrencode` if it is installed
+fn print_qr(url: &str) -> Result<()> {
+    let status = Command::new("qrencode")
+        .args(["-t", "ANSIUTF8", url])
+        .status()
+        .context("Failed to run qrencode (install it with `brew install qrencode` or your package manager)")?;
+    anyhow::ensure!(status.success(), "qrencode failed");
+    Ok(())
+}
@@ -0,0 +1,312 @@
+//! `verify`: check the ports a project's files claim against its `ports.toml`
+//!
+//! `ports.toml` at the root of the repository holds the ranges allocated to the
+//! project, as `[[ports]]` entries like those of the config. Compose files publish
+//! host ports and devcontainer.json forwards them; every tracked one of these files is
+//! read, and a port outside all ranges fails the command (see `hook install`).
+
+use crate::theme::Themed;
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// `ports.toml`
+#[derive(Debug, Deserialize)]
+struct PortsFile {
+    #[serde(default)]
+    ports: Vec<crate::PortEntry>,
+}
+
+/// A port a file asks for
+struct Claim {
+    file: String,
+    /// 1-based, when the format is read line by line
+    line: Option<usize>,
+    port: u16,
+}
+
+/// The repository the current directory is in, else the current directory
+pub fn project_root() -> Result<PathBuf> {
+    let output = Command::new("git")
+        .args(["rev-parse", "--show-toplevel"])
+        .output();
+    match output {
+        Ok(output) if output.status.success() => Ok(PathBuf::from(
+            String::from_utf8_lossy(&output.stdout).trim_end(),
+        )),
+        _ => std::env::current_dir().context("Failed to read the current directory"),
+    }
+}
+
+/// Files tracked by git under `root` (relative paths), or `None` outside a repository
+fn tracked_files(root: &Path) -> Option<Vec<String>> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(root)
+        .args(["ls-files", "-z"])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())?;
+    Some(
+        String::from_utf8_lossy(&output.stdout)
+            .split('\0')
+            .filter(|path| !path.is_empty())
+            .map(str::to_string)
+            .collect(),
+    )
+}
+
+/// `compose.yaml`, `docker-compose.override.yml`, ...
+fn is_compose(path: &str) -> bool {
+    let name = path.rsplit('/').next().unwrap_or(path);
+    let name = name.strip_prefix("docker-").unwrap_or(name);
+    name.starts_with("compose") && (name.ends_with(".yml") || name.ends_with(".yaml"))
+}
+
+/// `.devcontainer/devcontainer.json`, `.devcontainer.json`, ...
+pub fn is_devcontainer(path: &str) -> bool {
+    let name = path.rsplit('/').next().unwrap_or(path);
+    name == "devcontainer.json" || name == ".devcontainer.json"
+}
+
+/// The files `verify` reads: tracked ones in a repository, else those in `root` itself
+pub fn project_files(root: &Path, wanted: impl Fn(&str) -> bool) -> Vec<String> {
+    match tracked_files(root) {
+        Some(files) => files.into_iter().filter(|path| wanted(path)).collect(),
+        None => std::fs::read_dir(root)
+            .into_iter()
+            .flatten()
+            .flatten()
+            .map(|entry| entry.file_name().to_string_lossy().into_owned())
+            .chain([".devcontainer/devcontainer.json".to_string()])
+            .filter(|path| wanted(path) && root.join(path).is_file())
+            .collect(),
+    }
+}
+
+/// Host ports of a short-syntax entry: `"3000:3000"`, `127.0.0.1:5432:5432/tcp`,
+/// `3000-3005:3000-3005`. A bare container port publishes a random host port.
+fn short_syntax_ports(entry: &str) -> Vec<u16> {
+    let entry = entry.trim_matches(|c| c == '"' || c == '\'');
+    let entry = entry.split('/').next().unwrap_or(entry);
+    let mut parts = entry.rsplit(':');
+    let (Some(_container), Some(host)) = (parts.next(), parts.next()) else {
+        return Vec::new();
+    };
+    crate::expand_ports(host).unwrap_or_default()
+}
+
+/// Host ports published by a compose file, with their line numbers. This reads the
+/// `ports:` lists line by line rather than parsing YAML: the short syntax (`- "3000:3000"`,
+/// also in `[...]` flow lists) and `published:` of the long one.
+fn compose_ports(text: &str) -> Vec<(usize, u16)> {
+    let mut claims = Vec::new();
+    // Indentation of the `ports:` key whose list is being read
+    let mut list: Option<usize> = None;
+    for (number, line) in text.lines().enumerate() {
+        let trimmed = line.trim();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+        let indent = line.len() - line.trim_start().len();
+        // Items may be indented like their key
+        if list.is_some_and(|key| indent < key || (indent == key && !trimmed.starts_with('-'))) {
+            list = None;
+        }
+
+        let mut add = |ports: Vec<u16>| claims.extend(ports.into_iter().map(|p| (number + 1, p)));
+        if let Some(value) = trimmed.strip_prefix("ports:") {
+            let value = value.trim();
+            match value.strip_prefix('[').and_then(|v| v.strip_suffix(']')) {
+                Some(items) => items
+                    .split(',')
+                    .for_each(|item| add(short_syntax_ports(item.trim()))),
+                None if value.is_empty() => list = Some(indent),
+                None => {}
+            }
+            continue;
+        }
+        if list.is_none() {
+            continue;
+        }
+        let item = trimmed.strip_prefix('-').map_or(trimmed, str::trim);
+        if let Some(published) = item.strip_prefix("published:") {
+            let published = published.trim().trim_matches(|c| c == '"' || c == '\'');
+            add(crate::expand_ports(published).unwrap_or_default());
+        } else if trimmed.starts_with('-') && !item.contains(": ") && !item.ends_with(':') {
+            add(short_syntax_ports(item));
+        }
+    }
+    claims
+}
+
+/// JSON with comments and trailing commas (devcontainer.json, VS Code settings) as JSON
+pub fn strip_jsonc(text: &str) -> String {
+    let mut json = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    let mut in_string = false;
+    while let Some(c) = chars.next() {
+        if in_string {
+            json.push(c);
+            match c {
+                '\\' => json.extend(chars.next()),
+                '"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+        match (c, chars.peek()) {
+            ('/', Some('/')) => while chars.next_if(|c| *c != '\n').is_some() {},
+            ('/', Some('*')) => {
+                chars.next();
+                let mut previous = ' ';
+                for c in chars.by_ref() {
+                    if previous == '*' && c == '/' {
+                        break;
+                    }
+                    previous = c;
+                }
+            }
+            _ => {
+                in_string = c == '"';
+                json.push(c);
+            }
+        }
+    }
+    without_trailing_commas(&json)
+}
+
+/// `[1, 2,]` → `[1, 2]`, outside of strings
+fn without_trailing_commas(json: &str) -> String {
+    let mut result = String::with_capacity(json.len());
+    let mut in_string = false;
+    let mut escaped = false;
+    for (i, c) in json.char_indices() {
+        if in_string {
+            in_string = escaped || c != '"';
+            escaped = !escaped && c == '\\';
+        } else if c == '"' {
+            in_string = true;
+        } else if c == ',' && json[i + 1..].trim_start().starts_with(['}', ']']) {
+            continue;
+        }
+        result.push(c);
+    }
+    result
+}
+
+/// A port given as a number or inside a string, e.g. `"db:5432"` (`last` part) or
+/// `"8000:8010"` (first part)
+fn port_value(item: &serde_json::Value, last: bool) -> Option<u16> {
+    match item {
+        serde_json::Value::Number(n) => n.as_u64().and_then(|p| u16::try_from(p).ok()),
+        serde_json::Value::String(s) => {
+            let part = if last {
+                s.rsplit(':').next()
+            } else {
+                s.split(':').next()
+            };
+            part.and_then(|p| p.parse().ok())
+        }
+        _ => None,
+    }
+}
+
+/// Local ports of `forwardPorts` (`3000`, `"db:5432"`) and `appPort` (`3000`,
+/// `"8000:8010"`, or a list of them)
+pub fn devcontainer_ports(text: &str) -> Result<Vec<u16>> {
+    let json: serde_json::Value =
+        serde_json::from_str(&strip_jsonc(text)).context("Invalid devcontainer.json")?;
+    let items = |key: &str| match json.get(key) {
+        Some(serde_json::Value::Array(items)) => items.clone(),
+        Some(item) => vec![item.clone()],
+        None => Vec::new(),
+    };
+    let forwarded = items("forwardPorts")
+        .into_iter()
+        .filter_map(|i| port_value(&i, true));
+    let app = items("appPort")
+        .into_iter()
+        .filter_map(|i| port_value(&i, false));
+    Ok(forwarded.chain(app).collect())
+}
+
+pub fn run() -> Result<()> {
+    let root = project_root()?;
+    let path = root.join("ports.toml");
+    let content = std::fs::read_to_string(&path)
+        .with_context(|| format!("No ports.toml in {} to verify against", root.display()))?;
+    let allocated: PortsFile =
+        toml::from_str(&content).with_context(|| format!("Invalid {}", path.display()))?;
+    anyhow::ensure!(
+        !allocated.ports.is_empty(),
+        "{} has no [[ports]] ranges",
+        path.display()
+    );
+    let mut ranges = Vec::new();
+    for entry in &allocated.ports {
+        ranges.push((entry, entry.expand()?));
+    }
+
+    let files = project_files(&root, |path| is_compose(path) || is_devcontainer(path));
+    let mut claims = Vec::new();
+    for file in &files {
+        let text = std::fs::read_to_string(root.join(file))
+            .with_context(|| format!("Failed to read {}", file))?;
+        if is_devcontainer(file) {
+            let ports = devcontainer_ports(&text).with_context(|| format!("In {}", file))?;
+            claims.extend(ports.into_iter().map(|port| Claim {
+                file: file.clone(),
+                line: None,
+                port,
+            }));
+        } else {
+            claims.extend(compose_ports(&text).into_iter().map(|(line, port)| Claim {
+                file: file.clone(),
+                line: Some(line),
+                port,
+            }));
+        }
+    }
+
+    let outside: Vec<&Claim> = claims
+        .iter()
+        .filter(|claim| !ranges.iter().any(|(_, ports)| ports.contains(&claim.port)))
+        .collect();
+    if outside.is_empty() {
+        println!(
+            "{} {} port(s) in {} file(s) within the ranges of ports.toml",
+            crate::output::glyphs().check.success(),
+            claims.len(),
+            files.len()
+        );
+        return Ok(());
+    }
+
+    let allowed: Vec<String> = ranges
+        .iter()
+        .map(|(entry, _)| match &entry.name {
+            Some(name) => format!("{} {}", name, entry.ports),
+            None => entry.ports.clone(),
+        })
+        .collect();
+    for claim in &outside {
+        let location = match claim.line {
+            Some(line) => format!("{}:{}", claim.file, line),
+            None => claim.file.clone(),
+        };
+        println!(
+            "{} {} claims port {}, outside the allocated ranges",
+            crate::output::glyphs().warn.warning(),
+            location,
+            claim.port.to_string().accent()
+        );
+    }
+    println!("{}", format!("  allocated: {}", allowed.join(", ")).muted());
+    anyhow::bail!(
+        "{} port(s) outside the ranges of {}",
+        outside.len(),
+        path.display()
+    )
+}
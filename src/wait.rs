@@ -0,0 +1,97 @@
+//! `wait`: block until a port is free or listening, for chaining dev servers in scripts
+
+use crate::theme::Themed;
+use anyhow::Result;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, TcpStream};
+use std::time::{Duration, Instant};
+
+const POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+/// Listeners on the port, and whether something holds it at all (including
+/// processes the collector can't see). While waiting for a listener, that is a connect
+/// to loopback: a test bind could take the port from the server being waited for.
+fn state(port: u16, listen: bool) -> Result<(Vec<crate::PortInfo>, bool)> {
+    let listeners = crate::tcp_listeners(port)?;
+    let in_use = !listeners.is_empty()
+        || match listen {
+            true => accepts(port),
+            false => crate::check::bind_in_use(port),
+        };
+    Ok((listeners, in_use))
+}
+
+/// Whether a connect to the port on loopback (IPv4 or IPv6) completes
+fn accepts(port: u16) -> bool {
+    [
+        IpAddr::V4(Ipv4Addr::LOCALHOST),
+        IpAddr::V6(Ipv6Addr::LOCALHOST),
+    ]
+    .into_iter()
+    .any(|ip| TcpStream::connect_timeout(&SocketAddr::new(ip, port), POLL_INTERVAL).is_ok())
+}
+
+fn holders(listeners: &[crate::PortInfo]) -> String {
+    let mut holders: Vec<String> = listeners
+        .iter()
+        .map(|i| format!("{} {}", i.process, crate::format_pid(&i.pid)))
+        .collect();
+    holders.dedup();
+    holders.join(", ")
+}
+
+/// Poll until the port is free (`listen` false) or listening, or `timeout` elapses
+pub fn run(port: u16, listen: bool, timeout: Option<Duration>) -> Result<()> {
+    let start = Instant::now();
+    let mut announced = false;
+    loop {
+        let (listeners, in_use) = state(port, listen)?;
+        if in_use == listen {
+            let elapsed = if announced {
+                format!(" after {:.1}s", start.elapsed().as_secs_f32())
+            } else {
+                String::new()
+            };
+            let status = if !listen {
+                "free".to_string()
+            } else if listeners.is_empty() {
+                "listening".to_string()
+            } else {
+                format!("listening ({})", holders(&listeners))
+            };
+            println!(
+                "{} Port {} is {}{}",
+                crate::output::glyphs().check.success(),
+                port.to_string().accent(),
+                status,
+                elapsed.muted()
+            );
+            return Ok(());
+        }
+
+        if timeout.is_some_and(|timeout| start.elapsed() >= timeout) {
+            let timeout = timeout.unwrap_or_default().as_secs_f32();
+            match listen {
+                true => anyhow::bail!("Nothing listening on port {} after {}s", port, timeout),
+                false if listeners.is_empty() => {
+                    anyhow::bail!("Port {} still in use after {}s", port, timeout)
+                }
+                false => anyhow::bail!(
+                    "Port {} still in use after {}s by {}",
+                    port,
+                    timeout,
+                    holders(&listeners)
+                ),
+            }
+        }
+        // On stderr, so scripts capturing the output only get the result
+        if !announced {
+            let condition = if listen { "listen" } else { "be free" };
+            eprintln!(
+                "{}",
+                format!("Waiting for port {} to {}...", port, condition).muted()
+            );
+            announced = true;
+        }
+        std::thread::sleep(POLL_INTERVAL);
+    }
+}
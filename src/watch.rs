@@ -0,0 +1,140 @@
+//! `--watch`: redraw the listing every `--interval` seconds
+//!
+//! Unlike `watch -n2 lsof-work-ports`, the output keeps its colors, and ports that
+//! started or stopped listening stay pointed out for a few refreshes. Each refresh
+//! only enriches the sockets that are new since the previous one, and monitored ports
+//! with a health check get an up/degraded/down column. `--output-file` appends the
+//! listen/close events to a JSON-lines file, like `daemon --output-file`.
+
+use crate::theme::Themed;
+use anyhow::Result;
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Refreshes a change stays on screen
+const KEEP_REFRESHES: u32 = 5;
+
+struct Change {
+    port: u16,
+    process: Arc<str>,
+    appeared: bool,
+    refreshes_left: u32,
+}
+
+/// Listening ports with the name of their first process
+fn snapshot(port_infos: &[crate::PortInfo]) -> BTreeMap<u16, Arc<str>> {
+    let mut ports = BTreeMap::new();
+    for info in port_infos {
+        ports
+            .entry(info.port)
+            .or_insert_with(|| info.process.clone());
+    }
+    ports
+}
+
+/// Forget old changes and add the ones between two snapshots, latest first
+fn update_changes(
+    changes: &mut Vec<Change>,
+    previous: &BTreeMap<u16, Arc<str>>,
+    current: &BTreeMap<u16, Arc<str>>,
+) {
+    for change in changes.iter_mut() {
+        change.refreshes_left -= 1;
+    }
+    changes.retain(|c| c.refreshes_left > 0);
+
+    let appeared = current
+        .iter()
+        .filter(|(port, _)| !previous.contains_key(port))
+        .map(|(port, process)| (*port, process, true));
+    let gone = previous
+        .iter()
+        .filter(|(port, _)| !current.contains_key(port))
+        .map(|(port, process)| (*port, process, false));
+    let new: Vec<Change> = appeared
+        .chain(gone)
+        .map(|(port, process, appeared)| Change {
+            port,
+            process: process.clone(),
+            appeared,
+            refreshes_left: KEEP_REFRESHES,
+        })
+        .collect();
+    // A port that came back (or went away again) only keeps its latest change
+    changes.retain(|c| !new.iter().any(|n| n.port == c.port));
+    changes.splice(0..0, new);
+}
+
+fn print_changes(changes: &[Change]) {
+    if changes.is_empty() {
+        return;
+    }
+    let glyphs = crate::output::glyphs();
+    println!();
+    for change in changes {
+        let port = format!(":{}", change.port);
+        if change.appeared {
+            println!(
+                "{} {} {}",
+                glyphs.up.success(),
+                port.accent(),
+                change.process.success()
+            );
+        } else {
+            println!(
+                "{} {} {}",
+                glyphs.down.error(),
+                port.muted(),
+                change.process.muted()
+            );
+        }
+    }
+}
+
+/// Collect and `render` the ports until interrupted
+pub fn run(
+    interval: Duration,
+    output_file: Option<crate::events::Sink>,
+    mut render: impl FnMut(Vec<crate::PortInfo>) -> Result<()>,
+) -> Result<()> {
+    let mut snapshots = crate::snapshot::Snapshots::default();
+    let mut tracker = crate::events::Tracker::default();
+    let mut previous: Option<BTreeMap<u16, Arc<str>>> = None;
+    let mut changes: Vec<Change> = Vec::new();
+    loop {
+        let port_infos = snapshots.next()?;
+        if let Some(sink) = &output_file {
+            sink.append(&tracker.diff(&port_infos))?;
+        }
+        let current = snapshot(&port_infos);
+        if let Some(previous) = &previous {
+            update_changes(&mut changes, previous, &current);
+        }
+        crate::highlight::set_appeared(
+            changes
+                .iter()
+                .filter(|c| c.appeared)
+                .map(|c| c.port)
+                .collect(),
+        );
+
+        print!("{}", crate::output::CLEAR_SCREEN);
+        println!(
+            "{}",
+            format!(
+                "Every {}s {} Ctrl-C to quit",
+                interval.as_secs_f32(),
+                crate::output::glyphs().sep
+            )
+            .muted()
+        );
+        render(port_infos)?;
+        print_changes(&changes);
+        std::io::stdout().flush()?;
+
+        previous = Some(current);
+        std::thread::sleep(interval);
+    }
+}
@@ -0,0 +1,104 @@
+//! Ports conventionally owned by system services and popular infrastructure
+
+pub struct WellKnown {
+    pub port: u16,
+    pub service: &'static str,
+    /// Executables of the service; if one is installed it may want the port back
+    pub binaries: &'static [&'static str],
+}
+
+const fn entry(port: u16, service: &'static str, binaries: &'static [&'static str]) -> WellKnown {
+    WellKnown {
+        port,
+        service,
+        binaries,
+    }
+}
+
+pub const PORTS: &[WellKnown] = &[
+    entry(22, "ssh", &["sshd"]),
+    entry(53, "DNS", &["dnsmasq", "named", "unbound"]),
+    entry(80, "HTTP", &["nginx", "httpd", "apache2", "caddy"]),
+    entry(443, "HTTPS", &["nginx", "httpd", "apache2", "caddy"]),
+    entry(631, "CUPS printing", &["cupsd"]),
+    entry(1433, "SQL Server", &["sqlservr"]),
+    entry(1521, "Oracle", &["tnslsnr"]),
+    entry(2181, "ZooKeeper", &["zkServer.sh"]),
+    entry(3306, "MySQL", &["mysqld", "mariadbd"]),
+    entry(5000, "macOS AirPlay Receiver", &[]),
+    entry(5353, "mDNS", &["avahi-daemon", "mDNSResponder"]),
+    entry(5432, "PostgreSQL", &["postgres"]),
+    entry(5672, "RabbitMQ", &["rabbitmq-server"]),
+    entry(6379, "Redis", &["redis-server"]),
+    entry(7000, "macOS AirPlay Receiver", &[]),
+    entry(8086, "InfluxDB", &["influxd"]),
+    entry(
+        9092,
+        "Kafka",
+        &["kafka-server-start", "kafka-server-start.sh"],
+    ),
+    entry(9200, "Elasticsearch", &["elasticsearch"]),
+    entry(11211, "Memcached", &["memcached"]),
+    entry(15672, "RabbitMQ management", &["rabbitmq-server"]),
+    entry(27017, "MongoDB", &["mongod"]),
+];
+
+/// Directories searched for service executables besides `$PATH` (daemons often live in sbin)
+const EXTRA_DIRS: &[&str] = &[
+    "/usr/sbin",
+    "/usr/local/sbin",
+    "/usr/local/bin",
+    "/opt/homebrew/bin",
+    "/opt/homebrew/sbin",
+];
+
+/// Service conventionally using `port`
+pub fn service(port: u16) -> Option<&'static str> {
+    lookup(port).map(|w| w.service)
+}
+
+pub fn lookup(port: u16) -> Option<&'static WellKnown> {
+    PORTS.iter().find(|w| w.port == port)
+}
+
+/// First installed executable of a service
+fn installed_binary(well_known: &WellKnown) -> Option<&'static str> {
+    let path = std::env::var_os("PATH").unwrap_or_default();
+    let dirs: Vec<std::path::PathBuf> = std::env::split_paths(&path)
+        .chain(EXTRA_DIRS.iter().map(std::path::PathBuf::from))
+        .collect();
+    well_known
+        .binaries
+        .iter()
+        .copied()
+        .find(|binary| dirs.iter().any(|dir| dir.join(binary).is_file()))
+}
+
+/// Warning for a dev process holding the port of a service that is installed but not
+/// running (it would fail to start, or start on another address and shadow the port)
+pub fn conflict_warning(port: u16, process: &str) -> Option<String> {
+    let well_known = lookup(port)?;
+    let binary = installed_binary(well_known)?;
+    if process.eq_ignore_ascii_case(binary) {
+        return None;
+    }
+    Some(format!(
+        "{} :{} is the {} port and {} is installed; it can't start while {} holds the port",
+        crate::output::glyphs().warn,
+        port,
+        well_known.service,
+        binary,
+        process
+    ))
+}
+
+/// macOS 12+ runs the AirPlay Receiver in ControlCenter, listening on 5000 and 7000
+/// (lsof truncates the process name to `ControlCe`)
+pub fn is_airplay_receiver(port: u16, process: &str) -> bool {
+    matches!(port, 5000 | 7000) && process.starts_with("ControlCe")
+}
+
+/// How to free a port held by the AirPlay Receiver
+pub const AIRPLAY_FIX: &str =
+    "System Settings > General > AirDrop & Handoff → turn off \"AirPlay Receiver\" \
+(System Preferences > Sharing on macOS 12), or run your server on another port";
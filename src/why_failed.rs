@@ -0,0 +1,95 @@
+//! `why-failed <port>`: find recent bind failures for a port in the system log
+//! and show who holds the port now
+//!
+//! Linux reads journald, macOS the unified log. Only messages that mention the
+//! port number are shown, so unrelated EADDRINUSE errors are skipped.
+
+use crate::theme::Themed;
+use anyhow::{Context, Result};
+use colored::Colorize;
+use std::process::Command;
+
+/// Phrases that runtimes use when bind() fails with EADDRINUSE
+const BIND_ERRORS: &[&str] = &[
+    "EADDRINUSE",
+    "address already in use",
+    "Address already in use",
+    "address in use",
+    "port is already allocated",
+];
+
+/// Whether `line` contains `port` as a whole number (not part of 13000 or 30001)
+fn mentions_port(line: &str, port: u16) -> bool {
+    let port = port.to_string();
+    line.match_indices(&port).any(|(start, _)| {
+        let before = line[..start].chars().next_back();
+        let after = line[start + port.len()..].chars().next();
+        !before.is_some_and(|c| c.is_ascii_digit()) && !after.is_some_and(|c| c.is_ascii_digit())
+    })
+}
+
+/// Log lines with bind errors from the last `since` (journald/`log show` syntax, e.g. `1h`)
+fn bind_errors(since: &str) -> Result<String> {
+    let output = if cfg!(target_os = "macos") {
+        let predicate = BIND_ERRORS
+            .iter()
+            .map(|e| format!("eventMessage CONTAINS[c] \"{}\"", e))
+            .collect::<Vec<_>>()
+            .join(" OR ");
+        Command::new("log")
+            .args(["show", "--style", "compact", "--last", since, "--predicate"])
+            .arg(predicate)
+            .output()
+            .context("Failed to run `log show`")?
+    } else {
+        let since = format!("-{}", since);
+        Command::new("journalctl")
+            .args(["--no-pager", "-o", "short-iso", "--since", &since, "--grep"])
+            .arg(BIND_ERRORS.join("|"))
+            .output()
+            .context("Failed to run journalctl")?
+    };
+    // journalctl exits with 1 when nothing matches
+    anyhow::ensure!(
+        output.status.success() || output.stderr.is_empty(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr).trim()
+    );
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+pub fn run(port: u16, since: &str) -> Result<()> {
+    let log = bind_errors(since)?;
+    let failures: Vec<&str> = log
+        .lines()
+        .filter(|line| BIND_ERRORS.iter().any(|e| line.contains(e)))
+        .filter(|line| mentions_port(line, port))
+        .collect();
+
+    println!("{}", format!("Port {}", port).accent().bold());
+    if failures.is_empty() {
+        println!("  No bind failures logged in the last {}", since);
+    } else {
+        println!("  Bind failures ({}):", failures.len());
+        for line in &failures {
+            println!("    {}", line.trim().warning());
+        }
+    }
+
+    let holders = crate::filter_port_infos(crate::get_port_info()?, Some(port), None, None);
+    if holders.is_empty() {
+        println!("  Nothing is listening now");
+    } else {
+        println!("  Held by:");
+        for info in &holders {
+            println!(
+                "    {} {}  started {}",
+                info.process.success(),
+                crate::format_pid(&info.pid).muted(),
+                info.start_time
+            );
+            println!("      {}", info.command.muted());
+        }
+    }
+    Ok(())
+}